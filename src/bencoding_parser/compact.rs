@@ -0,0 +1,109 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+use super::BencodingError;
+
+/// 4-byte IPv4 address followed by a 2-byte big-endian port.
+pub(crate) const PEER_V4_LEN: usize = 6;
+/// 20-byte node id followed by a compact IPv4 address.
+pub(crate) const NODE_V4_LEN: usize = 20 + PEER_V4_LEN;
+/// 16-byte IPv6 address followed by a 2-byte big-endian port.
+pub(crate) const PEER_V6_LEN: usize = 18;
+/// 20-byte node id followed by a compact IPv6 address.
+pub(crate) const NODE_V6_LEN: usize = 20 + PEER_V6_LEN;
+
+/// Splits `data` into `entry_size`-byte chunks, failing if there's a partial
+/// entry at the end.
+pub(crate) fn entries(data: &[u8], entry_size: usize) -> Result<Vec<&[u8]>, BencodingError> {
+    if !data.len().is_multiple_of(entry_size) {
+        return Err(BencodingError::InvalidCompactLength {
+            length: data.len(),
+            entry_size,
+        });
+    }
+
+    return Ok(data.chunks_exact(entry_size).collect());
+}
+
+pub(crate) fn peer_v4(entry: &[u8]) -> SocketAddrV4 {
+    let ip = Ipv4Addr::new(entry[0], entry[1], entry[2], entry[3]);
+    let port = u16::from_be_bytes([entry[4], entry[5]]);
+
+    return SocketAddrV4::new(ip, port);
+}
+
+pub(crate) fn peer_v6(entry: &[u8]) -> SocketAddrV6 {
+    let mut octets = [0; 16];
+    octets.copy_from_slice(&entry[..16]);
+    let port = u16::from_be_bytes([entry[16], entry[17]]);
+
+    return SocketAddrV6::new(Ipv6Addr::from(octets), port, 0, 0);
+}
+
+pub(crate) fn peers_v4(data: &[u8]) -> Result<Vec<SocketAddrV4>, BencodingError> {
+    let peers = entries(data, PEER_V4_LEN)?
+        .into_iter()
+        .map(peer_v4)
+        .collect();
+
+    return Ok(peers);
+}
+
+pub(crate) fn peers_v6(data: &[u8]) -> Result<Vec<SocketAddrV6>, BencodingError> {
+    let peers = entries(data, PEER_V6_LEN)?
+        .into_iter()
+        .map(peer_v6)
+        .collect();
+
+    return Ok(peers);
+}
+
+pub(crate) fn nodes_v4(data: &[u8]) -> Result<Vec<(Vec<u8>, SocketAddrV4)>, BencodingError> {
+    let nodes = entries(data, NODE_V4_LEN)?
+        .into_iter()
+        .map(|entry| (entry[..20].to_vec(), peer_v4(&entry[20..])))
+        .collect();
+
+    return Ok(nodes);
+}
+
+pub(crate) fn nodes_v6(data: &[u8]) -> Result<Vec<(Vec<u8>, SocketAddrV6)>, BencodingError> {
+    let nodes = entries(data, NODE_V6_LEN)?
+        .into_iter()
+        .map(|entry| (entry[..20].to_vec(), peer_v6(&entry[20..])))
+        .collect();
+
+    return Ok(nodes);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    use crate::bencoding_parser::compact;
+
+    #[test]
+    fn peer_v4_reads_big_endian_port() {
+        let addr = compact::peer_v4(b"\x7F\x00\x00\x01\x1A\xE1");
+        assert_eq!(addr, SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 6881));
+    }
+
+    #[test]
+    fn peer_v6_reads_address_and_port() {
+        let addr = compact::peer_v6(
+            b"\x20\x01\x0D\xB8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\x1A\xE1",
+        );
+        let ip = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        assert_eq!(addr, SocketAddrV6::new(ip, 6881, 0, 0));
+    }
+
+    #[test]
+    fn entries_rejects_partial_entry() {
+        assert!(compact::entries(b"\x7F\x00\x00\x01\x1A", compact::PEER_V4_LEN).is_err());
+    }
+
+    #[test]
+    fn entries_of_empty_string_is_empty() {
+        let entries = compact::entries(b"", compact::NODE_V4_LEN).unwrap();
+        assert!(entries.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use super::{Bencoding, TorrentFile};
+
+/// A way in which the content of two torrents differs, as reported by
+/// `Bencoding::compare_content`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentDifference {
+    /// A file at this path is only in the first torrent.
+    OnlyInSelf(Vec<Vec<u8>>),
+    /// A file at this path is only in the second torrent.
+    OnlyInOther(Vec<Vec<u8>>),
+    /// Both torrents have a file at this path, of different lengths.
+    LengthDiffers {
+        path: Vec<Vec<u8>>,
+        length: i64,
+        other_length: i64,
+    },
+}
+
+/// What changed from one version of a torrent to another, as reported by
+/// `Bencoding::info_diff`. File paths are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InfoDiff {
+    /// The paths of the files only in the new torrent.
+    pub added: Vec<Vec<Vec<u8>>>,
+    /// The paths of the files only in the old torrent.
+    pub removed: Vec<Vec<Vec<u8>>>,
+    /// The paths of the files in both, with different lengths.
+    pub changed: Vec<Vec<Vec<u8>>>,
+    /// Whether the `info` dictionaries encode differently, and so have
+    /// different info-hashes. This can be the case with no file changes,
+    /// when the piece length, the name or other fields of `info` differ.
+    pub info_hash_changed: bool,
+}
+
+impl Bencoding {
+    /// The files holding actual content, whatever the torrent's version: the
+    /// v2 `file tree` if there is one, or else the v1 file list without its
+    /// padding files.
+    pub fn content_files(&self) -> Option<Vec<TorrentFile>> {
+        if let Some(files) = self.file_tree() {
+            return Some(files);
+        }
+
+        let mut files = self.files()?;
+        files.retain(|file| !file.padding);
+
+        return Some(files);
+    }
+
+    /// Compares the files of two torrents, of any version, by path and
+    /// length, so that a v1 torrent and a v2 or hybrid one can be checked to
+    /// describe the same content.
+    ///
+    /// Files are read with `content_files`, so padding files and the order of
+    /// the files don't matter, and neither does anything else in `info`, such
+    /// as the piece hashes or the torrents' names. The differences are sorted
+    /// by path; an empty list means the content is the same. Returns `None`
+    /// if either file list is malformed.
+    pub fn compare_content(&self, other: &Bencoding) -> Option<Vec<ContentDifference>> {
+        return Some(compare_files(self.content_files()?, other.content_files()?));
+    }
+
+    /// Compares this torrent with `other`, a newer version of it, to tell
+    /// whether an update only adds or changes some files or if the whole
+    /// content has to be fetched again.
+    ///
+    /// Files are compared as by `compare_content`, and the info-hashes by
+    /// `encoded_info`. Returns `None` if either torrent has no `info`, or a
+    /// malformed file list.
+    pub fn info_diff(&self, other: &Bencoding) -> Option<InfoDiff> {
+        let info = self.encoded_info()?;
+        let other_info = other.encoded_info()?;
+
+        let mut diff = InfoDiff {
+            info_hash_changed: info != other_info,
+            ..InfoDiff::default()
+        };
+        for difference in self.compare_content(other)? {
+            match difference {
+                ContentDifference::OnlyInSelf(path) => diff.removed.push(path),
+                ContentDifference::OnlyInOther(path) => diff.added.push(path),
+                ContentDifference::LengthDiffers { path, .. } => diff.changed.push(path),
+            }
+        }
+
+        return Some(diff);
+    }
+}
+
+/// The differences between two file lists, by path and length, sorted by
+/// path.
+pub(crate) fn compare_files(
+    ours: Vec<TorrentFile>,
+    theirs: Vec<TorrentFile>,
+) -> Vec<ContentDifference> {
+    let ours = by_path(ours);
+    let theirs = by_path(theirs);
+
+    let mut paths: Vec<&Vec<Vec<u8>>> = ours.keys().chain(theirs.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut differences = Vec::new();
+    for path in paths {
+        match (ours.get(path), theirs.get(path)) {
+            (Some(&length), Some(&other_length)) if length != other_length => {
+                differences.push(ContentDifference::LengthDiffers {
+                    path: path.clone(),
+                    length,
+                    other_length,
+                });
+            }
+            (Some(_), None) => differences.push(ContentDifference::OnlyInSelf(path.clone())),
+            (None, Some(_)) => differences.push(ContentDifference::OnlyInOther(path.clone())),
+            _ => {}
+        }
+    }
+
+    return differences;
+}
+
+fn by_path(files: Vec<TorrentFile>) -> BTreeMap<Vec<Vec<u8>>, i64> {
+    return files
+        .into_iter()
+        .map(|file| (file.path, file.length))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{Bencoding, ContentDifference, InfoDiff};
+
+    const V1: &[u8] = b"d4:infod5:filesld6:lengthi1e4:pathl3:dir1:aeed4:attr1:p6:lengthi15e4:pathl4:.pad2:15eed6:lengthi3e4:pathl1:ceee4:name2:v112:piece lengthi16e6:pieces0:ee";
+
+    #[test]
+    fn compare_content_of_v1_and_v2_equivalents() {
+        let v2 = Bencoding::decode(
+            b"d4:infod9:file treed1:cd0:d6:lengthi3eee3:dird1:ad0:d6:lengthi1eeeee12:meta versioni2e4:name2:v212:piece lengthi16eee",
+        )
+        .unwrap();
+        let v1 = Bencoding::decode(V1).unwrap();
+
+        assert_eq!(v1.compare_content(&v2), Some(Vec::new()));
+    }
+
+    #[test]
+    fn compare_content_reports_differences() {
+        let v2 = Bencoding::decode(
+            b"d4:infod9:file treed1:cd0:d6:lengthi4eee1:dd0:d6:lengthi1eeee12:meta versioni2e4:name2:v2ee",
+        )
+        .unwrap();
+        let v1 = Bencoding::decode(V1).unwrap();
+
+        assert_eq!(
+            v1.compare_content(&v2).unwrap(),
+            vec![
+                ContentDifference::LengthDiffers {
+                    path: vec![b"c".to_vec()],
+                    length: 3,
+                    other_length: 4,
+                },
+                ContentDifference::OnlyInOther(vec![b"d".to_vec()]),
+                ContentDifference::OnlyInSelf(vec![b"dir".to_vec(), b"a".to_vec()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn info_diff_reports_file_changes() {
+        let old = Bencoding::decode(V1).unwrap();
+        let new = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl3:dir1:aeed6:lengthi4e4:pathl1:ceed6:lengthi2e4:pathl1:eeee4:name2:v112:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            old.info_diff(&new),
+            Some(InfoDiff {
+                added: vec![vec![b"e".to_vec()]],
+                removed: Vec::new(),
+                changed: vec![vec![b"c".to_vec()]],
+                info_hash_changed: true,
+            })
+        );
+        assert_eq!(
+            new.info_diff(&old).unwrap().removed,
+            vec![vec![b"e".to_vec()]]
+        );
+    }
+
+    #[test]
+    fn info_diff_of_same_files_with_other_info() {
+        let old = Bencoding::decode(b"d4:infod6:lengthi3e4:name1:x12:piece lengthi16eee").unwrap();
+        let renamed =
+            Bencoding::decode(b"d4:infod6:lengthi3e4:name1:x12:piece lengthi32eee").unwrap();
+        let same = Bencoding::decode(
+            b"d8:announce12:http://t/ann4:infod6:lengthi3e4:name1:x12:piece lengthi16eee",
+        )
+        .unwrap();
+
+        let diff = old.info_diff(&renamed).unwrap();
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty());
+        assert!(diff.info_hash_changed);
+        assert_eq!(old.info_diff(&same), Some(InfoDiff::default()));
+    }
+
+    #[test]
+    fn info_diff_without_info_is_none() {
+        let old = Bencoding::decode(V1).unwrap();
+        let empty = Bencoding::decode(b"de").unwrap();
+        assert_eq!(old.info_diff(&empty), None);
+        assert_eq!(empty.info_diff(&old), None);
+    }
+}
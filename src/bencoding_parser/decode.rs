@@ -0,0 +1,842 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::{BencodingDict, BencodingError, BencodingValue, ValueFactory};
+
+/// Leniency switches for `Bencoding::decode_with_options`. Everything is off
+/// by default, which is what `Bencoding::decode` uses.
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// Accept a single `\r` right before the `:` of a string length prefix,
+    /// so `5\r:hello` decodes as `5:hello`. Some broken encoders emit these.
+    pub allow_cr_in_string_length: bool,
+    /// Give up with `Err(BudgetExceeded)` after this many operations, where
+    /// decoding each string (including dictionary keys), integer, list or
+    /// dictionary is one operation. This bounds the total work spent on
+    /// untrusted input, whatever its shape.
+    pub max_operations: Option<usize>,
+    /// Scan the whole input once before decoding, checking every string
+    /// length against the bytes actually left, so that malformed input is
+    /// rejected before anything is allocated.
+    ///
+    /// A single pass already rejects such input, but only when it gets
+    /// there, after allocating the strings before it; many moderate lengths
+    /// can add up to much more memory than the input's size would suggest
+    /// before that. The scan costs a second pass over valid input, so it's
+    /// worth it for large untrusted input only. `max_operations` applies to
+    /// each pass separately.
+    pub validate_lengths_first: bool,
+    /// Skip a UTF-8 byte order mark (`EF BB BF`) at the very start of the
+    /// input, as prepended by some text-oriented tools.
+    pub skip_bom: bool,
+    /// Skip ASCII whitespace before the top-level value, after the byte
+    /// order mark if `skip_bom` is also set.
+    pub skip_leading_whitespace: bool,
+    /// Accept a single NUL byte after the top-level value, as appended by
+    /// some C tools that treat the buffer as a string. Any other byte after
+    /// the value is rejected whatever the options.
+    pub allow_trailing_nul: bool,
+    /// Reject dictionary keys that are the empty string with
+    /// `Err(EmptyKey)`. They are valid bencoding, but no torrent field has
+    /// one, so they point to a broken or crafted torrent.
+    ///
+    /// The exception is the v2 `file tree` (BEP 52), where the empty key
+    /// marks the dictionary describing a file: keys anywhere under a
+    /// `file tree` key are accepted, at any depth of the structure.
+    pub reject_empty_keys: bool,
+    /// Decode non-negative integers above `i64::MAX`, up to `u64::MAX`, as
+    /// `BencodingValue::Unsigned` rather than rejecting them with
+    /// `Err(InvalidInteger)`. Some clients write transfer counters in resume
+    /// data that have outgrown an `i64`; integers that fit one are still
+    /// `Integer`.
+    pub allow_u64_integers: bool,
+}
+
+/// The number of values of each type in bencoded data, as returned by
+/// `count_tokens`. Dictionary keys count as strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenCounts {
+    pub strings: usize,
+    pub integers: usize,
+    pub lists: usize,
+    pub dicts: usize,
+}
+
+/// The UTF-8 encoding of U+FEFF, skipped with `DecodeOptions::skip_bom`.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Length of the big-endian length prefix read by
+/// `BencodingValue::decode_framed`.
+const FRAME_HEADER_LEN: usize = 4;
+
+/// Top-level keys with the byte ranges of their values in the input.
+pub(crate) type ValueSpans<'a> = Vec<(&'a [u8], Range<usize>)>;
+
+/// Recursive descent decoder over a byte buffer.
+///
+/// Besides the decoded values it keeps track of the first place where the
+/// input deviates from the canonical encoding, for
+/// `Bencoding::assert_canonical`.
+pub(crate) struct Decoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    options: &'a DecodeOptions,
+    operations: usize,
+    in_file_tree: bool,
+    pub(crate) non_canonical: Option<(usize, &'static str)>,
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(data: &'a [u8], options: &'a DecodeOptions) -> Self {
+        return Self {
+            data,
+            position: 0,
+            options,
+            operations: 0,
+            in_file_tree: false,
+            non_canonical: None,
+        };
+    }
+
+    pub(crate) fn decode_dict(&mut self) -> Result<BencodingDict, BencodingError> {
+        return self.decode_dict_reporting_keys(|_| {});
+    }
+
+    /// Like `decode_dict`, but calls `on_key` with each of the dictionary's
+    /// own keys once it's decoded, before decoding its value.
+    pub(crate) fn decode_dict_reporting_keys<F: FnMut(&[u8])>(
+        &mut self,
+        mut on_key: F,
+    ) -> Result<BencodingDict, BencodingError> {
+        self.count_operation()?;
+        self.expect(b'd')?;
+        let mut previous_key: Option<Vec<u8>> = None;
+
+        let mut dict = BencodingDict::new();
+        loop {
+            // 0x65 ('e') indicates end of dictionary
+            if self.peek()? == b'e' {
+                self.position += 1;
+                break;
+            }
+
+            let key_position = self.position;
+            if !self.peek()?.is_ascii_digit() {
+                return Err(self.unexpected_byte());
+            }
+            let key = self.decode_string()?;
+            if key.is_empty() && self.options.reject_empty_keys && !self.in_file_tree {
+                return Err(BencodingError::EmptyKey {
+                    position: key_position,
+                });
+            }
+            if let Some(previous_key) = &previous_key {
+                if key == *previous_key {
+                    self.mark_non_canonical(key_position, "duplicate dictionary key");
+                } else if key < *previous_key {
+                    self.mark_non_canonical(key_position, "dictionary keys are not sorted");
+                }
+            }
+            on_key(&key);
+
+            let in_file_tree = self.in_file_tree;
+            self.in_file_tree |= key == b"file tree";
+            let value = self.decode_next()?;
+            self.in_file_tree = in_file_tree;
+            previous_key = Some(key.clone());
+            dict.insert(key, value);
+        }
+
+        return Ok(dict);
+    }
+
+    /// Advances past the byte order mark and whitespace that the options
+    /// allow before the top-level value. Error positions stay relative to
+    /// the start of the input.
+    pub(crate) fn skip_leading_bytes(&mut self) {
+        if self.options.skip_bom && self.data[self.position..].starts_with(BOM) {
+            self.position += BOM.len();
+        }
+        if self.options.skip_leading_whitespace {
+            while self
+                .data
+                .get(self.position)
+                .is_some_and(u8::is_ascii_whitespace)
+            {
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Checks that the whole input has been decoded, allowing a single
+    /// trailing NUL byte with `DecodeOptions::allow_trailing_nul`.
+    pub(crate) fn expect_end(&mut self) -> Result<(), BencodingError> {
+        if self.options.allow_trailing_nul && self.data[self.position..] == [0] {
+            self.position += 1;
+        }
+        if self.position < self.data.len() {
+            return Err(self.unexpected_byte());
+        }
+
+        return Ok(());
+    }
+
+    /// The offset of the next byte to decode.
+    pub(crate) fn position(&self) -> usize {
+        return self.position;
+    }
+
+    /// Moves to `position` in the input, where decoding continues.
+    pub(crate) fn seek(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Decodes the top-level dictionary's keys, skipping over the values and
+    /// returning their byte ranges in the input instead.
+    ///
+    /// The values are still checked to be well-formed, so that decoding a
+    /// range later can't fail.
+    pub(crate) fn decode_dict_spans(&mut self) -> Result<ValueSpans<'a>, BencodingError> {
+        self.count_operation()?;
+        self.expect(b'd')?;
+
+        let mut spans = Vec::new();
+        loop {
+            if self.peek()? == b'e' {
+                self.position += 1;
+                break;
+            }
+
+            if !self.peek()?.is_ascii_digit() {
+                return Err(self.unexpected_byte());
+            }
+            let key = self.string_span()?;
+            let start = self.position;
+            self.skip_next()?;
+            spans.push((&self.data[key], start..self.position));
+        }
+
+        return Ok(spans);
+    }
+
+    fn decode_string(&mut self) -> Result<Vec<u8>, BencodingError> {
+        let span = self.string_span()?;
+
+        return Ok(self.data[span].to_vec());
+    }
+
+    /// Advances past a string and returns the range of its contents.
+    fn string_span(&mut self) -> Result<Range<usize>, BencodingError> {
+        self.count_operation()?;
+        let start = self.position;
+        let mut separator_idx = start;
+
+        while self.byte_at(separator_idx)? != b':' {
+            separator_idx += 1;
+        }
+
+        let data = self.data;
+        let mut digits = &data[start..separator_idx];
+        if self.options.allow_cr_in_string_length && digits.last() == Some(&b'\r') {
+            digits = &digits[..digits.len() - 1];
+            self.mark_non_canonical(start, "string length contains a carriage return");
+        }
+        // No slice can be longer than isize::MAX bytes, even where usize
+        // holds larger lengths.
+        let length: usize = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&length| length <= isize::MAX as usize)
+            .ok_or(BencodingError::InvalidStringLength { position: start })?;
+        if length.to_string().as_bytes() != digits {
+            self.mark_non_canonical(start, "string length is not in minimal form");
+        }
+
+        self.position = separator_idx + 1;
+        if length > self.data.len() - self.position {
+            return Err(BencodingError::UnexpectedEof);
+        }
+        let span = self.position..self.position + length;
+        self.position += length;
+
+        return Ok(span);
+    }
+
+    fn decode_integer(&mut self) -> Result<i64, BencodingError> {
+        return self.decode_integer_as();
+    }
+
+    /// Decodes an integer as `Integer`, or as `Unsigned` if it only fits a
+    /// `u64` and `DecodeOptions::allow_u64_integers` is set.
+    fn decode_integer_value(&mut self) -> Result<BencodingValue, BencodingError> {
+        let start = self.position;
+        let operations = self.operations;
+        match self.decode_integer() {
+            Err(BencodingError::InvalidInteger { .. }) if self.options.allow_u64_integers => {
+                self.position = start;
+                self.operations = operations;
+                return Ok(BencodingValue::Unsigned(self.decode_integer_as()?));
+            }
+            result => return Ok(BencodingValue::Integer(result?)),
+        }
+    }
+
+    fn decode_integer_as<T: FromStr + ToString>(&mut self) -> Result<T, BencodingError> {
+        // Encodings with a leading zero such as i03e, or i-0e, are accepted but
+        // recorded as non-canonical. Only i0e is the canonical form of zero.
+        self.count_operation()?;
+        self.expect(b'i')?;
+        let start = self.position;
+        let mut ending_idx = start;
+        while self.byte_at(ending_idx)? != b'e' {
+            ending_idx += 1;
+        }
+
+        let digits = &self.data[start..ending_idx];
+        let value: T = std::str::from_utf8(digits)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(BencodingError::InvalidInteger { position: start })?;
+        if value.to_string().as_bytes() != digits {
+            self.mark_non_canonical(start, "integer is not in minimal form");
+        }
+
+        self.position = ending_idx + 1;
+
+        return Ok(value);
+    }
+
+    fn decode_list(&mut self) -> Result<Vec<BencodingValue>, BencodingError> {
+        self.count_operation()?;
+        self.expect(b'l')?;
+
+        let mut list: Vec<BencodingValue> = Vec::new();
+        loop {
+            // 0x65 ('e') indicates end of list
+            if self.peek()? == b'e' {
+                self.position += 1;
+                break;
+            }
+
+            list.push(self.decode_next()?);
+        }
+
+        return Ok(list);
+    }
+
+    pub(crate) fn decode_next(&mut self) -> Result<BencodingValue, BencodingError> {
+        match self.peek()? {
+            b'i' => return self.decode_integer_value(),
+            b'l' => return Ok(BencodingValue::List(self.decode_list()?)),
+            b'd' => return Ok(BencodingValue::Dict(self.decode_dict()?)),
+            b'0'..=b'9' => return Ok(BencodingValue::String(self.decode_string()?)),
+            _ => return Err(self.unexpected_byte()),
+        };
+    }
+
+    /// Decodes the next value like `decode_next`, but builds it with
+    /// `factory`.
+    pub(crate) fn build_next<F: ValueFactory>(
+        &mut self,
+        factory: &mut F,
+    ) -> Result<F::Value, BencodingError> {
+        match self.peek()? {
+            b'i' => return Ok(factory.integer(self.decode_integer()?)),
+            b'0'..=b'9' => {
+                let span = self.string_span()?;
+                return Ok(factory.string(&self.data[span]));
+            }
+            b'l' => {
+                self.count_operation()?;
+                self.position += 1;
+                let mut elements = Vec::new();
+                while self.peek()? != b'e' {
+                    elements.push(self.build_next(factory)?);
+                }
+                self.position += 1;
+                return Ok(factory.list(elements));
+            }
+            b'd' => {
+                self.count_operation()?;
+                self.position += 1;
+                let mut entries = Vec::new();
+                while self.peek()? != b'e' {
+                    if !self.peek()?.is_ascii_digit() {
+                        return Err(self.unexpected_byte());
+                    }
+                    let key = self.decode_string()?;
+                    entries.push((key, self.build_next(factory)?));
+                }
+                self.position += 1;
+                return Ok(factory.dict(entries));
+            }
+            _ => return Err(self.unexpected_byte()),
+        }
+    }
+
+    /// Advances past the next value, checking it like `decode_next` but
+    /// without building it.
+    pub(crate) fn skip_next(&mut self) -> Result<(), BencodingError> {
+        return self.skip_counting(&mut TokenCounts::default());
+    }
+
+    /// Like `skip_next`, adding the values skipped over to `counts`.
+    pub(crate) fn skip_counting(&mut self, counts: &mut TokenCounts) -> Result<(), BencodingError> {
+        match self.peek()? {
+            b'i' => {
+                self.decode_integer_value()?;
+                counts.integers += 1;
+            }
+            b'0'..=b'9' => {
+                self.string_span()?;
+                counts.strings += 1;
+            }
+            b'l' => {
+                self.count_operation()?;
+                self.position += 1;
+                while self.peek()? != b'e' {
+                    self.skip_counting(counts)?;
+                }
+                self.position += 1;
+                counts.lists += 1;
+            }
+            b'd' => {
+                self.count_operation()?;
+                self.position += 1;
+                while self.peek()? != b'e' {
+                    if !self.peek()?.is_ascii_digit() {
+                        return Err(self.unexpected_byte());
+                    }
+                    self.string_span()?;
+                    counts.strings += 1;
+                    self.skip_counting(counts)?;
+                }
+                self.position += 1;
+                counts.dicts += 1;
+            }
+            _ => return Err(self.unexpected_byte()),
+        }
+
+        return Ok(());
+    }
+
+    fn count_operation(&mut self) -> Result<(), BencodingError> {
+        self.operations += 1;
+        match self.options.max_operations {
+            Some(limit) if self.operations > limit => {
+                return Err(BencodingError::BudgetExceeded { limit })
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    fn peek(&self) -> Result<u8, BencodingError> {
+        return self.byte_at(self.position);
+    }
+
+    fn byte_at(&self, position: usize) -> Result<u8, BencodingError> {
+        return self
+            .data
+            .get(position)
+            .copied()
+            .ok_or(BencodingError::UnexpectedEof);
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), BencodingError> {
+        if self.peek()? != byte {
+            return Err(self.unexpected_byte());
+        }
+        self.position += 1;
+
+        return Ok(());
+    }
+
+    fn unexpected_byte(&self) -> BencodingError {
+        return BencodingError::UnexpectedByte {
+            position: self.position,
+            byte: self.data[self.position],
+        };
+    }
+
+    fn mark_non_canonical(&mut self, position: usize, reason: &'static str) {
+        if self.non_canonical.is_none() {
+            self.non_canonical = Some((position, reason));
+        }
+    }
+}
+
+/// Counts the values of each type in `data`, which must hold a single
+/// bencoded value of any type, without building them: strings are only
+/// checked to fit in the input, not copied. This is much faster than
+/// decoding when only structural statistics are needed.
+///
+/// The input is checked as when decoding it, bytes after the value
+/// included, so malformed data is an error rather than a partial count.
+pub fn count_tokens(data: &[u8]) -> Result<TokenCounts, BencodingError> {
+    let options = DecodeOptions::default();
+    let mut decoder = Decoder::new(data, &options);
+    let mut counts = TokenCounts::default();
+    decoder.skip_counting(&mut counts)?;
+    decoder.expect_end()?;
+
+    return Ok(counts);
+}
+
+impl BencodingValue {
+    /// Decodes one length-framed value from the front of `data` and returns
+    /// it with the bytes following the frame.
+    ///
+    /// A frame is a 4-byte big-endian length followed by exactly that many
+    /// bytes, which must hold a single bencoded value of any type. A frame
+    /// running past the end of `data` is `Err(UnexpectedEof)`, and bytes left
+    /// in the frame after the value are `Err(UnexpectedByte)`. Error
+    /// positions are relative to the start of the frame's payload.
+    pub fn decode_framed(data: &[u8]) -> Result<(BencodingValue, &[u8]), BencodingError> {
+        if data.len() < FRAME_HEADER_LEN {
+            return Err(BencodingError::UnexpectedEof);
+        }
+        let (header, rest) = data.split_at(FRAME_HEADER_LEN);
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if length > rest.len() {
+            return Err(BencodingError::UnexpectedEof);
+        }
+        let (payload, rest) = rest.split_at(length);
+
+        let options = DecodeOptions::default();
+        let mut decoder = Decoder::new(payload, &options);
+        let value = decoder.decode_next()?;
+        if decoder.position < payload.len() {
+            return Err(decoder.unexpected_byte());
+        }
+
+        return Ok((value, rest));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{
+        count_tokens, Bencoding, BencodingError, BencodingValue, DecodeOptions, TokenCounts,
+    };
+
+    #[test]
+    fn decode_truncated_string_is_error() {
+        let result = Bencoding::decode(b"d3:key10:valuee");
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn decode_unterminated_dict_is_error() {
+        let result = Bencoding::decode(b"d3:keyi1e");
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn decode_invalid_integer_is_error() {
+        let result = Bencoding::decode(b"d3:keyi1x2ee");
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidInteger { position: 7 })
+        ));
+    }
+
+    #[test]
+    fn decode_non_dict_top_level_is_error() {
+        let result = Bencoding::decode(b"li1ee");
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte {
+                position: 0,
+                byte: b'l'
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_rejects_cr_in_string_length_by_default() {
+        let result = Bencoding::decode(b"d3:key5\r:helloe");
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidStringLength { position: 6 })
+        ));
+    }
+
+    #[test]
+    fn decode_rejects_string_length_beyond_isize_max() {
+        let data = format!("d{}:xe", isize::MAX as usize + 1);
+        assert!(matches!(
+            Bencoding::decode(data.as_bytes()),
+            Err(BencodingError::InvalidStringLength { position: 1 })
+        ));
+
+        let data = format!("d{}:xe", isize::MAX);
+        assert!(matches!(
+            Bencoding::decode(data.as_bytes()),
+            Err(BencodingError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn decode_with_options_strips_cr_in_string_length() {
+        let options = DecodeOptions {
+            allow_cr_in_string_length: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(b"d3:key5\r:helloe", &options).unwrap();
+        let result = match parser.get(b"key").unwrap() {
+            BencodingValue::String(s) => s,
+            _ => panic!(),
+        };
+
+        assert_eq!(result, b"hello");
+        assert!(parser.assert_canonical().is_err());
+    }
+
+    #[test]
+    fn decode_with_options_rejects_more_than_one_cr() {
+        let options = DecodeOptions {
+            allow_cr_in_string_length: true,
+            ..Default::default()
+        };
+        let result = Bencoding::decode_with_options(b"d3:key5\r\r:helloe", &options);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn decode_rejects_trailing_bytes_by_default() {
+        let result = Bencoding::decode(b"d3:cow3:mooe\x00");
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte {
+                position: 12,
+                byte: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_allowing_trailing_nul() {
+        let options = DecodeOptions {
+            allow_trailing_nul: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(b"d3:cow3:mooe\x00", &options).unwrap();
+        assert_eq!(parser.get_string(b"cow"), Some(b"moo".as_slice()));
+        assert!(Bencoding::decode_with_options(b"d3:cow3:mooe", &options).is_ok());
+
+        assert!(Bencoding::decode_with_options(b"d3:cow3:mooe\x00\x00", &options).is_err());
+        assert!(Bencoding::decode_with_options(b"d3:cow3:mooe\n", &options).is_err());
+    }
+
+    #[test]
+    fn decode_allowing_u64_integers() {
+        let options = DecodeOptions {
+            allow_u64_integers: true,
+            validate_lengths_first: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(
+            b"d1:ai18446744073709551615e1:bi9223372036854775807ee",
+            &options,
+        )
+        .unwrap();
+        assert!(matches!(
+            parser.get(b"a"),
+            Some(BencodingValue::Unsigned(u64::MAX))
+        ));
+        assert!(matches!(
+            parser.get(b"b"),
+            Some(BencodingValue::Integer(i64::MAX))
+        ));
+
+        for data in [
+            b"d1:ai18446744073709551616ee".as_slice(),
+            b"d1:ai-9223372036854775809ee",
+        ] {
+            let result = Bencoding::decode_with_options(data, &options);
+            assert!(
+                matches!(result, Err(BencodingError::InvalidInteger { position: 5 })),
+                "{:?}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn decode_rejecting_empty_keys() {
+        let options = DecodeOptions {
+            reject_empty_keys: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Bencoding::decode_with_options(b"d0:5:valuee", &options),
+            Err(BencodingError::EmptyKey { position: 1 })
+        ));
+        assert!(matches!(
+            Bencoding::decode_with_options(b"d4:infod1:ad0:i1eeee", &options),
+            Err(BencodingError::EmptyKey { position: 12 })
+        ));
+        assert!(Bencoding::decode(b"d0:5:valuee").is_ok());
+    }
+
+    #[test]
+    fn decode_rejecting_empty_keys_allows_v2_file_tree() {
+        let options = DecodeOptions {
+            reject_empty_keys: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(
+            b"d4:infod9:file treed3:dird1:ad0:d6:lengthi1eeeee4:name1:xee",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(parser.file_tree().unwrap().len(), 1);
+
+        assert!(matches!(
+            Bencoding::decode_with_options(
+                b"d4:infod9:file treed1:ad0:d6:lengthi1eeee0:i1eee",
+                &options
+            ),
+            Err(BencodingError::EmptyKey { position: 41 })
+        ));
+    }
+
+    #[test]
+    fn count_tokens_of_nested_data() {
+        assert_eq!(
+            count_tokens(b"d1:ali1ei2ee1:bd1:c3:xyzee").unwrap(),
+            TokenCounts {
+                strings: 4,
+                integers: 2,
+                lists: 1,
+                dicts: 2,
+            }
+        );
+        assert_eq!(
+            count_tokens(b"i7e").unwrap(),
+            TokenCounts {
+                integers: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn count_tokens_of_malformed_data_is_error() {
+        assert!(matches!(
+            count_tokens(b"d1:ali1e"),
+            Err(BencodingError::UnexpectedEof)
+        ));
+        assert!(count_tokens(b"d1:a9:xe").is_err());
+        assert!(count_tokens(b"dei1e").is_err());
+    }
+
+    #[test]
+    fn decode_within_operation_budget() {
+        let options = DecodeOptions {
+            max_operations: Some(5),
+            ..Default::default()
+        };
+        assert!(Bencoding::decode_with_options(b"d1:ali1ei2eee", &options).is_ok());
+    }
+
+    #[test]
+    fn decode_over_operation_budget_is_error() {
+        let options = DecodeOptions {
+            max_operations: Some(4),
+            ..Default::default()
+        };
+        let result = Bencoding::decode_with_options(b"d1:ali1ei2eee", &options);
+        assert!(matches!(
+            result,
+            Err(BencodingError::BudgetExceeded { limit: 4 })
+        ));
+    }
+
+    #[test]
+    fn decode_validating_lengths_first() {
+        let options = DecodeOptions {
+            validate_lengths_first: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(b"d1:a3:abc1:bli1eee", &options).unwrap();
+        assert!(parser.get(b"b").is_some());
+
+        let result = Bencoding::decode_with_options(b"d1:a3:abc1:b99:xe", &options);
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+        let result = Bencoding::decode_with_options(b"i1e", &options);
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte { position: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn decode_skipping_bom() {
+        let options = DecodeOptions {
+            skip_bom: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(b"\xEF\xBB\xBFd1:ai1ee", &options).unwrap();
+        assert!(parser.get(b"a").is_some());
+        assert!(Bencoding::decode(b"\xEF\xBB\xBFd1:ai1ee").is_err());
+
+        let result = Bencoding::decode_with_options(b"\xEF\xBB\xBF d1:ai1ee", &options);
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte {
+                position: 3,
+                byte: b' '
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_skipping_leading_whitespace() {
+        let options = DecodeOptions {
+            skip_bom: true,
+            skip_leading_whitespace: true,
+            ..Default::default()
+        };
+        let parser =
+            Bencoding::decode_with_options(b"\xEF\xBB\xBF\r\n\t d1:ai1ee", &options).unwrap();
+        assert!(parser.get(b"a").is_some());
+        assert!(Bencoding::decode_with_options(b"  d1:ai1ee", &options).is_ok());
+        assert!(matches!(
+            Bencoding::decode_with_options(b" \n", &options),
+            Err(BencodingError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn decode_framed_returns_value_and_rest() {
+        let (value, rest) =
+            BencodingValue::decode_framed(b"\x00\x00\x00\x04i42e\x00\x00\x00\x02le").unwrap();
+        assert!(matches!(value, BencodingValue::Integer(42)));
+        assert_eq!(rest, b"\x00\x00\x00\x02le");
+    }
+
+    #[test]
+    fn decode_framed_longer_than_data_is_error() {
+        let result = BencodingValue::decode_framed(b"\x00\x00\x00\x09i42e");
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn decode_framed_with_bytes_after_value_is_error() {
+        let result = BencodingValue::decode_framed(b"\x00\x00\x00\x05i42ex");
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte {
+                position: 4,
+                byte: b'x'
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_framed_truncated_in_value_is_error() {
+        let result = BencodingValue::decode_framed(b"\x00\x00\x00\x03i42e");
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+    }
+}
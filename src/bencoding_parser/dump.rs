@@ -0,0 +1,285 @@
+use super::{Bencoding, BencodingDict, BencodingValue, PathSegment};
+
+impl Bencoding {
+    /// A deterministic, line-oriented text rendering of the structure, meant
+    /// for diffing torrents, for instance when storing them in git.
+    ///
+    /// There is one `path: value` line per value, in `walk` order, so keys
+    /// are sorted and two structures that encode to the same bytes dump to
+    /// the same text. Paths are dictionary keys joined with `.` and list
+    /// indices in brackets, such as `info.files[0].length`; keys that aren't
+    /// plain text or contain `.`, `[`, `]`, `:`, `"`, `\` or spaces are quoted
+    /// and escaped with `escape_bytes`, even binary ones. Dictionaries and
+    /// lists show their length. Strings that are printable UTF-8 are quoted,
+    /// with `"` and `\` escaped; other strings,
+    /// such as `pieces`, are shown as their length and 64-bit FNV-1a hash,
+    /// which is enough to tell blobs apart but not cryptographic.
+    pub fn canonical_text_dump(&self) -> String {
+        let mut out = String::new();
+        self.walk(|path, value| {
+            out += &dump_path(path);
+            out += ": ";
+            match value {
+                BencodingValue::String(s) => out += &dump_string(s),
+                BencodingValue::Integer(i) => out += &i.to_string(),
+                BencodingValue::Unsigned(u) => out += &u.to_string(),
+                BencodingValue::List(list) => out += &format!("list (len {})", list.len()),
+                BencodingValue::Dict(dict) => out += &format!("dict (len {})", dict.len()),
+            }
+            out.push('\n');
+        });
+
+        return out;
+    }
+
+    /// The path of every value in the structure, in `walk` order and
+    /// formatted as in `canonical_text_dump`, such as
+    /// `info.files[0].length`, for enumerating the fields a torrent contains.
+    pub fn all_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.walk(|path, _| paths.push(dump_path(path)));
+
+        return paths;
+    }
+
+    /// A one-line rendering of the structure for logs, with nesting cut off
+    /// below `max_depth`, such as `{announce: "url", info: {...}}` for a
+    /// depth of 1.
+    ///
+    /// The top-level dictionary is at depth 0 and its values at depth 1.
+    /// Dictionaries and lists at `max_depth` that aren't empty are shown as
+    /// `{...}` and `[...]`. Keys are sorted, and keys and strings are shown
+    /// as in `canonical_text_dump`.
+    pub fn debug_shallow(&self, max_depth: usize) -> String {
+        let mut out = String::new();
+        shallow_dict(&self.dict, max_depth, &mut out);
+
+        return out;
+    }
+}
+
+fn shallow_dict(dict: &BencodingDict, depth_left: usize, out: &mut String) {
+    if dict.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+    if depth_left == 0 {
+        out.push_str("{...}");
+        return;
+    }
+
+    let mut keys: Vec<&Vec<u8>> = dict.keys().collect();
+    keys.sort();
+    out.push('{');
+    for (i, key) in keys.into_iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        out.push_str(&dump_key(key));
+        out.push_str(": ");
+        shallow_value(&dict[key], depth_left - 1, out);
+    }
+    out.push('}');
+}
+
+fn shallow_value(value: &BencodingValue, depth_left: usize, out: &mut String) {
+    match value {
+        BencodingValue::String(s) => out.push_str(&dump_string(s)),
+        BencodingValue::Integer(i) => out.push_str(&i.to_string()),
+        BencodingValue::Unsigned(u) => out.push_str(&u.to_string()),
+        BencodingValue::Dict(dict) => shallow_dict(dict, depth_left, out),
+        BencodingValue::List(list) if list.is_empty() => out.push_str("[]"),
+        BencodingValue::List(_) if depth_left == 0 => out.push_str("[...]"),
+        BencodingValue::List(list) => {
+            out.push('[');
+            for (i, element) in list.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                shallow_value(element, depth_left - 1, out);
+            }
+            out.push(']');
+        }
+    }
+}
+
+fn dump_path(path: &[PathSegment]) -> String {
+    let mut out = String::new();
+    for (i, segment) in path.iter().enumerate() {
+        match segment {
+            PathSegment::Key(key) => {
+                if i > 0 {
+                    out.push('.');
+                }
+                out += &dump_key(key);
+            }
+            PathSegment::Index(index) => out += &format!("[{}]", index),
+        }
+    }
+
+    return out;
+}
+
+fn dump_key(key: &[u8]) -> String {
+    let plain = !key.is_empty()
+        && key
+            .iter()
+            .all(|b| b.is_ascii_graphic() && !b".[]:\"\\".contains(b));
+    if plain {
+        return String::from_utf8_lossy(key).into_owned();
+    }
+
+    return format!("\"{}\"", escape_bytes(key));
+}
+
+fn dump_string(s: &[u8]) -> String {
+    if let Ok(text) = std::str::from_utf8(s) {
+        if !text.chars().any(char::is_control) {
+            return format!("\"{}\"", escape_bytes(s));
+        }
+    }
+
+    return format!("<{} bytes, fnv1a {:016x}>", s.len(), fnv1a(s));
+}
+
+/// Renders a byte string as text for display, the same way in every output
+/// of the crate: valid UTF-8 is kept as is, except that `\` and `"` are
+/// backslash-escaped, and control characters and bytes that aren't valid
+/// UTF-8 are written as `\xNN`, one escape per byte.
+///
+/// The result can be put in double quotes and read back unambiguously.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                c if c.is_control() => {
+                    for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                        out += &format!("\\x{:02x}", byte);
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            out += &format!("\\x{:02x}", byte);
+        }
+    }
+
+    return out;
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    return hash;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{escape_bytes, Bencoding};
+
+    #[test]
+    fn canonical_text_dump_of_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi5e4:pathl5:a.txteee6:pieces3:\x00\x01\x02e8:announce12:http://a/\"b\"4:x.pei1ee",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.canonical_text_dump(),
+            concat!(
+                "announce: \"http://a/\\\"b\\\"\"\n",
+                "info: dict (len 2)\n",
+                "info.files: list (len 1)\n",
+                "info.files[0]: dict (len 2)\n",
+                "info.files[0].length: 5\n",
+                "info.files[0].path: list (len 1)\n",
+                "info.files[0].path[0]: \"a.txt\"\n",
+                "info.pieces: <3 bytes, fnv1a d949aa186c0c4928>\n",
+                "\"x.pe\": 1\n",
+            )
+        );
+    }
+
+    #[test]
+    fn canonical_text_dump_is_stable_across_key_order_and_round_trips() {
+        let parser = Bencoding::decode(b"d1:bd1:yi2e1:xi1ee1:al0:i-3eee").unwrap();
+        let reordered = Bencoding::decode(b"d1:al0:i-3ee1:bd1:xi1e1:yi2eee").unwrap();
+        let round_tripped = Bencoding::decode(&parser.encode()).unwrap();
+
+        assert_eq!(
+            parser.canonical_text_dump(),
+            reordered.canonical_text_dump()
+        );
+        assert_eq!(
+            parser.canonical_text_dump(),
+            round_tripped.canonical_text_dump()
+        );
+    }
+
+    #[test]
+    fn all_paths_lists_every_value() {
+        let parser =
+            Bencoding::decode(b"d4:infod5:filesld6:lengthi5eee4:name1:ae3:a.bli1eee").unwrap();
+        assert_eq!(
+            parser.all_paths(),
+            vec![
+                "\"a.b\"",
+                "\"a.b\"[0]",
+                "info",
+                "info.files",
+                "info.files[0]",
+                "info.files[0].length",
+                "info.name",
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_shallow_limits_depth() {
+        let parser =
+            Bencoding::decode(b"d8:announce3:url4:infod5:filesld6:lengthi5eee4:name1:ae1:xlee")
+                .unwrap();
+
+        assert_eq!(parser.debug_shallow(0), "{...}");
+        assert_eq!(
+            parser.debug_shallow(1),
+            "{announce: \"url\", info: {...}, x: []}"
+        );
+        assert_eq!(
+            parser.debug_shallow(2),
+            "{announce: \"url\", info: {files: [...], name: \"a\"}, x: []}"
+        );
+        assert_eq!(
+            parser.debug_shallow(10),
+            "{announce: \"url\", info: {files: [{length: 5}], name: \"a\"}, x: []}"
+        );
+    }
+
+    #[test]
+    fn escape_bytes_of_mixed_input() {
+        assert_eq!(escape_bytes(b"plain text"), "plain text");
+        assert_eq!(
+            escape_bytes("caf\u{e9} \u{1f600}".as_bytes()),
+            "caf\u{e9} \u{1f600}"
+        );
+        assert_eq!(escape_bytes(b"a\x00b\xff\n"), "a\\x00b\\xff\\x0a");
+        assert_eq!(escape_bytes(b"say \"hi\" \\o/"), "say \\\"hi\\\" \\\\o/");
+        assert_eq!(escape_bytes("\u{85}".as_bytes()), "\\xc2\\x85");
+        assert_eq!(escape_bytes(b"\xe2\x82"), "\\xe2\\x82");
+        assert_eq!(escape_bytes(b""), "");
+    }
+
+    #[test]
+    fn binary_keys_are_escaped_in_dumps() {
+        let parser = Bencoding::decode(b"d2:\xaa\x01i1ee").unwrap();
+        assert_eq!(parser.canonical_text_dump(), "\"\\xaa\\x01\": 1\n");
+        assert_eq!(parser.debug_shallow(1), "{\"\\xaa\\x01\": 1}");
+    }
+}
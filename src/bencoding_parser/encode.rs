@@ -0,0 +1,862 @@
+use std::io::Write;
+
+#[cfg(feature = "sha1")]
+use sha1::{Digest, Sha1};
+
+use super::{Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+/// Destination of the encoder's output.
+trait Sink {
+    fn put(&mut self, bytes: &[u8]);
+}
+
+impl Sink for Vec<u8> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Writes into a caller-provided buffer, which must have been checked to be
+/// large enough beforehand.
+struct SliceSink<'a> {
+    buf: &'a mut [u8],
+    position: usize,
+}
+
+impl Sink for SliceSink<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.buf[self.position..self.position + bytes.len()].copy_from_slice(bytes);
+        self.position += bytes.len();
+    }
+}
+
+/// Writes to an `io::Write`, keeping the first error and skipping all writes
+/// after it.
+struct WriteSink<W: Write> {
+    writer: W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> Sink for WriteSink<W> {
+    fn put(&mut self, bytes: &[u8]) {
+        if self.error.is_none() {
+            if let Err(error) = self.writer.write_all(bytes) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<W: Write> WriteSink<W> {
+    fn new(writer: W) -> Self {
+        return Self {
+            writer,
+            error: None,
+        };
+    }
+
+    fn finish(self) -> Result<(), BencodingError> {
+        match self.error {
+            Some(error) => return Err(BencodingError::Io(error)),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Passes writes on to `inner`, also feeding them to `hasher` while it's set.
+#[cfg(feature = "sha1")]
+struct HashingSink<S: Sink> {
+    inner: S,
+    hasher: Option<Sha1>,
+}
+
+#[cfg(feature = "sha1")]
+impl<S: Sink> Sink for HashingSink<S> {
+    fn put(&mut self, bytes: &[u8]) {
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(bytes);
+        }
+        self.inner.put(bytes);
+    }
+}
+
+impl BencodingValue {
+    /// Encodes the value in canonical form: dictionary keys are sorted and
+    /// integers and string lengths have no leading zeros.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.encoded_len());
+        self.encode_to(&mut out);
+
+        return out;
+    }
+
+    /// The length in bytes of the canonical encoding of the value.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            BencodingValue::String(s) => return string_len(s),
+            BencodingValue::Integer(i) => return integer_len(*i),
+            BencodingValue::Unsigned(u) => return 2 + decimal_len(*u),
+            BencodingValue::List(list) => {
+                return 2 + list.iter().map(BencodingValue::encoded_len).sum::<usize>()
+            }
+            BencodingValue::Dict(dict) => return dict_len(dict),
+        }
+    }
+
+    pub(crate) fn encode_to(&self, out: &mut Vec<u8>) {
+        encode_value(self, out, false);
+    }
+}
+
+impl Bencoding {
+    /// Encodes the top-level dictionary in canonical form: dictionary keys
+    /// are sorted at every level and integers and string lengths have no
+    /// leading zeros.
+    ///
+    /// The output is the same whatever the storage backend, so info-hashes
+    /// don't depend on whether some crate in the build enabled the
+    /// `indexmap` feature. To write the keys in their input order instead,
+    /// use `encode_stored_order`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.encoded_len());
+        encode_dict(&self.dict, &mut out, false);
+
+        return out;
+    }
+
+    /// Like `encode`, but with the `indexmap` feature every dictionary is
+    /// written in its stored order, so decoded input round-trips byte for
+    /// byte even if its keys weren't sorted. Such output isn't canonical, and
+    /// isn't what an info-hash is computed over. Without `indexmap` this is
+    /// the same as `encode`.
+    pub fn encode_stored_order(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.encoded_len());
+        encode_dict(&self.dict, &mut out, true);
+
+        return out;
+    }
+
+    /// The canonical encoding of the top-level dictionary, the same as
+    /// `encode`.
+    pub fn canonicalize(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.encoded_len());
+        encode_dict(&self.dict, &mut out, false);
+
+        return out;
+    }
+
+    /// Sorts every dictionary in the structure by key, so that iteration and
+    /// `encode_stored_order` follow key order from then on.
+    ///
+    /// A `HashMap` has no order to fix, as the encoder sorts its keys anyway,
+    /// so this only does work with the `indexmap` feature.
+    pub fn canonicalize_in_place(&mut self) {
+        #[cfg(feature = "indexmap")]
+        sort_dict(&mut self.dict);
+    }
+
+    /// Writes the output of `encode` to `writer` as it's produced, without
+    /// building it in memory. Most writes are small, so `writer` should be
+    /// buffered; each string, though, is written straight from its stored
+    /// bytes in a single write, so a large `pieces` goes through a
+    /// `BufWriter` without being copied into its buffer.
+    pub fn write_encoded<W: Write>(&self, writer: W) -> Result<(), BencodingError> {
+        let mut sink = WriteSink::new(writer);
+        encode_dict(&self.dict, &mut sink, false);
+
+        return sink.finish();
+    }
+
+    /// Like `write_encoded`, but also computes the v1 info-hash from the
+    /// `info` bytes as they're written, so a newly built torrent can be
+    /// written out and identified in a single pass over the structure.
+    ///
+    /// The hash is the same as `info_hash`'s, or `None` if there is no
+    /// `info`. Requires the `sha1` feature.
+    #[cfg(feature = "sha1")]
+    pub fn write_encoded_with_info_hash<W: Write>(
+        &self,
+        writer: W,
+    ) -> Result<Option<[u8; 20]>, BencodingError> {
+        let mut sink = HashingSink {
+            inner: WriteSink::new(writer),
+            hasher: None,
+        };
+        let mut info_hash = None;
+        sink.put(b"d");
+        for key in encode_order(&self.dict, false) {
+            encode_string(key, &mut sink);
+            if key == b"info" {
+                sink.hasher = Some(Sha1::new());
+                encode_value(&self.dict[key], &mut sink, false);
+                info_hash = sink.hasher.take().map(|hasher| hasher.finalize().into());
+            } else {
+                encode_value(&self.dict[key], &mut sink, false);
+            }
+        }
+        sink.put(b"e");
+        sink.inner.finish()?;
+
+        return Ok(info_hash);
+    }
+
+    /// Like `write_encoded`, but sorting keys as `canonicalize` does and
+    /// leaving out the entries at the key paths in `omit`. A path such as
+    /// `[b"info", b"pieces"]` lists the dictionary keys leading to the entry;
+    /// list indices are not part of it, so it applies to every element of a
+    /// list along the way, as in `find_strings`.
+    ///
+    /// Leaving out anything inside `info` changes the info-hash of the
+    /// output: it's meant for metadata-only copies, not for torrents to be
+    /// shared as the same swarm.
+    pub fn write_encoded_omitting<W: Write>(
+        &self,
+        writer: W,
+        omit: &[&[&[u8]]],
+    ) -> Result<(), BencodingError> {
+        let mut sink = WriteSink::new(writer);
+        encode_dict_omitting(&self.dict, &mut sink, &mut Vec::new(), omit);
+
+        return sink.finish();
+    }
+
+    /// The canonical encoding of a dictionary holding only the top-level
+    /// entries under `keys`, such as `[b"announce", b"info"]` for a torrent
+    /// without its comments and other extras. Keys that aren't in the
+    /// structure are ignored, and keys are sorted as `canonicalize` does.
+    ///
+    /// Keeping `info` as it is keeps the info-hash, so the output still
+    /// joins the same swarm; leaving it out gives data without any
+    /// info-hash at all.
+    pub fn encode_projection(&self, keys: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.put(b"d");
+        for key in encode_order(&self.dict, false) {
+            if keys.contains(&key.as_slice()) {
+                encode_string(key, &mut out);
+                encode_value(&self.dict[key], &mut out, false);
+            }
+        }
+        out.put(b"e");
+
+        return out;
+    }
+
+    /// The length in bytes of `encode`'s output, without encoding.
+    pub fn encoded_len(&self) -> usize {
+        return dict_len(&self.dict);
+    }
+
+    /// Writes the canonical encoding into `buf` and returns the number of
+    /// bytes written, or `Err(BufferTooSmall)` without writing anything if it
+    /// doesn't fit. Use `encoded_len` to size the buffer.
+    ///
+    /// No output buffer is allocated, although the keys of each dictionary
+    /// are still gathered in a temporary list to sort them.
+    pub fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, BencodingError> {
+        let needed = self.encoded_len();
+        if needed > buf.len() {
+            return Err(BencodingError::BufferTooSmall {
+                needed,
+                available: buf.len(),
+            });
+        }
+
+        let mut sink = SliceSink { buf, position: 0 };
+        encode_dict(&self.dict, &mut sink, false);
+
+        return Ok(sink.position);
+    }
+}
+
+/// With `stored_order`, dictionaries are encoded in the order the storage
+/// backend keeps, if it keeps one, rather than in key order.
+fn encode_value<S: Sink>(value: &BencodingValue, out: &mut S, stored_order: bool) {
+    match value {
+        BencodingValue::String(s) => encode_string(s, out),
+        BencodingValue::Integer(i) => {
+            let mut digits = [0; 20];
+            out.put(b"i");
+            if *i < 0 {
+                out.put(b"-");
+            }
+            out.put(decimal(i.unsigned_abs(), &mut digits));
+            out.put(b"e");
+        }
+        BencodingValue::Unsigned(u) => {
+            let mut digits = [0; 20];
+            out.put(b"i");
+            out.put(decimal(*u, &mut digits));
+            out.put(b"e");
+        }
+        BencodingValue::List(list) => {
+            out.put(b"l");
+            for value in list {
+                encode_value(value, out, stored_order);
+            }
+            out.put(b"e");
+        }
+        BencodingValue::Dict(dict) => encode_dict(dict, out, stored_order),
+    }
+}
+
+/// The string's bytes are passed to the sink in one piece, borrowed from the
+/// structure, so that the largest values such as `pieces` are never copied
+/// on their way out.
+fn encode_string<S: Sink>(s: &[u8], out: &mut S) {
+    let mut digits = [0; 20];
+    out.put(decimal(s.len() as u64, &mut digits));
+    out.put(b":");
+    out.put(s);
+}
+
+fn encode_dict<S: Sink>(dict: &BencodingDict, out: &mut S, stored_order: bool) {
+    out.put(b"d");
+    for key in encode_order(dict, stored_order) {
+        encode_string(key, out);
+        encode_value(&dict[key], out, stored_order);
+    }
+    out.put(b"e");
+}
+
+/// Encodes `dict`, found at `path`, canonically without the entries whose
+/// path is in `omit`.
+fn encode_dict_omitting<'a, S: Sink>(
+    dict: &'a BencodingDict,
+    out: &mut S,
+    path: &mut Vec<&'a [u8]>,
+    omit: &[&[&[u8]]],
+) {
+    out.put(b"d");
+    for key in encode_order(dict, false) {
+        path.push(key);
+        if !omit.contains(&path.as_slice()) {
+            encode_string(key, out);
+            encode_value_omitting(&dict[key], out, path, omit);
+        }
+        path.pop();
+    }
+    out.put(b"e");
+}
+
+fn encode_value_omitting<'a, S: Sink>(
+    value: &'a BencodingValue,
+    out: &mut S,
+    path: &mut Vec<&'a [u8]>,
+    omit: &[&[&[u8]]],
+) {
+    match value {
+        BencodingValue::Dict(dict) => encode_dict_omitting(dict, out, path, omit),
+        BencodingValue::List(list) => {
+            out.put(b"l");
+            for value in list {
+                encode_value_omitting(value, out, path, omit);
+            }
+            out.put(b"e");
+        }
+        _ => encode_value(value, out, false),
+    }
+}
+
+/// Keys are sorted, except that an `IndexMap` keeps its stored order with
+/// `stored_order`, so that `encode_stored_order` round-trips the input order.
+fn encode_order(dict: &BencodingDict, stored_order: bool) -> Vec<&Vec<u8>> {
+    let mut keys: Vec<&Vec<u8>> = dict.keys().collect();
+    if !(stored_order && cfg!(feature = "indexmap")) {
+        keys.sort();
+    }
+
+    return keys;
+}
+
+#[cfg(feature = "indexmap")]
+fn sort_dict(dict: &mut BencodingDict) {
+    dict.sort_keys();
+    for value in dict.values_mut() {
+        sort_value(value);
+    }
+}
+
+#[cfg(feature = "indexmap")]
+fn sort_value(value: &mut BencodingValue) {
+    match value {
+        BencodingValue::Dict(dict) => sort_dict(dict),
+        BencodingValue::List(list) => list.iter_mut().for_each(sort_value),
+        _ => {}
+    }
+}
+
+/// Formats `n` in decimal into the end of `buf`.
+fn decimal(mut n: u64, buf: &mut [u8; 20]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+
+    return &buf[start..];
+}
+
+fn decimal_len(mut n: u64) -> usize {
+    let mut len = 1;
+    while n >= 10 {
+        n /= 10;
+        len += 1;
+    }
+
+    return len;
+}
+
+fn string_len(s: &[u8]) -> usize {
+    return decimal_len(s.len() as u64) + 1 + s.len();
+}
+
+fn integer_len(i: i64) -> usize {
+    let sign = if i < 0 { 1 } else { 0 };
+
+    return 2 + sign + decimal_len(i.unsigned_abs());
+}
+
+fn dict_len(dict: &BencodingDict) -> usize {
+    return 2 + dict
+        .iter()
+        .map(|(key, value)| string_len(key) + value.encoded_len())
+        .sum::<usize>();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+    #[test]
+    fn encode_sorts_dict_keys() {
+        let parser = Bencoding::decode(b"d4:spaml1:a1:be3:cow3:mooe").unwrap();
+        assert_eq!(parser.encode(), b"d3:cow3:moo4:spaml1:a1:bee");
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn encode_stored_order_preserves_input_order() {
+        let data = b"d4:spaml1:a1:be3:cowd1:zi1e1:yi2eee";
+        let parser = Bencoding::decode(data).unwrap();
+        assert_eq!(parser.encode_stored_order(), data);
+        assert_eq!(parser.encode(), b"d3:cowd1:yi2e1:zi1ee4:spaml1:a1:bee");
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn iter_follows_input_order() {
+        let parser = Bencoding::decode(b"d1:bi1e1:ci2e1:ai3ee").unwrap();
+        let keys: Vec<&[u8]> = parser.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![b"b".as_slice(), b"c", b"a"]);
+    }
+
+    #[test]
+    fn canonicalize_sorts_keys() {
+        let parser = Bencoding::decode(b"d1:bd1:zi1e1:yi2ee1:al1:dd1:fi1e1:ei2eeee").unwrap();
+        assert_eq!(
+            parser.canonicalize(),
+            b"d1:al1:dd1:ei2e1:fi1eee1:bd1:yi2e1:zi1eee"
+        );
+    }
+
+    #[test]
+    fn canonicalize_in_place_makes_encode_canonical() {
+        let mut parser = Bencoding::decode(b"d1:bd1:zi1e1:yi2ee1:al1:dd1:fi1e1:ei2eeee").unwrap();
+        parser.canonicalize_in_place();
+        assert_eq!(parser.encode(), parser.canonicalize());
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn canonicalize_in_place_reorders_index_map() {
+        let mut parser = Bencoding::decode(b"d1:bi1e1:ai2ee").unwrap();
+        assert_eq!(parser.encode_stored_order(), b"d1:bi1e1:ai2ee");
+
+        parser.canonicalize_in_place();
+        let keys: Vec<&[u8]> = parser.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![b"a".as_slice(), b"b"]);
+    }
+
+    #[test]
+    fn encode_keeps_non_standard_keys() {
+        let data = b"d8:announce3:url4:infod4:name1:a6:x-hashi1e13:x-private-tagd1:zleee10:x-amz-date16:20240101T000000Z13:x-amz-meta-id3:\x00\xFF\x019:\xC3\xA9t\xC3\xA9 keyi-1ee";
+        let parser = Bencoding::decode(data).unwrap();
+        assert_eq!(parser.encode(), data);
+    }
+
+    #[test]
+    fn encode_negative_integer() {
+        assert_eq!(BencodingValue::Integer(-18).encode(), b"i-18e");
+    }
+
+    #[test]
+    fn encode_zero_is_minimal() {
+        let mut dict = BencodingDict::new();
+        dict.insert(b"a".to_vec(), BencodingValue::Integer(0));
+        assert_eq!(BencodingValue::Dict(dict).encode(), b"d1:ai0ee");
+    }
+
+    #[test]
+    fn encode_shrinks_leniently_decoded_integers() {
+        let parser = Bencoding::decode(b"d1:ai-0e1:bi007e1:ci+3e1:di-01ee").unwrap();
+        assert!(parser.assert_canonical().is_err());
+        assert_eq!(parser.encode(), b"d1:ai0e1:bi7e1:ci3e1:di-1ee");
+    }
+
+    #[test]
+    fn encode_extreme_integers() {
+        assert_eq!(
+            BencodingValue::Integer(i64::MIN).encode(),
+            b"i-9223372036854775808e"
+        );
+        assert_eq!(
+            BencodingValue::Integer(i64::MAX).encode(),
+            b"i9223372036854775807e"
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "indexmap"))]
+    fn encode_nested_dict_is_canonical() {
+        let parser = Bencoding::decode(b"d1:bd1:zi1e1:yi2ee1:a0:e").unwrap();
+        assert_eq!(parser.encode(), b"d1:a0:1:bd1:yi2e1:zi1eee");
+    }
+
+    const KEYS: [&[u8]; 5] = [b"name", b"length", b"piece length", b"pieces", b"files"];
+
+    /// A structure with `keys` inserted in the given order, at the top level
+    /// and in a nested dictionary under each of them.
+    fn dict_in_order(keys: &[&[u8]]) -> Bencoding {
+        let mut dict = BencodingDict::new();
+        for key in keys {
+            let mut nested = BencodingDict::new();
+            for other in keys {
+                nested.insert(other.to_vec(), BencodingValue::Integer(other.len() as i64));
+            }
+            dict.insert(key.to_vec(), BencodingValue::Dict(nested));
+        }
+
+        return Bencoding {
+            dict,
+            non_canonical: None,
+        };
+    }
+
+    #[test]
+    fn encode_is_deterministic_across_runs() {
+        let parser = dict_in_order(&KEYS);
+        let first = parser.encode();
+        for _ in 0..100 {
+            assert_eq!(parser.encode(), first);
+            // Each new map hashes its keys differently.
+            assert_eq!(dict_in_order(&KEYS).encode(), first);
+        }
+    }
+
+    #[test]
+    fn encode_is_independent_of_insertion_order() {
+        let mut reversed = KEYS;
+        reversed.reverse();
+        assert_eq!(
+            dict_in_order(&KEYS).encode(),
+            dict_in_order(&reversed).encode()
+        );
+    }
+
+    #[test]
+    fn canonicalize_is_independent_of_insertion_order() {
+        let mut reversed = KEYS;
+        reversed.reverse();
+        assert_eq!(
+            dict_in_order(&KEYS).canonicalize(),
+            dict_in_order(&reversed).canonicalize()
+        );
+    }
+
+    #[test]
+    fn encoded_len_matches_encode() {
+        let parser = Bencoding::decode(b"d1:ai-100e1:bl0:i0ei10ee1:cd10:0123456789i9eee").unwrap();
+        assert_eq!(parser.encoded_len(), parser.encode().len());
+    }
+
+    #[test]
+    fn encode_into_slice_writes_canonical_encoding() {
+        let parser = Bencoding::decode(b"d3:cow3:moo4:spaml1:a1:bee").unwrap();
+        let mut buf = [0; 64];
+        let written = parser.encode_into_slice(&mut buf).unwrap();
+
+        assert_eq!(written, 26);
+        assert_eq!(&buf[..written], b"d3:cow3:moo4:spaml1:a1:bee");
+    }
+
+    #[test]
+    fn encode_into_slice_exact_size() {
+        let parser = Bencoding::decode(b"d3:cow3:mooe").unwrap();
+        let mut buf = [0; 12];
+        assert_eq!(parser.encode_into_slice(&mut buf).unwrap(), 12);
+    }
+
+    #[test]
+    fn encode_into_slice_too_small_is_error() {
+        let parser = Bencoding::decode(b"d3:cow3:mooe").unwrap();
+        let mut buf = [0; 11];
+        let result = parser.encode_into_slice(&mut buf);
+
+        assert!(matches!(
+            result,
+            Err(BencodingError::BufferTooSmall {
+                needed: 12,
+                available: 11
+            })
+        ));
+        assert_eq!(buf, [0; 11]);
+    }
+
+    #[test]
+    fn write_encoded_matches_encode() {
+        let parser = Bencoding::decode(b"d3:cow3:moo4:spaml1:a1:bee").unwrap();
+        let mut out = Vec::new();
+        parser.write_encoded(&mut out).unwrap();
+        assert_eq!(out, parser.encode());
+    }
+
+    #[test]
+    fn write_encoded_omitting_skips_paths() {
+        let parser = Bencoding::decode(
+            b"d8:announce3:url4:infod5:filesld6:lengthi1e6:md5sum1:xee4:name1:a6:pieces2:xxee",
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        parser
+            .write_encoded_omitting(
+                &mut out,
+                &[
+                    &[b"announce"],
+                    &[b"info", b"pieces"],
+                    &[b"info", b"files", b"md5sum"],
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(out, b"d4:infod5:filesld6:lengthi1eee4:name1:aee");
+    }
+
+    #[test]
+    fn encode_projection_keeps_only_named_keys() {
+        let parser = Bencoding::decode(
+            b"d8:announce3:url7:comment2:hi10:created byi1e4:infod4:name1:a6:pieces2:xxee",
+        )
+        .unwrap();
+        let projection = parser.encode_projection(&[b"info", b"announce", b"missing"]);
+
+        assert_eq!(
+            projection,
+            b"d8:announce3:url4:infod4:name1:a6:pieces2:xxee"
+        );
+        #[cfg(feature = "sha1")]
+        assert_eq!(
+            Bencoding::decode(&projection).unwrap().info_hash(),
+            parser.info_hash()
+        );
+        assert_eq!(parser.encode_projection(&[]), b"de");
+    }
+
+    #[test]
+    #[cfg(feature = "indexmap")]
+    fn encode_projection_sorts_keys() {
+        let parser = Bencoding::decode(b"d1:bd1:yi1e1:xi2ee1:ai3e1:ci4ee").unwrap();
+        assert_eq!(
+            parser.encode_projection(&[b"b", b"a"]),
+            b"d1:ai3e1:bd1:xi2e1:yi1eee"
+        );
+    }
+
+    #[test]
+    fn write_encoded_reports_write_errors() {
+        let parser = Bencoding::decode(b"d3:cow3:mooe").unwrap();
+        let mut buf = [0; 4];
+        let result = parser.write_encoded(buf.as_mut_slice());
+        assert!(matches!(result, Err(BencodingError::Io(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn write_encoded_with_info_hash_matches_two_passes() {
+        let parser =
+            Bencoding::decode(b"d8:announce3:url4:infod4:name1:a6:pieces2:xxe1:zi1ee").unwrap();
+        let mut out = Vec::new();
+        let info_hash = parser.write_encoded_with_info_hash(&mut out).unwrap();
+
+        assert_eq!(out, parser.encode());
+        assert_eq!(info_hash, parser.info_hash());
+        assert!(info_hash.is_some());
+    }
+
+    /// Compares the single pass with encoding then hashing `info` on a
+    /// torrent of 50,000 pieces, printing both timings; run with
+    /// `--nocapture` and `--release` to see them. Only the hashes are
+    /// asserted, as timings vary between machines.
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn write_encoded_with_info_hash_of_large_torrent() {
+        let mut info = BencodingDict::new();
+        info.insert(b"length".to_vec(), BencodingValue::Integer(50_000 << 18));
+        info.insert(b"name".to_vec(), BencodingValue::String(b"big".to_vec()));
+        info.insert(b"piece length".to_vec(), BencodingValue::Integer(1 << 18));
+        let pieces = (0..50_000u32 * 20).map(|i| (i % 251) as u8).collect();
+        info.insert(b"pieces".to_vec(), BencodingValue::String(pieces));
+        let mut dict = BencodingDict::new();
+        dict.insert(b"info".to_vec(), BencodingValue::Dict(info));
+        let parser = Bencoding {
+            dict,
+            non_canonical: None,
+        };
+
+        let start = std::time::Instant::now();
+        let single_pass = parser
+            .write_encoded_with_info_hash(std::io::sink())
+            .unwrap();
+        let single_pass_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        parser.write_encoded(std::io::sink()).unwrap();
+        let two_passes = parser.info_hash();
+        let two_passes_time = start.elapsed();
+
+        eprintln!(
+            "single pass: {:?}, encode then info_hash: {:?}",
+            single_pass_time, two_passes_time
+        );
+        assert_eq!(single_pass, two_passes);
+        assert!(single_pass.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn write_encoded_with_info_hash_without_info() {
+        let parser = Bencoding::decode(b"d3:cow3:mooe").unwrap();
+        let mut out = Vec::new();
+        assert_eq!(parser.write_encoded_with_info_hash(&mut out).unwrap(), None);
+        assert_eq!(out, b"d3:cow3:mooe");
+    }
+
+    /// Torrents of every shape the parser is expected to handle, all in
+    /// canonical form so that a lossless round trip gives back the same bytes.
+    const CORPUS: &[(&str, &[u8])] = &[
+        (
+            "v1_single",
+            include_bytes!("../../testdata/v1_single.torrent"),
+        ),
+        (
+            "v1_multi",
+            include_bytes!("../../testdata/v1_multi.torrent"),
+        ),
+        (
+            "v1_private",
+            include_bytes!("../../testdata/v1_private.torrent"),
+        ),
+        ("v2", include_bytes!("../../testdata/v2.torrent")),
+        ("hybrid", include_bytes!("../../testdata/hybrid.torrent")),
+        (
+            "unknown_nested",
+            include_bytes!("../../testdata/unknown_nested.torrent"),
+        ),
+    ];
+
+    #[test]
+    fn corpus_round_trips_byte_for_byte() {
+        for (name, data) in CORPUS {
+            let parser = Bencoding::decode(data).unwrap();
+            assert_eq!(parser.encode(), *data, "{name}");
+            assert_eq!(parser.canonicalize(), *data, "{name}");
+            assert_eq!(parser.encoded_len(), data.len(), "{name}");
+
+            let mut written = Vec::new();
+            parser.write_encoded(&mut written).unwrap();
+            assert_eq!(written, *data, "{name}");
+        }
+    }
+
+    #[test]
+    fn corpus_round_trips_structurally() {
+        for (name, data) in CORPUS {
+            let parser = Bencoding::decode(data).unwrap();
+            let reparsed = Bencoding::decode(&parser.encode()).unwrap();
+
+            assert_eq!(
+                reparsed.canonical_text_dump(),
+                parser.canonical_text_dump(),
+                "{name}"
+            );
+            assert_eq!(reparsed.all_paths(), parser.all_paths(), "{name}");
+            assert_eq!(reparsed.type_histogram(), parser.type_histogram(), "{name}");
+            assert_eq!(reparsed.files(), parser.files(), "{name}");
+            assert_eq!(reparsed.is_private(), parser.is_private(), "{name}");
+            assert_eq!(reparsed.meta_version(), parser.meta_version(), "{name}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn corpus_round_trip_keeps_info_hash() {
+        for (name, data) in CORPUS {
+            let parser = Bencoding::decode(data).unwrap();
+            let reparsed = Bencoding::decode(&parser.encode()).unwrap();
+            assert!(parser.info_hash().is_some(), "{name}");
+            assert_eq!(reparsed.info_hash(), parser.info_hash(), "{name}");
+        }
+    }
+
+    /// A torrent whose `pieces` makes up nearly all of its encoding.
+    fn piece_heavy_torrent() -> Vec<u8> {
+        let pieces: Vec<u8> = (0..20 * 4096).map(|i| (i % 251) as u8).collect();
+        let mut data =
+            b"d4:infod6:lengthi268435456e4:name1:a12:piece lengthi65536e6:pieces81920:".to_vec();
+        data.extend_from_slice(&pieces);
+        data.extend_from_slice(b"ee");
+
+        return data;
+    }
+
+    #[test]
+    fn encode_of_piece_heavy_torrent_allocates_output_once() {
+        let data = piece_heavy_torrent();
+        let parser = Bencoding::decode(&data).unwrap();
+        let encoded = parser.encode();
+
+        assert_eq!(encoded, data);
+        assert_eq!(encoded.capacity(), data.len());
+    }
+
+    #[test]
+    fn write_encoded_writes_pieces_in_a_single_write() {
+        /// Records the size of every write.
+        struct Recorder(Vec<usize>, Vec<u8>);
+
+        impl std::io::Write for Recorder {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.len());
+                self.1.extend_from_slice(buf);
+                return Ok(buf.len());
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                return Ok(());
+            }
+        }
+
+        let data = piece_heavy_torrent();
+        let parser = Bencoding::decode(&data).unwrap();
+        let mut recorder = Recorder(Vec::new(), Vec::new());
+        parser.write_encoded(&mut recorder).unwrap();
+
+        assert_eq!(recorder.1, data);
+        assert_eq!(recorder.0.iter().filter(|&&size| size == 81920).count(), 1);
+        assert!(recorder.0.iter().all(|&size| size == 81920 || size < 64));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use super::Bencoding;
+
+/// The size of the pieces the info dictionary is exchanged in (BEP 9).
+const METADATA_PIECE_SIZE: u64 = 16384;
+
+/// The BEP 10 extension protocol handshake, read by
+/// `Bencoding::extension_handshake`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionHandshake {
+    /// The extension message ids the sender supports, from `m`, by extension
+    /// name (such as `ut_metadata` or `ut_holepunch`). An id of 0 means the
+    /// sender disabled the extension.
+    pub extensions: BTreeMap<Vec<u8>, i64>,
+    /// The client name and version, from `v`.
+    pub client: Option<Vec<u8>>,
+    /// The sender's listening port, from `p`.
+    pub port: Option<u16>,
+    /// The number of outstanding requests the sender supports, from `reqq`.
+    pub request_queue: Option<i64>,
+    /// The size of the info dictionary, from `metadata_size` (BEP 9). A
+    /// size that isn't positive is `None`, as there would be nothing to
+    /// request.
+    pub metadata_size: Option<i64>,
+    /// The receiver's address as seen by the sender, from `yourip`: 4 bytes
+    /// for IPv4 or 16 for IPv6.
+    pub your_ip: Option<Vec<u8>>,
+}
+
+impl Bencoding {
+    /// Reads the dictionary as an extension handshake.
+    ///
+    /// Only `m` is required; other fields that are missing, of the wrong type
+    /// or out of range are `None`, and `m` entries that aren't integers are
+    /// skipped. Returns `None` if `m` is missing or isn't a dictionary.
+    pub fn extension_handshake(&self) -> Option<ExtensionHandshake> {
+        let extensions = self
+            .get_dict(b"m")?
+            .iter()
+            .filter_map(|(name, id)| Some((name.clone(), id.as_integer()?)))
+            .collect();
+
+        return Some(ExtensionHandshake {
+            extensions,
+            client: self.get_string(b"v").map(<[u8]>::to_vec),
+            port: self.get_integer(b"p").and_then(|p| u16::try_from(p).ok()),
+            request_queue: self.get_integer(b"reqq"),
+            metadata_size: self.get_integer(b"metadata_size").filter(|&size| size > 0),
+            your_ip: self
+                .get_string(b"yourip")
+                .filter(|ip| ip.len() == 4 || ip.len() == 16)
+                .map(<[u8]>::to_vec),
+        });
+    }
+}
+
+impl ExtensionHandshake {
+    /// The message id the sender uses for the extension `name`, or `None` if
+    /// it doesn't support it or disabled it.
+    pub fn extension_id(&self, name: &[u8]) -> Option<i64> {
+        return self.extensions.get(name).copied().filter(|&id| id != 0);
+    }
+
+    /// The size in bytes of the info dictionary the sender offers with
+    /// `ut_metadata` (BEP 9), or `None` if it didn't send a positive
+    /// `metadata_size`.
+    pub fn metadata_size(&self) -> Option<i64> {
+        return self.metadata_size;
+    }
+
+    /// The number of 16 KiB pieces to request with `ut_metadata` (BEP 9) to
+    /// get the whole info dictionary, from `metadata_size`. The last piece
+    /// may be shorter.
+    pub fn metadata_piece_count(&self) -> Option<usize> {
+        let size = self.metadata_size()?;
+
+        return usize::try_from((size as u64).div_ceil(METADATA_PIECE_SIZE)).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::Bencoding;
+
+    #[test]
+    fn extension_handshake_fields() {
+        let parser = Bencoding::decode(
+            b"d1:md11:ut_metadatai3e6:ut_pexi0e12:ut_holepunchi4ee13:metadata_sizei31235e1:pi6881e4:reqqi500e1:v15:qBittorrent 4.66:yourip4:\x0A\x00\x00\x01e",
+        )
+        .unwrap();
+        let handshake = parser.extension_handshake().unwrap();
+
+        assert_eq!(handshake.extension_id(b"ut_metadata"), Some(3));
+        assert_eq!(handshake.extension_id(b"ut_holepunch"), Some(4));
+        assert_eq!(handshake.extension_id(b"ut_pex"), None);
+        assert_eq!(handshake.extensions.len(), 3);
+        assert_eq!(handshake.client, Some(b"qBittorrent 4.6".to_vec()));
+        assert_eq!(handshake.port, Some(6881));
+        assert_eq!(handshake.request_queue, Some(500));
+        assert_eq!(handshake.metadata_size, Some(31235));
+        assert_eq!(handshake.your_ip, Some(vec![10, 0, 0, 1]));
+    }
+
+    #[test]
+    fn metadata_size_of_ut_metadata_handshake() {
+        let parser = Bencoding::decode(
+            b"d1:ei0e1:md11:lt_donthavei7e10:share_modei8e11:upload_onlyi3e12:ut_holepunchi4e11:ut_metadatai2e6:ut_pexi1ee13:metadata_sizei45282e1:pi51413e4:reqqi512e1:v17:Transmission 4.0.6:yourip4:\xC0\xA8\x01\x02e",
+        )
+        .unwrap();
+        let handshake = parser.extension_handshake().unwrap();
+
+        assert_eq!(handshake.extension_id(b"ut_metadata"), Some(2));
+        assert_eq!(handshake.metadata_size(), Some(45282));
+        assert_eq!(handshake.metadata_piece_count(), Some(3));
+    }
+
+    #[test]
+    fn metadata_piece_count_at_piece_boundary() {
+        let handshake = Bencoding::decode(b"d1:mde13:metadata_sizei32768ee")
+            .unwrap()
+            .extension_handshake()
+            .unwrap();
+        assert_eq!(handshake.metadata_piece_count(), Some(2));
+
+        let handshake = Bencoding::decode(b"d1:mde13:metadata_sizei0ee")
+            .unwrap()
+            .extension_handshake()
+            .unwrap();
+        assert_eq!(handshake.metadata_size(), None);
+        assert_eq!(handshake.metadata_piece_count(), None);
+    }
+
+    #[test]
+    fn extension_handshake_with_only_m() {
+        let handshake = Bencoding::decode(b"d1:mde1:pi70000ee")
+            .unwrap()
+            .extension_handshake()
+            .unwrap();
+        assert!(handshake.extensions.is_empty());
+        assert_eq!(handshake.port, None);
+        assert_eq!(handshake.client, None);
+    }
+
+    #[test]
+    fn extension_handshake_without_m_is_none() {
+        let parser = Bencoding::decode(b"d1:pi6881ee").unwrap();
+        assert!(parser.extension_handshake().is_none());
+    }
+}
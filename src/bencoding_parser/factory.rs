@@ -0,0 +1,136 @@
+use super::{BencodingError, DecodeOptions, Decoder};
+
+/// Builds values of the caller's own type while decoding, for
+/// `decode_with_factory`.
+///
+/// Each method is called once the corresponding value has been decoded, with
+/// its contents: lists and dictionaries get their elements already built, so
+/// values are built bottom-up, children before their parents.
+pub trait ValueFactory {
+    /// The type the factory builds.
+    type Value;
+
+    fn string(&mut self, s: &[u8]) -> Self::Value;
+
+    fn integer(&mut self, i: i64) -> Self::Value;
+
+    fn list(&mut self, elements: Vec<Self::Value>) -> Self::Value;
+
+    /// The entries are in input order, including any duplicate or unsorted
+    /// keys.
+    fn dict(&mut self, entries: Vec<(Vec<u8>, Self::Value)>) -> Self::Value;
+}
+
+/// Decodes the bencoded value at the start of `data` into the factory's own
+/// type, without building `BencodingValue`s along the way.
+///
+/// Unlike `Bencoding::decode` the value can be of any type, and bytes after
+/// the value are ignored.
+pub fn decode_with_factory<F: ValueFactory>(
+    data: &[u8],
+    factory: &mut F,
+) -> Result<F::Value, BencodingError> {
+    let options = DecodeOptions::default();
+    let mut decoder = Decoder::new(data, &options);
+
+    return decoder.build_next(factory);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{decode_with_factory, BencodingError, ValueFactory};
+
+    /// A file entry of a torrent, built straight from the decoder.
+    #[derive(Debug, PartialEq)]
+    struct File {
+        path: String,
+        length: i64,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Node {
+        Text(String),
+        Number(i64),
+        File(File),
+        Files(Vec<File>),
+        Other,
+    }
+
+    /// Builds `File`s from dictionaries with a `length` and a `path`, and a
+    /// list of them from lists. Everything else is dropped.
+    struct FileFactory;
+
+    impl ValueFactory for FileFactory {
+        type Value = Node;
+
+        fn string(&mut self, s: &[u8]) -> Node {
+            return Node::Text(String::from_utf8_lossy(s).into_owned());
+        }
+
+        fn integer(&mut self, i: i64) -> Node {
+            return Node::Number(i);
+        }
+
+        fn list(&mut self, elements: Vec<Node>) -> Node {
+            let files = elements
+                .into_iter()
+                .filter_map(|element| match element {
+                    Node::File(file) => Some(file),
+                    _ => None,
+                })
+                .collect();
+            return Node::Files(files);
+        }
+
+        fn dict(&mut self, entries: Vec<(Vec<u8>, Node)>) -> Node {
+            let mut path = None;
+            let mut length = None;
+            for (key, value) in entries {
+                match (key.as_slice(), value) {
+                    (b"path", Node::Text(text)) => path = Some(text),
+                    (b"length", Node::Number(number)) => length = Some(number),
+                    _ => {}
+                }
+            }
+            match (path, length) {
+                (Some(path), Some(length)) => return Node::File(File { path, length }),
+                _ => return Node::Other,
+            }
+        }
+    }
+
+    #[test]
+    fn decode_with_factory_builds_custom_types() {
+        let value = decode_with_factory(
+            b"ld6:lengthi5e4:path5:a.txted4:path5:b.txt6:lengthi7eei1ee",
+            &mut FileFactory,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            Node::Files(vec![
+                File {
+                    path: String::from("a.txt"),
+                    length: 5
+                },
+                File {
+                    path: String::from("b.txt"),
+                    length: 7
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_with_factory_scalar_and_errors() {
+        assert_eq!(
+            decode_with_factory(b"i42e", &mut FileFactory).unwrap(),
+            Node::Number(42)
+        );
+        assert!(matches!(
+            decode_with_factory(b"d4:pathi1e", &mut FileFactory),
+            Err(BencodingError::UnexpectedEof)
+        ));
+    }
+}
@@ -0,0 +1,490 @@
+use std::ops::RangeInclusive;
+
+use super::{Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+/// The type of a `BencodingValue`, without its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BencodingType {
+    String,
+    Integer,
+    List,
+    Dict,
+}
+
+impl BencodingValue {
+    /// The type of the value.
+    pub fn value_type(&self) -> BencodingType {
+        match self {
+            BencodingValue::String(_) => return BencodingType::String,
+            BencodingValue::Integer(_) | BencodingValue::Unsigned(_) => {
+                return BencodingType::Integer
+            }
+            BencodingValue::List(_) => return BencodingType::List,
+            BencodingValue::Dict(_) => return BencodingType::Dict,
+        }
+    }
+
+    /// The name of the value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            BencodingValue::String(_) => return "string",
+            BencodingValue::Integer(_) => return "integer",
+            BencodingValue::Unsigned(_) => return "unsigned integer",
+            BencodingValue::List(_) => return "list",
+            BencodingValue::Dict(_) => return "dictionary",
+        }
+    }
+}
+
+/// The integer elements of a list, read lazily without allocating, for
+/// callers that only fold over them. It's the borrowing counterpart of
+/// `Bencoding::get_integer_list`.
+///
+/// Only the integers are yielded: elements of other types are skipped, as
+/// with `get_integer_list`'s `skip_non_integers`. Returns `None` if `value`
+/// isn't a list.
+pub fn integers_borrowed(value: &BencodingValue) -> Option<impl Iterator<Item = i64> + '_> {
+    return Some(
+        value
+            .as_list()?
+            .iter()
+            .filter_map(BencodingValue::as_integer),
+    );
+}
+
+impl Bencoding {
+    /// The string under `key`, or `None` if it's missing or not a string.
+    pub fn get_string(&self, key: &[u8]) -> Option<&[u8]> {
+        return self.dict.get(key)?.as_string();
+    }
+
+    /// The integer under `key`, or `None` if it's missing or not an integer.
+    pub fn get_integer(&self, key: &[u8]) -> Option<i64> {
+        return self.dict.get(key)?.as_integer();
+    }
+
+    /// The integer under `key` as an unsigned counter, such as the
+    /// `uploaded` total of resume data. Negative values are rejected as
+    /// `None` rather than wrapped, as are missing keys and other types.
+    ///
+    /// Values above `i64::MAX` are only decoded with
+    /// `DecodeOptions::allow_u64_integers`; without it they fail to decode.
+    pub fn get_u64(&self, key: &[u8]) -> Option<u64> {
+        return self.dict.get(key)?.as_u64();
+    }
+
+    /// The list under `key`, or `None` if it's missing or not a list.
+    pub fn get_list(&self, key: &[u8]) -> Option<&Vec<BencodingValue>> {
+        return self.dict.get(key)?.as_list();
+    }
+
+    /// The dictionary under `key`, or `None` if it's missing or not a
+    /// dictionary.
+    pub fn get_dict(&self, key: &[u8]) -> Option<&BencodingDict> {
+        return self.dict.get(key)?.as_dict();
+    }
+
+    /// A fully owned deep copy of the value under `key`, sharing nothing
+    /// with the structure: changing it, down to its innermost lists and
+    /// dictionaries, leaves the parser as it was, and it outlives the
+    /// parser. This is what `get` returns already; the name makes the intent
+    /// explicit where the borrowing getters would otherwise do.
+    pub fn get_owned(&self, key: &[u8]) -> Option<BencodingValue> {
+        return self.dict.get(key).cloned();
+    }
+
+    /// The top-level entries whose key starts with `prefix`, in sorted key
+    /// order, for families of namespaced keys such as BEP 36 feed fields or
+    /// `x-` client extensions.
+    pub fn get_by_prefix(&self, prefix: &[u8]) -> Vec<(&[u8], &BencodingValue)> {
+        let mut entries: Vec<(&[u8], &BencodingValue)> = self
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .collect();
+        entries.sort_by_key(|&(key, _)| key);
+
+        return entries;
+    }
+
+    /// The integer elements of the list under `key`, such as an availability
+    /// map.
+    ///
+    /// With `skip_non_integers`, elements of other types are left out.
+    /// Otherwise any such element makes the whole result `None`, as does a
+    /// missing key or a value that isn't a list.
+    pub fn get_integer_list(&self, key: &[u8], skip_non_integers: bool) -> Option<Vec<i64>> {
+        let list = self.get_list(key)?;
+        if skip_non_integers {
+            return Some(list.iter().filter_map(BencodingValue::as_integer).collect());
+        }
+
+        return list.iter().map(BencodingValue::as_integer).collect();
+    }
+
+    /// The string elements of the list under `key`, such as a flat list of
+    /// file names or URLs, borrowed from the structure.
+    ///
+    /// Non-string elements are handled as in `get_integer_list`: left out with
+    /// `skip_non_strings`, and otherwise making the whole result `None`.
+    pub fn get_string_list(&self, key: &[u8], skip_non_strings: bool) -> Option<Vec<&[u8]>> {
+        let list = self.get_list(key)?;
+        if skip_non_strings {
+            return Some(list.iter().filter_map(BencodingValue::as_string).collect());
+        }
+
+        return list.iter().map(BencodingValue::as_string).collect();
+    }
+
+    /// Decodes the string under `key` as a bencoded dictionary of its own, for
+    /// formats that wrap a whole torrent in a string field.
+    ///
+    /// Returns `None` if the key is missing or not a string, and `Some(Err)`
+    /// if the string isn't a valid bencoded dictionary.
+    pub fn decode_nested(&self, key: &[u8]) -> Option<Result<Bencoding, BencodingError>> {
+        return Some(Bencoding::decode(self.get_string(key)?));
+    }
+
+    /// Like `get_string`, but a value of another type is an
+    /// `Err(UnexpectedType)` naming the key rather than `None`.
+    pub fn get_string_checked(&self, key: &[u8]) -> Result<Option<&[u8]>, BencodingError> {
+        return self.get_checked(key, "string", BencodingValue::as_string);
+    }
+
+    /// Like `get_integer`, but a value of another type is an
+    /// `Err(UnexpectedType)` naming the key rather than `None`.
+    pub fn get_integer_checked(&self, key: &[u8]) -> Result<Option<i64>, BencodingError> {
+        return self.get_checked(key, "integer", BencodingValue::as_integer);
+    }
+
+    /// Like `get_u64`, but a value of another type or a negative integer is
+    /// an `Err(UnexpectedType)` naming the key rather than `None`.
+    pub fn get_u64_checked(&self, key: &[u8]) -> Result<Option<u64>, BencodingError> {
+        let integer = self.get_checked(key, "integer", |value| match value {
+            BencodingValue::Integer(_) | BencodingValue::Unsigned(_) => Some(value),
+            _ => None,
+        })?;
+        let value = match integer {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        match value.as_u64() {
+            Some(value) => return Ok(Some(value)),
+            None => {
+                return Err(BencodingError::UnexpectedType {
+                    key: String::from_utf8_lossy(key).into_owned(),
+                    expected: "non-negative integer",
+                    found: "negative integer",
+                })
+            }
+        }
+    }
+
+    /// Like `get_integer_checked`, but an integer outside `range`, such as a
+    /// port outside `1..=65535`, is also an error, `Err(OutOfRange)`.
+    pub fn get_integer_in_range(
+        &self,
+        key: &[u8],
+        range: RangeInclusive<i64>,
+    ) -> Result<Option<i64>, BencodingError> {
+        let value = match self.get_integer_checked(key)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if !range.contains(&value) {
+            return Err(BencodingError::OutOfRange {
+                key: String::from_utf8_lossy(key).into_owned(),
+                value,
+                min: *range.start(),
+                max: *range.end(),
+            });
+        }
+
+        return Ok(Some(value));
+    }
+
+    /// Like `get_list`, but a value of another type is an
+    /// `Err(UnexpectedType)` naming the key rather than `None`.
+    pub fn get_list_checked(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<&Vec<BencodingValue>>, BencodingError> {
+        return self.get_checked(key, "list", BencodingValue::as_list);
+    }
+
+    /// Like `get_dict`, but a value of another type is an
+    /// `Err(UnexpectedType)` naming the key rather than `None`.
+    pub fn get_dict_checked(&self, key: &[u8]) -> Result<Option<&BencodingDict>, BencodingError> {
+        return self.get_checked(key, "dictionary", BencodingValue::as_dict);
+    }
+
+    fn get_checked<'a, T>(
+        &'a self,
+        key: &[u8],
+        expected: &'static str,
+        cast: fn(&'a BencodingValue) -> Option<T>,
+    ) -> Result<Option<T>, BencodingError> {
+        let value = match self.dict.get(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        match cast(value) {
+            Some(value) => return Ok(Some(value)),
+            None => {
+                return Err(BencodingError::UnexpectedType {
+                    key: String::from_utf8_lossy(key).into_owned(),
+                    expected,
+                    found: value.type_name(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{
+        integers_borrowed, Bencoding, BencodingError, BencodingValue, DecodeOptions,
+    };
+
+    #[test]
+    fn get_typed_values() {
+        let parser = Bencoding::decode(b"d1:dde1:ii7e1:lle1:s3:fooe").unwrap();
+
+        assert_eq!(parser.get_string(b"s"), Some(b"foo".as_slice()));
+        assert_eq!(parser.get_integer(b"i"), Some(7));
+        assert!(parser.get_list(b"l").unwrap().is_empty());
+        assert!(parser.get_dict(b"d").unwrap().is_empty());
+    }
+
+    #[test]
+    fn get_typed_value_of_other_type_is_none() {
+        let parser = Bencoding::decode(b"d1:s3:fooe").unwrap();
+        assert_eq!(parser.get_integer(b"s"), None);
+        assert_eq!(parser.get_integer(b"missing"), None);
+    }
+
+    #[test]
+    fn get_by_prefix_returns_matching_entries() {
+        let parser =
+            Bencoding::decode(b"d9:rss:titlei1e8:announce3:url8:rss:link3:url3:rssi2e2:rsi3ee")
+                .unwrap();
+        let keys: Vec<&[u8]> = parser
+            .get_by_prefix(b"rss:")
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+
+        assert_eq!(keys, vec![b"rss:link".as_slice(), b"rss:title"]);
+        assert_eq!(parser.get_by_prefix(b"").len(), 5);
+        assert!(parser.get_by_prefix(b"x-").is_empty());
+    }
+
+    #[test]
+    fn get_u64_rejects_negative_values() {
+        let parser =
+            Bencoding::decode(b"d8:uploadedi9223372036854775807e4:lossi-1e1:s1:xe").unwrap();
+        assert_eq!(parser.get_u64(b"uploaded"), Some(i64::MAX as u64));
+        assert_eq!(parser.get_u64(b"loss"), None);
+        assert_eq!(parser.get_u64(b"s"), None);
+    }
+
+    #[test]
+    fn get_u64_above_i64_max_with_allow_u64_integers() {
+        let data = b"d8:uploadedi18446744073709551615e10:downloadedi7ee";
+        assert!(matches!(
+            Bencoding::decode(data),
+            Err(BencodingError::InvalidInteger { position: 12 })
+        ));
+
+        let options = DecodeOptions {
+            allow_u64_integers: true,
+            ..Default::default()
+        };
+        let parser = Bencoding::decode_with_options(data, &options).unwrap();
+        assert_eq!(parser.get_u64(b"uploaded"), Some(u64::MAX));
+        assert_eq!(parser.get_u64_checked(b"uploaded").unwrap(), Some(u64::MAX));
+        assert_eq!(parser.get_integer(b"uploaded"), None);
+        assert_eq!(parser.get_u64(b"downloaded"), Some(7));
+        assert_eq!(parser.get_integer(b"downloaded"), Some(7));
+        assert_eq!(
+            parser.encode(),
+            b"d10:downloadedi7e8:uploadedi18446744073709551615ee"
+        );
+    }
+
+    #[test]
+    fn get_u64_checked_negative_is_error() {
+        let parser = Bencoding::decode(b"d10:downloadedi-5e8:uploadedi7ee").unwrap();
+        assert_eq!(parser.get_u64_checked(b"uploaded").unwrap(), Some(7));
+        assert_eq!(
+            parser
+                .get_u64_checked(b"downloaded")
+                .unwrap_err()
+                .to_string(),
+            "expected non-negative integer for key 'downloaded', found negative integer"
+        );
+    }
+
+    #[test]
+    fn get_owned_is_independent_of_the_parser() {
+        let parser = Bencoding::decode(b"d1:ald1:bli1eeee1:ci2ee").unwrap();
+        let mut owned = parser.get_owned(b"a").unwrap();
+        match &mut owned {
+            BencodingValue::List(list) => match &mut list[0] {
+                BencodingValue::Dict(dict) => {
+                    dict.insert(b"z".to_vec(), BencodingValue::Integer(9));
+                    match dict.get_mut(b"b".as_slice()).unwrap() {
+                        BencodingValue::List(inner) => inner.clear(),
+                        _ => unreachable!(),
+                    }
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        assert_eq!(owned.encode(), b"ld1:ble1:zi9eee");
+        assert_eq!(parser.encode(), b"d1:ald1:bli1eeee1:ci2ee");
+        assert!(parser.get_owned(b"missing").is_none());
+    }
+
+    #[test]
+    fn integers_borrowed_skips_non_integers() {
+        let parser = Bencoding::decode(b"d1:ali1ei-4e1:xlei10ee1:bi3ee").unwrap();
+        let list = parser.get_owned(b"a").unwrap();
+
+        assert_eq!(integers_borrowed(&list).unwrap().sum::<i64>(), 7);
+        assert_eq!(
+            integers_borrowed(&list).unwrap().collect::<Vec<_>>(),
+            vec![1, -4, 10]
+        );
+        assert!(integers_borrowed(&parser.get_owned(b"b").unwrap()).is_none());
+    }
+
+    #[test]
+    fn get_integer_list_strict() {
+        let parser = Bencoding::decode(b"d1:ali1ei0ei-3ee1:bli1e1:xee").unwrap();
+        assert_eq!(parser.get_integer_list(b"a", false), Some(vec![1, 0, -3]));
+        assert_eq!(parser.get_integer_list(b"b", false), None);
+    }
+
+    #[test]
+    fn get_integer_list_skipping_non_integers() {
+        let parser = Bencoding::decode(b"d1:bli1e1:xlei2ee1:ci5ee").unwrap();
+        assert_eq!(parser.get_integer_list(b"b", true), Some(vec![1, 2]));
+        assert_eq!(parser.get_integer_list(b"c", true), None);
+    }
+
+    #[test]
+    fn get_string_list_of_strings() {
+        let parser = Bencoding::decode(b"d5:namesl5:a.txt0:5:b.txtee").unwrap();
+        assert_eq!(
+            parser.get_string_list(b"names", false),
+            Some(vec![b"a.txt".as_slice(), b"", b"b.txt"])
+        );
+    }
+
+    #[test]
+    fn get_string_list_with_non_strings() {
+        let parser = Bencoding::decode(b"d5:namesl5:a.txti1ed1:ai1eeee").unwrap();
+        assert_eq!(parser.get_string_list(b"names", false), None);
+        assert_eq!(
+            parser.get_string_list(b"names", true),
+            Some(vec![b"a.txt".as_slice()])
+        );
+    }
+
+    #[test]
+    fn decode_nested_torrent() {
+        let parser = Bencoding::decode(b"d7:torrent22:d4:infod4:name4:testeee").unwrap();
+        let nested = parser.decode_nested(b"torrent").unwrap().unwrap();
+        assert_eq!(nested.name(), Some(b"test".as_slice()));
+    }
+
+    #[test]
+    fn decode_nested_invalid_or_missing() {
+        let parser = Bencoding::decode(b"d1:ai1e7:torrent3:abce").unwrap();
+        assert!(parser.decode_nested(b"torrent").unwrap().is_err());
+        assert!(parser.decode_nested(b"a").is_none());
+        assert!(parser.decode_nested(b"missing").is_none());
+    }
+
+    #[test]
+    fn get_checked_missing_key_is_none() {
+        let parser = Bencoding::decode(b"de").unwrap();
+        assert_eq!(parser.get_integer_checked(b"piece length").unwrap(), None);
+    }
+
+    #[test]
+    fn get_checked_type_mismatch_names_key() {
+        let parser = Bencoding::decode(b"d12:piece length5:16384e").unwrap();
+        let error = parser.get_integer_checked(b"piece length").unwrap_err();
+
+        assert!(matches!(
+            &error,
+            BencodingError::UnexpectedType {
+                key,
+                expected: "integer",
+                found: "string",
+            } if key == "piece length"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "expected integer for key 'piece length', found string"
+        );
+    }
+
+    #[test]
+    fn get_checked_non_utf8_key_is_lossy() {
+        let parser = Bencoding::decode(b"d2:\xFFki1ee").unwrap();
+        let error = parser.get_dict_checked(b"\xFFk").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected dictionary for key '\u{FFFD}k', found integer"
+        );
+    }
+
+    #[test]
+    fn get_integer_in_range_accepts_boundaries() {
+        let parser = Bencoding::decode(b"d3:lowi1e4:highi65535ee").unwrap();
+        assert_eq!(
+            parser.get_integer_in_range(b"low", 1..=65535).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            parser.get_integer_in_range(b"high", 1..=65535).unwrap(),
+            Some(65535)
+        );
+        assert_eq!(
+            parser.get_integer_in_range(b"missing", 1..=65535).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn get_integer_in_range_rejects_values_outside() {
+        let parser = Bencoding::decode(b"d3:lowi0e4:highi65536e1:s1:xe").unwrap();
+        assert!(matches!(
+            parser.get_integer_in_range(b"low", 1..=65535),
+            Err(BencodingError::OutOfRange {
+                value: 0,
+                min: 1,
+                max: 65535,
+                ..
+            })
+        ));
+        assert_eq!(
+            parser
+                .get_integer_in_range(b"high", 1..=65535)
+                .unwrap_err()
+                .to_string(),
+            "value 65536 for key 'high' is outside 1..=65535"
+        );
+        assert!(matches!(
+            parser.get_integer_in_range(b"s", 1..=65535),
+            Err(BencodingError::UnexpectedType { .. })
+        ));
+    }
+}
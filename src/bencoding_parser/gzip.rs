@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use super::{Bencoding, BencodingError};
+
+impl Bencoding {
+    /// Decompresses gzip-compressed `data` and decodes the result, for
+    /// torrent archives that store their files compressed.
+    ///
+    /// Data that fails to decompress is `Err(Decompression)`. Requires the
+    /// `flate2` feature.
+    pub fn decode_gzip(data: &[u8]) -> Result<Self, BencodingError> {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(data)
+            .read_to_end(&mut decompressed)
+            .map_err(BencodingError::Decompression)?;
+
+        return Self::decode(&decompressed);
+    }
+
+    /// Reads the gzip-compressed file at `path` and decodes it like
+    /// `decode_gzip`. Failing to read the file is `Err(Io)`. Requires the
+    /// `flate2` feature.
+    pub fn from_gzip_file<P: AsRef<Path>>(path: P) -> Result<Self, BencodingError> {
+        let mut data = Vec::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut data))
+            .map_err(BencodingError::Io)?;
+
+        return Self::decode_gzip(&data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use crate::bencoding_parser::{Bencoding, BencodingError};
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        return encoder.finish().unwrap();
+    }
+
+    #[test]
+    fn decode_gzip_decompresses_first() {
+        let parser = Bencoding::decode_gzip(&compress(b"d4:infod4:name4:testee")).unwrap();
+        assert_eq!(parser.name(), Some(b"test".as_slice()));
+    }
+
+    #[test]
+    fn decode_gzip_invalid_data_is_decompression_error() {
+        let result = Bencoding::decode_gzip(b"d4:infod4:name4:testee");
+        assert!(matches!(result, Err(BencodingError::Decompression(_))));
+    }
+
+    #[test]
+    fn from_gzip_file_reads_and_decodes() {
+        let path = std::env::temp_dir().join(format!(
+            "bencoding_parser_gzip_{}.torrent.gz",
+            std::process::id()
+        ));
+        std::fs::write(&path, compress(b"d1:ai1ee")).unwrap();
+        let parser = Bencoding::from_gzip_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(parser.unwrap().get_integer(b"a"), Some(1));
+        assert!(matches!(
+            Bencoding::from_gzip_file(&path),
+            Err(BencodingError::Io(_))
+        ));
+    }
+}
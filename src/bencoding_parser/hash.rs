@@ -0,0 +1,271 @@
+#[cfg(feature = "sha1")]
+use sha1::{Digest, Sha1};
+
+use super::Bencoding;
+#[cfg(feature = "sha1")]
+use super::{BencodingDict, BencodingValue};
+
+/// The `info` fields that determine the content: its layout and piece hashes,
+/// v1 or v2.
+#[cfg(feature = "sha1")]
+const CONTENT_KEYS: [&[u8]; 5] = [
+    b"file tree",
+    b"files",
+    b"length",
+    b"piece length",
+    b"pieces",
+];
+
+impl Bencoding {
+    /// The encoding of the `info` dictionary, which is what the info-hash is
+    /// computed over.
+    ///
+    /// It's re-encoded canonically from the decoded structure, whatever the
+    /// storage backend, so it only matches the original bytes if those were
+    /// canonical (see `assert_canonical`).
+    pub fn encoded_info(&self) -> Option<Vec<u8>> {
+        return Some(self.dict.get(b"info".as_slice())?.encode());
+    }
+
+    /// The v1 info-hash: the SHA-1 of `encoded_info`.
+    #[cfg(feature = "sha1")]
+    pub fn info_hash(&self) -> Option<[u8; 20]> {
+        let info = self.encoded_info()?;
+
+        return Some(Sha1::digest(info).into());
+    }
+
+    /// Both `encoded_info` and `info_hash`, encoding `info` only once.
+    #[cfg(feature = "sha1")]
+    pub fn canonical_info_with_hash(&self) -> Option<(Vec<u8>, [u8; 20])> {
+        let info = self.encoded_info()?;
+        let hash = Sha1::digest(&info).into();
+
+        return Some((info, hash));
+    }
+
+    /// The v1 info-hash the torrent would have with `info.source` set to
+    /// `source`, for cross-seeding: trackers that require a `source` tag
+    /// give the same content a different info-hash, so finding a torrent's
+    /// copy on another tracker means hashing it with that tracker's tag.
+    ///
+    /// An existing `source` is overwritten. The structure itself is left
+    /// as it is; the changed `info` is a copy, encoded with its keys sorted
+    /// whatever the storage backend.
+    #[cfg(feature = "sha1")]
+    pub fn info_hash_with_source(&self, source: &str) -> Option<[u8; 20]> {
+        let mut info = self.info_dict()?.clone();
+        info.insert(
+            b"source".to_vec(),
+            BencodingValue::String(source.as_bytes().to_vec()),
+        );
+        let info = Bencoding {
+            dict: info,
+            non_canonical: None,
+        };
+
+        return Some(Sha1::digest(info.canonicalize()).into());
+    }
+
+    /// A SHA-1 over the content-identifying part of `info`, so that the same
+    /// content can be recognised across torrents with different trackers,
+    /// names or tags.
+    ///
+    /// It's the hash of the encoding of a dictionary holding only the `info`
+    /// fields `file tree` (with the v2 `pieces root` of each file), `files`
+    /// (the paths and lengths, which don't include the torrent's `name`),
+    /// `length`, `piece length` and `pieces`, those that are present. Every
+    /// other field, such as `name`, `private` or `source`, is left out. Keys
+    /// are sorted at every level whatever the storage backend, so the
+    /// fingerprint doesn't depend on the input's key order.
+    ///
+    /// Returns `None` if there is no `info`, or if it has none of
+    /// `file tree`, `files`, `length` and `pieces`, as there is no content
+    /// to identify. A hybrid torrent's fingerprint covers both its v1 and v2
+    /// fields, so it differs from that of the same content as v1 or v2 only.
+    #[cfg(feature = "sha1")]
+    pub fn content_fingerprint(&self) -> Option<[u8; 20]> {
+        let info = self.info_dict()?;
+        let mut content = BencodingDict::new();
+        for key in CONTENT_KEYS {
+            if let Some(value) = info.get(key) {
+                content.insert(key.to_vec(), value.clone());
+            }
+        }
+        if content.keys().all(|key| key == b"piece length") {
+            return None;
+        }
+        let content = Bencoding {
+            dict: content,
+            non_canonical: None,
+        };
+        let encoded = content.canonicalize();
+
+        return Some(Sha1::digest(encoded).into());
+    }
+}
+
+/// Lowercase hex, as used for info-hashes in magnet links.
+#[cfg(feature = "sha1")]
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push(DIGITS[(byte >> 4) as usize] as char);
+        out.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::Bencoding;
+
+    const TORRENT: &[u8] =
+        b"d8:announce3:url4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee";
+
+    #[test]
+    fn encoded_info_is_info_dict() {
+        let parser = Bencoding::decode(TORRENT).unwrap();
+        assert_eq!(
+            parser.encoded_info().unwrap(),
+            b"d6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:e"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn info_hash_of_info_dict() {
+        let parser = Bencoding::decode(TORRENT).unwrap();
+        assert_eq!(
+            super::hex(&parser.info_hash().unwrap()),
+            "fab7bc801b6070674f82ace84aa75ab5ff358221"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn canonical_info_with_hash_matches_separate_calls() {
+        let parser = Bencoding::decode(TORRENT).unwrap();
+        let (info, hash) = parser.canonical_info_with_hash().unwrap();
+
+        assert_eq!(info, parser.encoded_info().unwrap());
+        assert_eq!(hash, parser.info_hash().unwrap());
+        assert!(Bencoding::decode(b"de")
+            .unwrap()
+            .canonical_info_with_hash()
+            .is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn info_hash_of_unsorted_info_is_canonical() {
+        let parser =
+            Bencoding::decode(b"d4:infod6:pieces0:4:name1:a12:piece lengthi16e6:lengthi1eee")
+                .unwrap();
+
+        assert_eq!(
+            parser.encoded_info().unwrap(),
+            b"d6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:e"
+        );
+        assert_eq!(
+            super::hex(&parser.info_hash().unwrap()),
+            "fab7bc801b6070674f82ace84aa75ab5ff358221"
+        );
+    }
+
+    #[test]
+    fn encoded_info_without_info_is_none() {
+        let parser = Bencoding::decode(b"d8:announce3:urle").unwrap();
+        assert!(parser.encoded_info().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn content_fingerprint_ignores_metadata_fields() {
+        let plain = Bencoding::decode(TORRENT).unwrap();
+        let tagged = Bencoding::decode(
+            b"d8:announce5:other4:infod6:lengthi1e4:name5:other12:piece lengthi16e6:pieces0:7:privatei1e6:source3:srcee",
+        )
+        .unwrap();
+
+        assert_eq!(plain.content_fingerprint(), tagged.content_fingerprint());
+        assert_ne!(plain.info_hash(), tagged.info_hash());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn content_fingerprint_depends_on_content() {
+        let plain = Bencoding::decode(TORRENT).unwrap();
+        let longer =
+            Bencoding::decode(b"d4:infod6:lengthi2e4:name1:a12:piece lengthi16e6:pieces0:ee")
+                .unwrap();
+        assert_ne!(plain.content_fingerprint(), longer.content_fingerprint());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn content_fingerprint_of_v2_torrents_depends_on_file_tree() {
+        let first = Bencoding::decode(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1eee1:bd0:d6:lengthi2eeee12:meta versioni2e4:name1:x12:piece lengthi16384eee",
+        )
+        .unwrap();
+        let renamed = Bencoding::decode(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1eee1:bd0:d6:lengthi2eeee12:meta versioni2e4:name1:y12:piece lengthi16384eee",
+        )
+        .unwrap();
+        let other = Bencoding::decode(
+            b"d4:infod9:file treed1:ad0:d6:lengthi1eee1:cd0:d6:lengthi2eeee12:meta versioni2e4:name1:x12:piece lengthi16384eee",
+        )
+        .unwrap();
+
+        assert!(first.content_fingerprint().is_some());
+        assert_eq!(first.content_fingerprint(), renamed.content_fingerprint());
+        assert_ne!(first.content_fingerprint(), other.content_fingerprint());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn content_fingerprint_without_content_is_none() {
+        let parser = Bencoding::decode(b"d4:infod4:name1:a12:piece lengthi16384eee").unwrap();
+        assert_eq!(parser.content_fingerprint(), None);
+        assert_eq!(
+            Bencoding::decode(b"de").unwrap().content_fingerprint(),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn info_hash_with_source_for_cross_seeding() {
+        let tagged =
+            Bencoding::decode(include_bytes!("../../testdata/v1_private.torrent")).unwrap();
+        assert_eq!(
+            super::hex(&tagged.info_hash().unwrap()),
+            "1edb8910e5e74a954aacb2b373f89b1ed31a0024"
+        );
+        assert_eq!(tagged.info_hash_with_source("EXAMPLE"), tagged.info_hash());
+        assert_eq!(
+            super::hex(&tagged.info_hash_with_source("OPS").unwrap()),
+            "dff170733ccac05d973e0ed58e3c6d2f56fe88cf"
+        );
+        assert_eq!(
+            tagged.get_dict(b"info").unwrap()[b"source".as_slice()].as_string(),
+            Some(b"EXAMPLE".as_slice())
+        );
+
+        let untagged =
+            Bencoding::decode(include_bytes!("../../testdata/v1_single.torrent")).unwrap();
+        assert_eq!(
+            super::hex(&untagged.info_hash_with_source("OPS").unwrap()),
+            "a43c4d64f38c24b5d691e185f10e86365a2aac39"
+        );
+        assert_eq!(
+            Bencoding::decode(b"de")
+                .unwrap()
+                .info_hash_with_source("OPS"),
+            None
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::content::compare_files;
+use super::hash::hex;
+use super::{Bencoding, BencodingError, ContentDifference};
+
+/// The result of `Bencoding::check_hybrid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridReport {
+    /// The SHA-1 of the encoded `info`.
+    pub info_hash_v1: [u8; 20],
+    /// The SHA-256 of the same bytes.
+    pub info_hash_v2: [u8; 32],
+    /// How the v1 file list, without padding files, differs from the v2
+    /// `file tree`, with the v1 files as `OnlyInSelf`.
+    pub file_differences: Vec<ContentDifference>,
+    /// The number of hashes in `pieces`.
+    pub piece_count: usize,
+    /// The number of pieces the `file tree` needs, with every file starting
+    /// on a piece boundary as BEP 52 requires of hybrid torrents.
+    pub expected_piece_count: usize,
+}
+
+impl HybridReport {
+    /// Whether the v1 and v2 parts describe the same content.
+    pub fn is_consistent(&self) -> bool {
+        return self.file_differences.is_empty() && self.piece_count == self.expected_piece_count;
+    }
+}
+
+impl Bencoding {
+    /// Checks that the v1 and v2 parts of a hybrid torrent's `info` describe
+    /// the same content, and computes both info-hashes from it.
+    ///
+    /// Both hashes come from a single encoding of `info`, so they always
+    /// match each other; what can be inconsistent in a corrupt or badly
+    /// built torrent is the content. The v1 files, without padding files,
+    /// must have the same paths and lengths as the `file tree`, and `pieces`
+    /// must hold one hash per piece of the `file tree`'s files, each padded
+    /// to a whole number of pieces.
+    ///
+    /// A torrent without `pieces`, `piece length` or `file tree`, or with a
+    /// malformed file list, is `Err(MissingField)`, and one whose `file tree`
+    /// holds more pieces than a `usize` can count is `Err(IntegerOverflow)`.
+    /// Requires the `sha1` and `sha2` features.
+    pub fn check_hybrid(&self) -> Result<HybridReport, BencodingError> {
+        let info = self
+            .encoded_info()
+            .ok_or(BencodingError::MissingField("info"))?;
+        let piece_count = self
+            .piece_count()
+            .ok_or(BencodingError::MissingField("pieces"))?;
+        let piece_length = self
+            .piece_length()
+            .ok_or(BencodingError::MissingField("piece length"))?;
+        let tree = self
+            .file_tree()
+            .ok_or(BencodingError::MissingField("file tree"))?;
+        let mut files = self
+            .files()
+            .ok_or(BencodingError::MissingField("length or files"))?;
+        files.retain(|file| !file.padding);
+
+        // The lengths come from the torrent, so their piece counts may not
+        // add up to anything a `usize` holds.
+        let expected_piece_count = tree
+            .iter()
+            .try_fold(0usize, |count, file| {
+                let pieces = (file.length as u64).div_ceil(piece_length as u64);
+                return count.checked_add(usize::try_from(pieces).ok()?);
+            })
+            .ok_or(BencodingError::IntegerOverflow)?;
+
+        return Ok(HybridReport {
+            info_hash_v1: Sha1::digest(&info).into(),
+            info_hash_v2: Sha256::digest(&info).into(),
+            file_differences: compare_files(files, tree),
+            piece_count,
+            expected_piece_count,
+        });
+    }
+
+    /// A single identifier for the torrent, whatever its version, for
+    /// indexing torrents by one key: the lowercase hex of the v1 info-hash
+    /// followed by that of the v2 info-hash, 104 characters in all.
+    ///
+    /// Both hashes are taken over the same encoding of `info`, so a hybrid
+    /// torrent's ID holds both of its info-hashes, and its first 40
+    /// characters are the v1 one. For a torrent that isn't v2 the second
+    /// part is only a SHA-256 with no meaning to other clients, and
+    /// conversely for one that isn't v1, but the ID is still stable.
+    /// Returns `None` if there is no `info`. Requires the `sha1` and `sha2`
+    /// features.
+    pub fn hybrid_id(&self) -> Option<String> {
+        let info = self.encoded_info()?;
+        let v1: [u8; 20] = Sha1::digest(&info).into();
+        let v2: [u8; 32] = Sha256::digest(&info).into();
+
+        return Some(hex(&v1) + &hex(&v2));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::hash::hex;
+    use crate::bencoding_parser::{Bencoding, BencodingError, ContentDifference};
+
+    fn hybrid(length: usize, piece_count: usize) -> Bencoding {
+        let pieces = "x".repeat(piece_count * 20);
+        let data = format!(
+            "d4:infod6:lengthi{}e4:name4:file12:piece lengthi16384e6:pieces{}:{}ee",
+            length,
+            pieces.len(),
+            pieces
+        );
+        let v1 = Bencoding::decode(data.as_bytes()).unwrap();
+        return v1.upgrade_to_v2(vec![7; length].as_slice(), true).unwrap();
+    }
+
+    #[test]
+    fn check_hybrid_of_consistent_torrent() {
+        let torrent = hybrid(40000, 3);
+        let report = torrent.check_hybrid().unwrap();
+
+        assert!(report.is_consistent());
+        assert_eq!(report.expected_piece_count, 3);
+        assert_eq!(report.info_hash_v1, torrent.info_hash().unwrap());
+    }
+
+    #[test]
+    fn check_hybrid_reports_piece_count_mismatch() {
+        let report = hybrid(40000, 2).check_hybrid().unwrap();
+
+        assert!(!report.is_consistent());
+        assert_eq!(report.piece_count, 2);
+        assert_eq!(report.expected_piece_count, 3);
+        assert!(report.file_differences.is_empty());
+    }
+
+    #[test]
+    fn check_hybrid_reports_file_mismatch() {
+        let encoded = hybrid(40000, 3).encode();
+        let position = encoded
+            .windows(17)
+            .position(|window| window == b"0:d6:lengthi40000")
+            .unwrap();
+        let mut tampered = encoded.clone();
+        tampered[position + 12] = b'5';
+        let report = Bencoding::decode(&tampered)
+            .unwrap()
+            .check_hybrid()
+            .unwrap();
+
+        assert_eq!(
+            report.file_differences,
+            vec![ContentDifference::LengthDiffers {
+                path: vec![b"file".to_vec()],
+                length: 40000,
+                other_length: 50000,
+            }]
+        );
+    }
+
+    #[test]
+    fn hybrid_id_holds_both_info_hashes() {
+        let torrent = hybrid(40000, 3);
+        let report = torrent.check_hybrid().unwrap();
+        let id = torrent.hybrid_id().unwrap();
+
+        assert_eq!(id.len(), 104);
+        assert_eq!(id[..40], hex(&report.info_hash_v1));
+        assert_eq!(id[40..], hex(&report.info_hash_v2));
+        assert_eq!(Bencoding::decode(b"de").unwrap().hybrid_id(), None);
+    }
+
+    #[test]
+    fn hybrid_id_of_v1_torrent() {
+        let parser = Bencoding::decode(
+            b"d8:announce3:url4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.hybrid_id().unwrap(),
+            "fab7bc801b6070674f82ace84aa75ab5ff358221\
+             9a5468202657c429e4226abdbad26a8b4789532a8a0a1c46a5931ae5033cf4ed"
+        );
+    }
+
+    #[test]
+    fn check_hybrid_with_overflowing_piece_count_is_error() {
+        let file = format!("d0:d6:lengthi{}eee", i64::MAX);
+        let data = format!(
+            "d4:infod9:file treed1:a{0}1:b{0}1:c{0}e6:lengthi1e4:name1:a12:piece lengthi1e6:pieces20:xxxxxxxxxxxxxxxxxxxxee",
+            file
+        );
+        let parser = Bencoding::decode(data.as_bytes()).unwrap();
+
+        assert!(matches!(
+            parser.check_hybrid(),
+            Err(BencodingError::IntegerOverflow)
+        ));
+    }
+
+    #[test]
+    fn check_hybrid_of_v1_torrent_is_error() {
+        let parser = Bencoding::decode(
+            b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16384e6:pieces20:xxxxxxxxxxxxxxxxxxxxee",
+        )
+        .unwrap();
+        assert!(matches!(
+            parser.check_hybrid(),
+            Err(BencodingError::MissingField("file tree"))
+        ));
+    }
+}
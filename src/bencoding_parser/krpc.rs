@@ -0,0 +1,402 @@
+use std::net::{SocketAddrV4, SocketAddrV6};
+
+use super::{compact, Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+/// A DHT message as defined by the KRPC protocol (BEP 5).
+///
+/// Every KRPC message is a dictionary with a transaction id under `t` and a
+/// message type under `y`. The remaining payload depends on the type.
+#[derive(Debug, Clone)]
+pub enum KrpcMessage {
+    /// `y` is `q`: the method name is under `q` and its arguments under `a`.
+    Query {
+        transaction_id: Vec<u8>,
+        method: Vec<u8>,
+        arguments: BencodingDict,
+    },
+    /// `y` is `r`: the return values are under `r`.
+    Response {
+        transaction_id: Vec<u8>,
+        values: BencodingDict,
+    },
+    /// `y` is `e`: `e` is a list holding the error code and message.
+    Error {
+        transaction_id: Vec<u8>,
+        code: i64,
+        message: Vec<u8>,
+    },
+}
+
+impl KrpcMessage {
+    pub fn transaction_id(&self) -> &[u8] {
+        match self {
+            KrpcMessage::Query { transaction_id, .. } => transaction_id,
+            KrpcMessage::Response { transaction_id, .. } => transaction_id,
+            KrpcMessage::Error { transaction_id, .. } => transaction_id,
+        }
+    }
+}
+
+impl Bencoding {
+    /// Classifies the decoded dictionary as a KRPC query, response or error.
+    ///
+    /// Returns `None` if the envelope is incomplete or the payload for the
+    /// message type is missing or has the wrong type.
+    pub fn krpc_message(&self) -> Option<KrpcMessage> {
+        let transaction_id = match self.dict.get(b"t".as_slice())? {
+            BencodingValue::String(s) => s.clone(),
+            _ => return None,
+        };
+        let message_type = match self.dict.get(b"y".as_slice())? {
+            BencodingValue::String(s) => s,
+            _ => return None,
+        };
+
+        match message_type.as_slice() {
+            b"q" => {
+                let method = match self.dict.get(b"q".as_slice())? {
+                    BencodingValue::String(s) => s.clone(),
+                    _ => return None,
+                };
+                let arguments = match self.dict.get(b"a".as_slice())? {
+                    BencodingValue::Dict(d) => d.clone(),
+                    _ => return None,
+                };
+                return Some(KrpcMessage::Query {
+                    transaction_id,
+                    method,
+                    arguments,
+                });
+            }
+            b"r" => {
+                let values = match self.dict.get(b"r".as_slice())? {
+                    BencodingValue::Dict(d) => d.clone(),
+                    _ => return None,
+                };
+                return Some(KrpcMessage::Response {
+                    transaction_id,
+                    values,
+                });
+            }
+            b"e" => {
+                let error = match self.dict.get(b"e".as_slice())? {
+                    BencodingValue::List(l) => l,
+                    _ => return None,
+                };
+                let (code, message) = match error.as_slice() {
+                    [BencodingValue::Integer(c), BencodingValue::String(m)] => (*c, m.clone()),
+                    _ => return None,
+                };
+                return Some(KrpcMessage::Error {
+                    transaction_id,
+                    code,
+                    message,
+                });
+            }
+            _ => return None,
+        }
+    }
+
+    /// Reads the compact node info from the `nodes` string of a
+    /// `find_node`/`get_peers` response. Each 26-byte entry is a node id
+    /// followed by its IPv4 address and port.
+    ///
+    /// A response without `nodes` has no nodes and yields an empty list.
+    pub fn compact_nodes(&self) -> Result<Vec<(Vec<u8>, SocketAddrV4)>, BencodingError> {
+        match self.response_string(b"nodes") {
+            Some(nodes) => return compact::nodes_v4(nodes),
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    /// Reads the IPv6 compact node info from the `nodes6` string of a
+    /// response (BEP 32). Each 38-byte entry is a node id followed by its
+    /// IPv6 address and port.
+    ///
+    /// A response without `nodes6` yields an empty list.
+    pub fn compact_nodes6(&self) -> Result<Vec<(Vec<u8>, SocketAddrV6)>, BencodingError> {
+        match self.response_string(b"nodes6") {
+            Some(nodes) => return compact::nodes_v6(nodes),
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    /// The write token of a `get_peers` response, from `token` in `r`,
+    /// which must be sent back in the `announce_peer` query to the same node.
+    pub fn dht_token(&self) -> Option<&[u8]> {
+        return self.response_string(b"token");
+    }
+
+    /// The DHT bootstrap nodes of a trackerless torrent, from `nodes`.
+    ///
+    /// BEP 5 stores them as a list of `[host, port]` pairs, where the host may
+    /// be a domain name. Some implementations use a compact string of 6-byte
+    /// IPv4 address and port entries instead. Both forms are accepted, and
+    /// each node is returned as a host and port, which can be resolved with
+    /// `std::net::ToSocketAddrs`.
+    ///
+    /// A torrent without `nodes` has none, and yields an empty list so that
+    /// clients can fall back to their default bootstrap nodes. A malformed
+    /// `nodes` is an error instead: `Err(InvalidCompactLength)` for a partial
+    /// compact entry, `Err(OutOfRange)` for a port that doesn't fit a `u16`,
+    /// and `Err(UnexpectedType)` for anything else.
+    pub fn nodes(&self) -> Result<Vec<(String, u16)>, BencodingError> {
+        let malformed = |expected, found| BencodingError::UnexpectedType {
+            key: String::from("nodes"),
+            expected,
+            found,
+        };
+
+        match self.dict.get(b"nodes".as_slice()) {
+            None => return Ok(Vec::new()),
+            Some(BencodingValue::String(compact)) => {
+                let nodes = compact::peers_v4(compact)?
+                    .into_iter()
+                    .map(|addr| (addr.ip().to_string(), addr.port()))
+                    .collect();
+                return Ok(nodes);
+            }
+            Some(BencodingValue::List(list)) => {
+                let mut nodes = Vec::new();
+                for node in list {
+                    let (host, port) = match node.as_list().map(Vec::as_slice) {
+                        Some([BencodingValue::String(host), BencodingValue::Integer(port)]) => {
+                            (host, *port)
+                        }
+                        _ => return Err(malformed("[host, port] pair", node.type_name())),
+                    };
+                    let host = String::from_utf8(host.clone())
+                        .map_err(|_| malformed("UTF-8 host", "binary string"))?;
+                    let port = u16::try_from(port).map_err(|_| BencodingError::OutOfRange {
+                        key: String::from("nodes"),
+                        value: port,
+                        min: 0,
+                        max: u16::MAX as i64,
+                    })?;
+                    nodes.push((host, port));
+                }
+                return Ok(nodes);
+            }
+            Some(other) => return Err(malformed("list", other.type_name())),
+        }
+    }
+
+    /// Whether the torrent has usable DHT bootstrap nodes, that is, a
+    /// well-formed `nodes` that isn't empty. Clients use their default
+    /// bootstrap nodes otherwise.
+    pub fn has_dht_nodes(&self) -> bool {
+        return self.nodes().is_ok_and(|nodes| !nodes.is_empty());
+    }
+
+    fn response_string(&self, key: &[u8]) -> Option<&[u8]> {
+        return self
+            .dict
+            .get(b"r".as_slice())
+            .and_then(BencodingValue::as_dict)
+            .and_then(|r| r.get(key))
+            .and_then(BencodingValue::as_string);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    use crate::bencoding_parser::{Bencoding, BencodingError, BencodingValue, KrpcMessage};
+
+    #[test]
+    fn krpc_ping_query() {
+        let parser =
+            Bencoding::decode(b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe").unwrap();
+        let (transaction_id, method, arguments) = match parser.krpc_message().unwrap() {
+            KrpcMessage::Query {
+                transaction_id,
+                method,
+                arguments,
+            } => (transaction_id, method, arguments),
+            _ => panic!(),
+        };
+        let id = match &arguments[&b"id".to_vec()] {
+            BencodingValue::String(s) => s,
+            _ => panic!(),
+        };
+
+        assert_eq!(transaction_id, b"aa");
+        assert_eq!(method, b"ping");
+        assert_eq!(id, b"abcdefghij0123456789");
+    }
+
+    #[test]
+    fn krpc_ping_response() {
+        let parser = Bencoding::decode(b"d1:rd2:id20:mnopqrstuvwxyz123456e1:t2:aa1:y1:re").unwrap();
+        let message = parser.krpc_message().unwrap();
+        let values = match &message {
+            KrpcMessage::Response { values, .. } => values,
+            _ => panic!(),
+        };
+
+        assert_eq!(message.transaction_id(), b"aa");
+        assert!(values.contains_key(b"id".as_slice()));
+    }
+
+    #[test]
+    fn krpc_error() {
+        let parser =
+            Bencoding::decode(b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee").unwrap();
+        let (code, message) = match parser.krpc_message().unwrap() {
+            KrpcMessage::Error { code, message, .. } => (code, message),
+            _ => panic!(),
+        };
+
+        assert_eq!(code, 201);
+        assert_eq!(message, b"A Generic Error Ocurred");
+    }
+
+    #[test]
+    fn krpc_unknown_message_type_is_none() {
+        let parser = Bencoding::decode(b"d1:t2:aa1:y1:xe").unwrap();
+        assert!(parser.krpc_message().is_none());
+    }
+
+    #[test]
+    fn krpc_query_without_arguments_is_none() {
+        let parser = Bencoding::decode(b"d1:q4:ping1:t2:aa1:y1:qe").unwrap();
+        assert!(parser.krpc_message().is_none());
+    }
+
+    #[test]
+    fn dht_token_from_get_peers_response() {
+        let parser = Bencoding::decode(
+            b"d1:rd2:id20:abcdefghij01234567895:token8:aoeusnth6:valuesl6:axje.u6:idhtnmee1:t2:aa1:y1:re",
+        )
+        .unwrap();
+        assert_eq!(parser.dht_token(), Some(b"aoeusnth".as_slice()));
+
+        let query =
+            Bencoding::decode(b"d1:ad5:token8:aoeusnthe1:q13:announce_peer1:t2:aa1:y1:qe").unwrap();
+        assert_eq!(query.dht_token(), None);
+    }
+
+    #[test]
+    fn compact_nodes_from_find_node_response() {
+        let mut data = b"d1:rd2:id20:mnopqrstuvwxyz1234565:nodes52:".to_vec();
+        data.extend_from_slice(b"abcdefghij0123456789\x0A\x00\x00\x01\x1A\xE1");
+        data.extend_from_slice(b"klmnopqrstuvwxyz0123\xC0\xA8\x01\x02\x00\x50");
+        data.extend_from_slice(b"e1:t2:aa1:y1:re");
+        let parser = Bencoding::decode(&data).unwrap();
+        let nodes = parser.compact_nodes().unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].0, b"abcdefghij0123456789");
+        assert_eq!(
+            nodes[0].1,
+            SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6881)
+        );
+        assert_eq!(nodes[1].0, b"klmnopqrstuvwxyz0123");
+        assert_eq!(
+            nodes[1].1,
+            SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 80)
+        );
+    }
+
+    #[test]
+    fn compact_nodes_with_partial_entry_is_error() {
+        let parser = Bencoding::decode(b"d1:rd5:nodes5:abcdee1:t2:aa1:y1:re").unwrap();
+        assert!(parser.compact_nodes().is_err());
+    }
+
+    #[test]
+    fn compact_nodes_without_nodes_is_empty() {
+        let parser = Bencoding::decode(b"d1:rd2:id20:mnopqrstuvwxyz123456e1:t2:aa1:y1:re").unwrap();
+        assert!(parser.compact_nodes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn compact_nodes6_from_find_node_response() {
+        let mut data = b"d1:rd2:id20:mnopqrstuvwxyz1234566:nodes638:".to_vec();
+        data.extend_from_slice(b"abcdefghij0123456789");
+        data.extend_from_slice(b"\x2A\x00\x14\x50\x40\x01\x08\x00\x00\x00\x00\x00\x00\x00\x20\x0E");
+        data.extend_from_slice(b"\x1A\xE1e1:t2:aa1:y1:re");
+        let parser = Bencoding::decode(&data).unwrap();
+        let nodes = parser.compact_nodes6().unwrap();
+        let ip = Ipv6Addr::new(0x2a00, 0x1450, 0x4001, 0x800, 0, 0, 0, 0x200e);
+
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].0, b"abcdefghij0123456789");
+        assert_eq!(nodes[0].1, SocketAddrV6::new(ip, 6881, 0, 0));
+    }
+
+    #[test]
+    fn compact_nodes6_rejects_v4_sized_entries() {
+        let mut data = b"d1:rd6:nodes626:".to_vec();
+        data.extend_from_slice(b"abcdefghij0123456789\x0A\x00\x00\x01\x1A\xE1");
+        data.extend_from_slice(b"e1:t2:aa1:y1:re");
+        let parser = Bencoding::decode(&data).unwrap();
+        assert!(parser.compact_nodes6().is_err());
+    }
+
+    #[test]
+    fn nodes_from_host_port_pairs() {
+        let parser = Bencoding::decode(
+            b"d4:infode5:nodesll21:router.bittorrent.comi6881eel9:127.0.0.1i51413eeee",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.nodes().unwrap(),
+            vec![
+                (String::from("router.bittorrent.com"), 6881),
+                (String::from("127.0.0.1"), 51413),
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes_from_compact_string() {
+        let parser = Bencoding::decode(
+            b"d4:infode5:nodes12:\x0A\x00\x00\x01\x1A\xE1\xC0\xA8\x01\x02\x00\x50e",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.nodes().unwrap(),
+            vec![
+                (String::from("10.0.0.1"), 6881),
+                (String::from("192.168.1.2"), 80),
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes_with_out_of_range_port_is_error() {
+        let parser = Bencoding::decode(b"d5:nodesll4:hosti70000eeee").unwrap();
+        assert!(matches!(
+            parser.nodes(),
+            Err(BencodingError::OutOfRange { value: 70000, .. })
+        ));
+        assert!(!parser.has_dht_nodes());
+    }
+
+    #[test]
+    fn nodes_without_nodes_is_empty() {
+        let parser = Bencoding::decode(b"d4:infodee").unwrap();
+        assert_eq!(parser.nodes().unwrap(), Vec::new());
+        assert!(!parser.has_dht_nodes());
+    }
+
+    #[test]
+    fn nodes_malformed_is_error() {
+        for data in [
+            b"d5:nodesi1ee".as_slice(),
+            b"d5:nodes5:abcdee",
+            b"d5:nodesl4:hostee",
+            b"d5:nodesll4:hosti1ei2eeee",
+            b"d5:nodesll2:\xff\xfei1eeee",
+        ] {
+            let parser = Bencoding::decode(data).unwrap();
+            assert!(parser.nodes().is_err());
+            assert!(!parser.has_dht_nodes());
+        }
+
+        let parser = Bencoding::decode(b"d5:nodesll4:hosti1eeee").unwrap();
+        assert!(parser.has_dht_nodes());
+    }
+}
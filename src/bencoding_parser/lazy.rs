@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::{BencodingError, BencodingValue, DecodeOptions, Decoder};
+
+/// Lazy decoding is strict, as `Bencoding::decode` is. The decoder borrows its
+/// options for as long as the input, hence the static.
+static OPTIONS: DecodeOptions = DecodeOptions {
+    allow_cr_in_string_length: false,
+    max_operations: None,
+    validate_lengths_first: false,
+    skip_bom: false,
+    skip_leading_whitespace: false,
+    allow_trailing_nul: false,
+    reject_empty_keys: false,
+    allow_u64_integers: false,
+};
+
+/// A top-level dictionary whose values are only decoded when asked for.
+///
+/// Decoding one only reads the top-level keys and the byte range of each
+/// value, so reading a couple of fields of a torrent doesn't pay for building
+/// its `pieces` string or file list. Values are still checked to be
+/// well-formed up front, so malformed input is rejected as by
+/// `Bencoding::decode`.
+///
+/// The input is borrowed for the lifetime of the dictionary.
+pub struct LazyBencoding<'a> {
+    data: &'a [u8],
+    spans: HashMap<&'a [u8], Range<usize>>,
+}
+
+impl<'a> LazyBencoding<'a> {
+    /// Reads the keys and value ranges of the top-level dictionary of `data`.
+    /// If a key is repeated, the last value wins, as with `Bencoding::decode`.
+    pub fn decode(data: &'a [u8]) -> Result<Self, BencodingError> {
+        let mut decoder = Decoder::new(data, &OPTIONS);
+        let spans = decoder.decode_dict_spans()?;
+        decoder.expect_end()?;
+
+        return Ok(Self {
+            data,
+            spans: spans.into_iter().collect(),
+        });
+    }
+
+    /// Decodes the value under `key`. Every call decodes it anew.
+    pub fn get(&self, key: &[u8]) -> Option<BencodingValue> {
+        let raw = &self.data[self.spans.get(key)?.clone()];
+
+        return Decoder::new(raw, &OPTIONS).decode_next().ok();
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        return self.spans.contains_key(key);
+    }
+
+    /// The top-level keys, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        return self.spans.keys().copied();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{BencodingError, BencodingValue, LazyBencoding};
+
+    #[test]
+    fn lazy_get_decodes_value_on_demand() {
+        let parser =
+            LazyBencoding::decode(b"d8:announce3:url4:infod4:name1:a6:piecesl1:x1:yeee").unwrap();
+
+        let announce = match parser.get(b"announce").unwrap() {
+            BencodingValue::String(s) => s,
+            _ => panic!(),
+        };
+        let info = match parser.get(b"info").unwrap() {
+            BencodingValue::Dict(d) => d,
+            _ => panic!(),
+        };
+
+        assert_eq!(announce, b"url");
+        assert_eq!(info[b"pieces".as_slice()].as_list().unwrap().len(), 2);
+        assert!(parser.get(b"missing").is_none());
+    }
+
+    #[test]
+    fn lazy_keys_of_top_level_dict() {
+        let parser = LazyBencoding::decode(b"d1:ai1e1:bli1ei2eee").unwrap();
+        let mut keys: Vec<&[u8]> = parser.keys().collect();
+        keys.sort();
+
+        assert_eq!(keys, vec![b"a".as_slice(), b"b"]);
+        assert!(parser.contains_key(b"b"));
+    }
+
+    #[test]
+    fn lazy_decode_rejects_malformed_nested_value() {
+        let result = LazyBencoding::decode(b"d1:ad1:bi1x2eee");
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidInteger { position: 9 })
+        ));
+    }
+
+    #[test]
+    fn lazy_decode_rejects_truncated_value() {
+        let result = LazyBencoding::decode(b"d1:al5:helloe");
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn lazy_decode_rejects_trailing_bytes() {
+        let result = LazyBencoding::decode(b"d1:ai1eeGARBAGE");
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte {
+                position: 8,
+                byte: b'G'
+            })
+        ));
+    }
+}
@@ -0,0 +1,373 @@
+use std::fmt;
+
+use super::hash::hex;
+use super::torrent::percent_encode;
+use super::{Bencoding, BencodingError};
+
+/// A magnet link for a torrent, built by `Bencoding::magnet_link` or parsed
+/// with `MagnetLink::from_magnet`, and formatted with `Display`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MagnetLink {
+    info_hash: [u8; 20],
+    name: Option<String>,
+    trackers: Vec<String>,
+    file_count: usize,
+    select_only: Vec<usize>,
+}
+
+impl MagnetLink {
+    /// Parses a magnet link, reading its info-hash from the `xt` parameter
+    /// `urn:btih:<hash>`, its display name from `dn` and its trackers from
+    /// each `tr`, in order. Other parameters, including `so`, are ignored.
+    ///
+    /// The info-hash is 40 hexadecimal digits or, as in older links, 32
+    /// base32 characters, told apart by their length; both are accepted in
+    /// any case, and `Display` writes the hash back as lowercase hex. Values
+    /// are percent-decoded, with `+` read as a space in the display name.
+    ///
+    /// A link without a `btih` hash, a hash of another length or with other
+    /// characters, or a value that doesn't percent-decode to UTF-8 is
+    /// `Err(InvalidMagnetLink)`. The number of files isn't known from a link,
+    /// so `select_only` keeps every index.
+    pub fn from_magnet(link: &str) -> Result<Self, BencodingError> {
+        let query = link
+            .get(..8)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("magnet:?"))
+            .map(|_| &link[8..])
+            .ok_or(BencodingError::InvalidMagnetLink("not a magnet: URI"))?;
+
+        let mut info_hash = None;
+        let mut name = None;
+        let mut trackers = Vec::new();
+        for parameter in query.split('&') {
+            let (key, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            match key {
+                "xt" if info_hash.is_none() => {
+                    let urn = percent_decode(value, false)?;
+                    if let Some(hash) = urn
+                        .get(..9)
+                        .filter(|prefix| prefix.eq_ignore_ascii_case("urn:btih:"))
+                        .map(|_| &urn[9..])
+                    {
+                        info_hash = Some(parse_info_hash(hash)?);
+                    }
+                }
+                "dn" => name = Some(percent_decode(value, true)?),
+                "tr" => trackers.push(percent_decode(value, false)?),
+                _ => {}
+            }
+        }
+
+        return Ok(MagnetLink {
+            info_hash: info_hash
+                .ok_or(BencodingError::InvalidMagnetLink("no urn:btih: info-hash"))?,
+            name,
+            trackers,
+            file_count: usize::MAX,
+            select_only: Vec::new(),
+        });
+    }
+
+    /// The v1 info-hash.
+    pub fn info_hash(&self) -> [u8; 20] {
+        return self.info_hash;
+    }
+
+    /// The display name, from `dn`.
+    pub fn name(&self) -> Option<&str> {
+        return self.name.as_deref();
+    }
+
+    /// The tracker URLs, from `tr`.
+    pub fn trackers(&self) -> &[String] {
+        return &self.trackers;
+    }
+
+    /// Adds a BEP 53 `so=` parameter selecting only the files at
+    /// `file_indices`, which index into `Bencoding::files`.
+    ///
+    /// Indices past the last file are ignored, and the order and repetitions
+    /// of the others don't matter. As BEP 53 allows, they are written in
+    /// ascending order, with runs of consecutive indices written as inclusive
+    /// ranges: `[5, 0, 1, 2]` becomes `so=0-2,5`.
+    pub fn select_only(mut self, file_indices: &[usize]) -> Self {
+        let mut indices: Vec<usize> = file_indices
+            .iter()
+            .copied()
+            .filter(|&i| i < self.file_count)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        self.select_only = indices;
+
+        return self;
+    }
+}
+
+impl fmt::Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "magnet:?xt=urn:btih:{}", hex(&self.info_hash))?;
+        if let Some(name) = &self.name {
+            write!(f, "&dn={}", percent_encode(name.as_bytes()))?;
+        }
+        for tracker in &self.trackers {
+            write!(f, "&tr={}", percent_encode(tracker.as_bytes()))?;
+        }
+        if !self.select_only.is_empty() {
+            write!(f, "&so={}", index_ranges(&self.select_only))?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Bencoding {
+    /// A magnet link with the info-hash, the `name` as display name and the
+    /// trackers of `announce` and `announce-list` in order, without
+    /// repetitions. A name or tracker that isn't valid UTF-8 is left out.
+    ///
+    /// Returns `None` if there is no `info` dictionary.
+    pub fn magnet_link(&self) -> Option<MagnetLink> {
+        let info_hash = self.info_hash()?;
+        let name = self
+            .name()
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .map(String::from);
+
+        let trackers = self
+            .tracker_urls()
+            .into_iter()
+            .filter_map(|url| std::str::from_utf8(url).ok())
+            .map(String::from)
+            .collect();
+
+        return Some(MagnetLink {
+            info_hash,
+            name,
+            trackers,
+            file_count: self.files().map_or(0, |files| files.len()),
+            select_only: Vec::new(),
+        });
+    }
+}
+
+/// Reads an info-hash written as 40 hex digits or 32 base32 characters
+/// (RFC 4648), in any case.
+fn parse_info_hash(hash: &str) -> Result<[u8; 20], BencodingError> {
+    let mut info_hash = [0; 20];
+    match hash.len() {
+        40 => {
+            for (i, pair) in hash.as_bytes().chunks(2).enumerate() {
+                info_hash[i] = hex_byte(pair)
+                    .ok_or(BencodingError::InvalidMagnetLink("invalid hex info-hash"))?;
+            }
+        }
+        32 => {
+            let mut bits: u64 = 0;
+            let mut bit_count = 0;
+            let mut i = 0;
+            for c in hash.bytes() {
+                let value = match c.to_ascii_uppercase() {
+                    c @ b'A'..=b'Z' => c - b'A',
+                    c @ b'2'..=b'7' => c - b'2' + 26,
+                    _ => {
+                        return Err(BencodingError::InvalidMagnetLink(
+                            "invalid base32 info-hash",
+                        ))
+                    }
+                };
+                bits = bits << 5 | value as u64;
+                bit_count += 5;
+                if bit_count >= 8 {
+                    bit_count -= 8;
+                    info_hash[i] = (bits >> bit_count) as u8;
+                    i += 1;
+                }
+            }
+        }
+        _ => {
+            return Err(BencodingError::InvalidMagnetLink(
+                "info-hash is neither 40 hex nor 32 base32 characters",
+            ))
+        }
+    }
+
+    return Ok(info_hash);
+}
+
+/// The byte written as the two hex digits in `pair`, in any case. Unlike
+/// `u8::from_str_radix`, a sign such as the `+` of `+a` is rejected.
+fn hex_byte(pair: &[u8]) -> Option<u8> {
+    if pair.len() != 2 || !pair.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let pair = std::str::from_utf8(pair).ok()?;
+
+    return u8::from_str_radix(pair, 16).ok();
+}
+
+/// Decodes `%XY` escapes, and `+` as a space with `plus_as_space`.
+fn percent_decode(value: &str, plus_as_space: bool) -> Result<String, BencodingError> {
+    let invalid = BencodingError::InvalidMagnetLink("invalid percent-encoding");
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                match bytes.get(i + 1..i + 3).and_then(hex_byte) {
+                    Some(byte) => out.push(byte),
+                    None => return Err(invalid),
+                }
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    return String::from_utf8(out).map_err(|_| invalid);
+}
+
+/// Formats sorted, distinct indices, collapsing consecutive runs into ranges.
+fn index_ranges(indices: &[usize]) -> String {
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < indices.len() {
+        let start = indices[i];
+        while i + 1 < indices.len() && indices[i + 1] == indices[i] + 1 {
+            i += 1;
+        }
+        if indices[i] == start {
+            parts.push(start.to_string());
+        } else {
+            parts.push(format!("{}-{}", start, indices[i]));
+        }
+        i += 1;
+    }
+
+    return parts.join(",");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{Bencoding, BencodingError, MagnetLink};
+
+    fn torrent(files: usize) -> Bencoding {
+        let mut list = String::new();
+        for i in 0..files {
+            list += &format!("d6:lengthi1e4:pathl5:file{}ee", i);
+        }
+        let data = format!(
+            "d8:announce14:http://a.test/13:announce-listll14:http://a.test/el14:http://b.test/ee4:infod5:filesl{}e4:name6:my dir12:piece lengthi16e6:pieces0:ee",
+            list
+        );
+
+        return Bencoding::decode(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn magnet_link_with_name_and_trackers() {
+        let magnet = torrent(1).magnet_link().unwrap().to_string();
+        let xt = format!(
+            "magnet:?xt=urn:btih:{}",
+            super::hex(&torrent(1).info_hash().unwrap())
+        );
+
+        assert_eq!(
+            magnet,
+            format!(
+                "{}&dn=my%20dir&tr=http%3A%2F%2Fa.test%2F&tr=http%3A%2F%2Fb.test%2F",
+                xt
+            )
+        );
+    }
+
+    #[test]
+    fn magnet_link_select_only_ranges() {
+        let magnet = torrent(8)
+            .magnet_link()
+            .unwrap()
+            .select_only(&[5, 0, 2, 1, 7, 2, 20]);
+        assert!(magnet.to_string().ends_with("&so=0-2,5,7"));
+    }
+
+    #[test]
+    fn magnet_link_without_selection_has_no_so() {
+        let magnet = torrent(2).magnet_link().unwrap().select_only(&[]);
+        assert!(!magnet.to_string().contains("&so="));
+    }
+
+    #[test]
+    fn magnet_link_without_info_is_none() {
+        let parser = Bencoding::decode(b"d8:announce3:urle").unwrap();
+        assert!(parser.magnet_link().is_none());
+    }
+
+    #[test]
+    fn from_magnet_normalizes_uppercase_hex() {
+        let link = "magnet:?xt=urn:btih:C12FE1C06BBA254A9DC9F519B335AA7C1367A88A&dn=my+dir%21&tr=http%3A%2F%2Fa.test%2F&tr=udp://b.test:80";
+        let magnet = MagnetLink::from_magnet(link).unwrap();
+
+        assert_eq!(magnet.info_hash()[..4], [0xc1, 0x2f, 0xe1, 0xc0]);
+        assert_eq!(magnet.name(), Some("my dir!"));
+        assert_eq!(magnet.trackers(), ["http://a.test/", "udp://b.test:80"]);
+        assert!(magnet
+            .to_string()
+            .starts_with("magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&"));
+    }
+
+    #[test]
+    fn from_magnet_reads_base32_in_any_case() {
+        let hex =
+            MagnetLink::from_magnet("magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a")
+                .unwrap();
+        let upper = MagnetLink::from_magnet("MAGNET:?xt=URN:BTIH:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK")
+            .unwrap();
+        let lower = MagnetLink::from_magnet("magnet:?xt=urn:btih:yex6dqdlxisuvhoj6um3gnnkpqjwpkek")
+            .unwrap();
+
+        assert_eq!(upper.info_hash(), hex.info_hash());
+        assert_eq!(lower.info_hash(), hex.info_hash());
+        assert_eq!(lower.name(), None);
+    }
+
+    #[test]
+    fn from_magnet_round_trips_magnet_link() {
+        let original = torrent(2).magnet_link().unwrap();
+        let parsed = MagnetLink::from_magnet(&original.to_string()).unwrap();
+
+        assert_eq!(parsed.info_hash(), original.info_hash());
+        assert_eq!(parsed.to_string(), original.to_string());
+    }
+
+    #[test]
+    fn from_magnet_of_malformed_links_is_error() {
+        let malformed = [
+            "http://example.com/?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a",
+            "magnet:?dn=name",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a8",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88g",
+            "magnet:?xt=urn:btih:+c+2e1c06bba254a9dc9f519b335aa7c1367a88a",
+            "magnet:?xt=urn:btih:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKE1",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&dn=%zz",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&dn=%+f",
+            "magnet:?xt=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a&tr=%ff",
+        ];
+        for link in malformed {
+            assert!(
+                matches!(
+                    MagnetLink::from_magnet(link),
+                    Err(BencodingError::InvalidMagnetLink(_))
+                ),
+                "{link}"
+            );
+        }
+    }
+}
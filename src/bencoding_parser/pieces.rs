@@ -0,0 +1,573 @@
+use std::collections::HashSet;
+
+use super::{Bencoding, BencodingError, FileEntry};
+
+/// Length of a SHA-1 piece hash in a v1 `pieces` string.
+pub(crate) const V1_HASH_LEN: usize = 20;
+
+impl Bencoding {
+    /// The concatenated SHA-1 piece hashes from `info.pieces`.
+    pub fn pieces(&self) -> Option<&[u8]> {
+        return self.info_dict()?.get(b"pieces".as_slice())?.as_string();
+    }
+
+    /// The number of pieces, from the number of hashes in `info.pieces`.
+    /// Returns `None` if `pieces` is missing or holds a partial hash.
+    pub fn piece_count(&self) -> Option<usize> {
+        let pieces = self.pieces()?;
+        if !pieces.len().is_multiple_of(V1_HASH_LEN) {
+            return None;
+        }
+
+        return Some(pieces.len() / V1_HASH_LEN);
+    }
+
+    /// The SHA-1 hash of the piece at `index`, borrowed from `info.pieces`.
+    ///
+    /// Returns `None` if the index is out of range or `pieces` is missing or
+    /// holds a partial hash. v2 piece hashes are stored per file in
+    /// `piece layers` rather than torrent-wide, so they are not covered; for
+    /// hybrid torrents this is the v1 hash.
+    pub fn piece_hash(&self, index: usize) -> Option<&[u8]> {
+        if index >= self.piece_count()? {
+            return None;
+        }
+
+        let start = index * V1_HASH_LEN;
+        return self.pieces()?.get(start..start + V1_HASH_LEN);
+    }
+
+    /// The v1 piece hashes in a set, to check quickly whether a hash matches
+    /// any piece.
+    ///
+    /// Pieces with the same content have the same hash, so the set can hold
+    /// fewer hashes than there are pieces and doesn't tell which or how many
+    /// pieces a hash belongs to; use `piece_hash` for that. Returns `None` as
+    /// `piece_count` does.
+    pub fn piece_hash_set(&self) -> Option<HashSet<[u8; 20]>> {
+        self.piece_count()?;
+
+        return Some(
+            self.pieces()?
+                .chunks(V1_HASH_LEN)
+                .map(|hash| hash.try_into().unwrap())
+                .collect(),
+        );
+    }
+
+    /// The v2 piece hashes of the file with the given `pieces root`, from the
+    /// top-level `piece layers` dictionary (BEP 52).
+    ///
+    /// Returns `None` if there is no layer for the root, or it isn't a whole
+    /// number of 32-byte hashes. Files no larger than one piece have no
+    /// layer, as their root is their only piece hash.
+    pub fn piece_layer(&self, pieces_root: &[u8]) -> Option<Vec<[u8; 32]>> {
+        let layer = self
+            .get_dict(b"piece layers")?
+            .get(pieces_root)?
+            .as_string()?;
+        if !layer.len().is_multiple_of(32) {
+            return None;
+        }
+
+        return Some(
+            layer
+                .chunks(32)
+                .map(|hash| hash.try_into().unwrap())
+                .collect(),
+        );
+    }
+
+    /// Checks that `pieces` holds one hash for each piece of the data, that
+    /// is, `ceil(total_length / piece_length)` hashes.
+    ///
+    /// A mismatch, including a partial hash, is `Err(InconsistentPieces)`,
+    /// and a missing or invalid `pieces`, `piece length` or file list is
+    /// `Err(MissingField)`.
+    pub fn validate_piece_count(&self) -> Result<(), BencodingError> {
+        let pieces = self
+            .pieces()
+            .ok_or(BencodingError::MissingField("pieces"))?;
+        let piece_length = self
+            .piece_length()
+            .ok_or(BencodingError::MissingField("piece length"))?;
+        let total_length = self
+            .total_length()
+            .ok_or(BencodingError::MissingField("files"))?;
+
+        // A piece count too large for a `pieces` string to hold, whatever
+        // its length, is reported as `usize::MAX`.
+        let expected_pieces = usize::try_from((total_length as u64).div_ceil(piece_length as u64))
+            .unwrap_or(usize::MAX);
+        match expected_pieces.checked_mul(V1_HASH_LEN) {
+            Some(expected_len) if expected_len == pieces.len() => return Ok(()),
+            _ => {
+                return Err(BencodingError::InconsistentPieces {
+                    pieces_len: pieces.len(),
+                    expected_pieces,
+                })
+            }
+        }
+    }
+
+    /// The length of the final piece, which is shorter than `piece length`
+    /// unless the total length is an exact multiple of it.
+    ///
+    /// Returns `None` if the torrent has no pieces or its piece count doesn't
+    /// match its total length.
+    pub fn last_piece_length(&self) -> Option<i64> {
+        let piece_length = self.piece_length()?;
+        let piece_count = self.piece_count()? as i64;
+        let total_length = self.total_length()?;
+        if piece_count == 0 {
+            return None;
+        }
+
+        let last = total_length.checked_sub((piece_count - 1).checked_mul(piece_length)?)?;
+        if last <= 0 || last > piece_length {
+            return None;
+        }
+
+        return Some(last);
+    }
+
+    /// The length of the piece at `index`: `piece length` for every piece
+    /// but the last, which holds what's left, as in `last_piece_length`.
+    ///
+    /// Returns `None` if the index is out of range, or under the same
+    /// conditions as `last_piece_length`.
+    pub fn piece_size(&self, index: usize) -> Option<i64> {
+        let piece_count = self.piece_count()?;
+        if index >= piece_count {
+            return None;
+        }
+        let last_piece_length = self.last_piece_length()?;
+        if index == piece_count - 1 {
+            return Some(last_piece_length);
+        }
+
+        return self.piece_length();
+    }
+
+    /// The piece indices in rarest-first order, by ascending `availability`,
+    /// the number of peers in the swarm having each piece, with
+    /// `availability[i]` for piece `i`. Pieces equally available stay in
+    /// index order.
+    ///
+    /// Returns `None` if `availability` doesn't hold one count per piece.
+    pub fn rarest_first(&self, availability: &[u32]) -> Option<Vec<usize>> {
+        if availability.len() != self.piece_count()? {
+            return None;
+        }
+
+        let mut order: Vec<usize> = (0..availability.len()).collect();
+        order.sort_by_key(|&piece| availability[piece]);
+
+        return Some(order);
+    }
+
+    /// The number of content bytes still to download, given which pieces
+    /// are already there, with `have[i]` for piece `i`. This is what a
+    /// client shows as "remaining"; the final piece counts for its actual,
+    /// possibly shorter, length.
+    ///
+    /// Returns `None` if `have` doesn't hold one entry per piece, or if the
+    /// piece count doesn't match the total length.
+    pub fn remaining_bytes(&self, have: &[bool]) -> Option<i64> {
+        let piece_count = self.piece_count()?;
+        if have.len() != piece_count {
+            return None;
+        }
+        if piece_count == 0 {
+            return (self.total_length()? == 0).then_some(0);
+        }
+
+        let piece_length = self.piece_length()?;
+        let last_piece_length = self.last_piece_length()?;
+        let missing_full = have[..piece_count - 1].iter().filter(|&&has| !has).count() as i64;
+        let missing_last = if have[piece_count - 1] {
+            0
+        } else {
+            last_piece_length
+        };
+
+        return missing_full
+            .checked_mul(piece_length)?
+            .checked_add(missing_last);
+    }
+
+    /// The indices, in `files` order, of the files whose pieces are all
+    /// present, with `have[i]` for piece `i`: the files a client can open
+    /// already. A piece shared with a neighbouring file must be there too.
+    /// Empty files are always complete.
+    ///
+    /// Returns `None` if `have` doesn't hold one entry per piece, or if the
+    /// file list or the piece count is inconsistent with the total length.
+    pub fn complete_files(&self, have: &[bool]) -> Option<Vec<usize>> {
+        let piece_count = self.piece_count()?;
+        if have.len() != piece_count {
+            return None;
+        }
+        // An empty torrent has no last piece, and then no pieces at all.
+        if piece_count == 0 {
+            if self.total_length()? != 0 {
+                return None;
+            }
+        } else {
+            self.last_piece_length()?;
+        }
+
+        let file_count = self.files()?.len();
+        let entries: Vec<FileEntry> = self.file_entries().collect();
+        if entries.len() != file_count {
+            return None;
+        }
+
+        return Some(
+            entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| have[entry.piece_range.clone()].iter().all(|&has| has))
+                .map(|(i, _)| i)
+                .collect(),
+        );
+    }
+}
+
+/// Packs which pieces a client has into a BitTorrent bitfield, as stored in
+/// resume data and sent in the peer protocol's `bitfield` message.
+///
+/// Piece 0 is the most significant bit of the first byte. The spare bits of
+/// the final byte are zero.
+pub fn pieces_to_bitfield(have: &[bool]) -> Vec<u8> {
+    let mut bitfield = vec![0; have.len().div_ceil(8)];
+    for (i, &has) in have.iter().enumerate() {
+        if has {
+            bitfield[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+
+    return bitfield;
+}
+
+const KIB: u64 = 1 << 10;
+const MIB: u64 = 1 << 20;
+const GIB: u64 = 1 << 30;
+
+/// The piece length for content up to each size, in increasing order. Past
+/// the last band the piece length stays at 16 MiB.
+const PIECE_LENGTH_BANDS: &[(u64, u64)] = &[
+    (512 * MIB, 256 * KIB),
+    (GIB, 512 * KIB),
+    (2 * GIB, MIB),
+    (4 * GIB, 2 * MIB),
+    (8 * GIB, 4 * MIB),
+    (16 * GIB, 8 * MIB),
+];
+
+/// Picks a piece length for a new torrent of `total_size` bytes.
+///
+/// Piece lengths are powers of two from 256 KiB to 16 MiB, doubling with
+/// each doubling of the size so that a torrent has at most 2048 pieces up to
+/// 16 GiB:
+///
+/// | Total size       | Piece length |
+/// |------------------|--------------|
+/// | up to 512 MiB    | 256 KiB      |
+/// | up to 1 GiB      | 512 KiB      |
+/// | up to 2 GiB      | 1 MiB        |
+/// | up to 4 GiB      | 2 MiB        |
+/// | up to 8 GiB      | 4 MiB        |
+/// | up to 16 GiB     | 8 MiB        |
+/// | above 16 GiB     | 16 MiB       |
+pub fn suggest_piece_length(total_size: u64) -> u64 {
+    for &(max_size, piece_length) in PIECE_LENGTH_BANDS {
+        if total_size <= max_size {
+            return piece_length;
+        }
+    }
+
+    return 16 * MIB;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{
+        pieces_to_bitfield, suggest_piece_length, Bencoding, BencodingError,
+    };
+
+    fn torrent(piece_length: i64, length: i64, piece_count: usize) -> Bencoding {
+        let pieces = "x".repeat(piece_count * 20);
+        let data = format!(
+            "d4:infod6:lengthi{}e4:name1:a12:piece lengthi{}e6:pieces{}:{}ee",
+            length,
+            piece_length,
+            pieces.len(),
+            pieces
+        );
+
+        return Bencoding::decode(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn piece_count_from_pieces() {
+        assert_eq!(torrent(16, 40, 3).piece_count(), Some(3));
+    }
+
+    #[test]
+    fn piece_count_with_partial_hash_is_none() {
+        let parser =
+            Bencoding::decode(b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces3:abcee")
+                .unwrap();
+        assert_eq!(parser.piece_count(), None);
+    }
+
+    #[test]
+    fn piece_hash_by_index() {
+        let pieces = format!("{}{}", "a".repeat(20), "b".repeat(20));
+        let data = format!(
+            "d4:infod6:lengthi30e4:name1:a12:piece lengthi16e6:pieces40:{}ee",
+            pieces
+        );
+        let parser = Bencoding::decode(data.as_bytes()).unwrap();
+
+        assert_eq!(parser.piece_hash(0), Some([b'a'; 20].as_slice()));
+        assert_eq!(parser.piece_hash(1), Some([b'b'; 20].as_slice()));
+        assert_eq!(parser.piece_hash(2), None);
+    }
+
+    #[test]
+    fn piece_hash_with_partial_hash_is_none() {
+        let parser =
+            Bencoding::decode(b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces3:abcee")
+                .unwrap();
+        assert_eq!(parser.piece_hash(0), None);
+    }
+
+    #[test]
+    fn last_piece_length_of_unaligned_total() {
+        assert_eq!(torrent(16, 40, 3).last_piece_length(), Some(8));
+    }
+
+    #[test]
+    fn last_piece_length_of_aligned_total() {
+        assert_eq!(torrent(16, 48, 3).last_piece_length(), Some(16));
+    }
+
+    #[test]
+    fn last_piece_length_of_single_piece() {
+        assert_eq!(torrent(16, 5, 1).last_piece_length(), Some(5));
+    }
+
+    #[test]
+    fn last_piece_length_with_inconsistent_piece_count_is_none() {
+        assert_eq!(torrent(16, 40, 4).last_piece_length(), None);
+        assert_eq!(torrent(16, 40, 2).last_piece_length(), None);
+    }
+
+    #[test]
+    fn piece_size_with_unaligned_last_piece() {
+        let parser = torrent(16, 40, 3);
+        assert_eq!(parser.piece_size(0), Some(16));
+        assert_eq!(parser.piece_size(1), Some(16));
+        assert_eq!(parser.piece_size(2), Some(8));
+        assert_eq!(parser.piece_size(3), None);
+    }
+
+    #[test]
+    fn piece_size_with_aligned_last_piece() {
+        let parser = torrent(16, 48, 3);
+        assert_eq!(parser.piece_size(2), Some(16));
+        assert_eq!(torrent(16, 5, 1).piece_size(0), Some(5));
+    }
+
+    #[test]
+    fn piece_size_of_inconsistent_torrent_is_none() {
+        assert_eq!(torrent(16, 40, 4).piece_size(0), None);
+        assert_eq!(torrent(16, 0, 0).piece_size(0), None);
+    }
+
+    #[test]
+    fn rarest_first_orders_by_availability() {
+        let parser = torrent(16, 80, 5);
+        assert_eq!(
+            parser.rarest_first(&[3, 1, 0, 1, 7]),
+            Some(vec![2, 1, 3, 0, 4])
+        );
+        assert_eq!(parser.rarest_first(&[1, 2, 3]), None);
+        assert_eq!(torrent(16, 0, 0).rarest_first(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn remaining_bytes_with_partial_last_piece() {
+        let parser = torrent(16, 40, 3);
+        assert_eq!(parser.remaining_bytes(&[false, false, false]), Some(40));
+        assert_eq!(parser.remaining_bytes(&[true, false, false]), Some(24));
+        assert_eq!(parser.remaining_bytes(&[false, false, true]), Some(32));
+        assert_eq!(parser.remaining_bytes(&[true, true, false]), Some(8));
+        assert_eq!(parser.remaining_bytes(&[true, true, true]), Some(0));
+    }
+
+    #[test]
+    fn remaining_bytes_of_malformed_input_is_none() {
+        assert_eq!(torrent(16, 40, 3).remaining_bytes(&[false, false]), None);
+        assert_eq!(torrent(16, 40, 4).remaining_bytes(&[false; 4]), None);
+        assert_eq!(torrent(16, 0, 0).remaining_bytes(&[]), Some(0));
+    }
+
+    #[test]
+    fn complete_files_needs_every_overlapping_piece() {
+        let pieces = "x".repeat(4 * 20);
+        let data = format!(
+            "d4:infod5:filesld6:lengthi20e4:pathl1:aeed6:lengthi0e4:pathl1:beed6:lengthi12e4:pathl1:ceed6:lengthi24e4:pathl1:deee4:name1:r12:piece lengthi16e6:pieces{}:{}ee",
+            pieces.len(),
+            pieces
+        );
+        let parser = Bencoding::decode(data.as_bytes()).unwrap();
+
+        assert_eq!(parser.complete_files(&[false; 4]), Some(vec![1]));
+        assert_eq!(
+            parser.complete_files(&[true, true, false, false]),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            parser.complete_files(&[false, true, true, true]),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(parser.complete_files(&[true; 4]), Some(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn complete_files_of_malformed_input_is_none() {
+        assert_eq!(torrent(16, 40, 3).complete_files(&[true, true]), None);
+        assert_eq!(torrent(16, 40, 4).complete_files(&[true; 4]), None);
+        assert_eq!(torrent(16, 40, 3).complete_files(&[true; 3]), Some(vec![0]));
+        assert_eq!(torrent(16, 40, 0).complete_files(&[]), None);
+    }
+
+    #[test]
+    fn complete_files_of_empty_torrent() {
+        assert_eq!(torrent(16, 0, 0).complete_files(&[]), Some(vec![0]));
+
+        let data = b"d4:infod5:filesld6:lengthi0e4:pathl1:aeed6:lengthi0e4:pathl1:beee4:name1:r12:piece lengthi16e6:pieces0:ee";
+        let parser = Bencoding::decode(data).unwrap();
+        assert_eq!(parser.complete_files(&[]), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn validate_piece_count_of_consistent_torrents() {
+        assert!(torrent(16, 40, 3).validate_piece_count().is_ok());
+        assert!(torrent(16, 48, 3).validate_piece_count().is_ok());
+        assert!(torrent(16, 0, 0).validate_piece_count().is_ok());
+    }
+
+    #[test]
+    fn validate_piece_count_of_inconsistent_torrent() {
+        let result = torrent(16, 40, 2).validate_piece_count();
+        assert!(matches!(
+            result,
+            Err(BencodingError::InconsistentPieces {
+                pieces_len: 40,
+                expected_pieces: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_piece_count_with_overflowing_piece_count() {
+        let parser = torrent(1, i64::MAX, 1);
+        let expected_pieces = usize::try_from(i64::MAX).unwrap_or(usize::MAX);
+        assert!(matches!(
+            parser.validate_piece_count(),
+            Err(BencodingError::InconsistentPieces { pieces_len: 20, expected_pieces: e })
+                if e == expected_pieces
+        ));
+        assert!(!parser.validate_all().is_valid());
+    }
+
+    #[test]
+    fn validate_piece_count_with_partial_hash() {
+        let parser =
+            Bencoding::decode(b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces3:abcee")
+                .unwrap();
+        assert!(matches!(
+            parser.validate_piece_count(),
+            Err(BencodingError::InconsistentPieces { pieces_len: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn piece_hash_set_collapses_duplicates() {
+        let pieces = [[1; 20], [2; 20], [1; 20]].concat();
+        let mut data = b"d4:infod6:pieces60:".to_vec();
+        data.extend_from_slice(&pieces);
+        data.extend_from_slice(b"ee");
+        let parser = Bencoding::decode(&data).unwrap();
+        let set = parser.piece_hash_set().unwrap();
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&[1; 20]));
+        assert!(!set.contains(&[3; 20]));
+        assert!(torrent(4, 4, 0).piece_hash_set().unwrap().is_empty());
+    }
+
+    #[test]
+    fn piece_layer_splits_hashes() {
+        let layer = [[1; 32], [2; 32]].concat();
+        let mut data = b"d12:piece layersd32:".to_vec();
+        data.extend_from_slice(&[0xaa; 32]);
+        data.extend_from_slice(b"64:");
+        data.extend_from_slice(&layer);
+        data.extend_from_slice(b"ee");
+        let parser = Bencoding::decode(&data).unwrap();
+
+        assert_eq!(
+            parser.piece_layer(&[0xaa; 32]),
+            Some(vec![[1; 32], [2; 32]])
+        );
+        assert_eq!(parser.piece_layer(&[0xbb; 32]), None);
+    }
+
+    #[test]
+    fn pieces_to_bitfield_packs_msb_first() {
+        let have = [true, false, false, false, false, false, true, true];
+        assert_eq!(pieces_to_bitfield(&have), vec![0b1000_0011]);
+        assert!(pieces_to_bitfield(&[]).is_empty());
+    }
+
+    #[test]
+    fn pieces_to_bitfield_pads_final_byte() {
+        let mut have = vec![true; 9];
+        have[1] = false;
+        assert_eq!(pieces_to_bitfield(&have), vec![0b1011_1111, 0b1000_0000]);
+        assert_eq!(pieces_to_bitfield(&[false, true, true]), vec![0b0110_0000]);
+    }
+
+    #[test]
+    fn suggest_piece_length_by_size_band() {
+        const MIB: u64 = 1 << 20;
+        const GIB: u64 = 1 << 30;
+
+        assert_eq!(suggest_piece_length(0), 256 * 1024);
+        assert_eq!(suggest_piece_length(700 * 1024), 256 * 1024);
+        assert_eq!(suggest_piece_length(512 * MIB), 256 * 1024);
+        assert_eq!(suggest_piece_length(512 * MIB + 1), 512 * 1024);
+        assert_eq!(suggest_piece_length(GIB + 1), MIB);
+        assert_eq!(suggest_piece_length(3 * GIB), 2 * MIB);
+        assert_eq!(suggest_piece_length(8 * GIB), 4 * MIB);
+        assert_eq!(suggest_piece_length(10 * GIB), 8 * MIB);
+        assert_eq!(suggest_piece_length(100 * GIB), 16 * MIB);
+        assert_eq!(suggest_piece_length(u64::MAX), 16 * MIB);
+    }
+
+    #[test]
+    fn suggest_piece_length_keeps_piece_count_reasonable() {
+        let mut size = 1 << 30;
+        while size <= 16 << 30 {
+            let piece_length = suggest_piece_length(size);
+            assert!(piece_length.is_power_of_two());
+            assert!(size.div_ceil(piece_length) <= 2048, "{size}");
+            size += 1 << 28;
+        }
+    }
+}
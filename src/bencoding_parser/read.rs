@@ -0,0 +1,144 @@
+use std::io::{BufRead, Read};
+
+use super::{Bencoding, BencodingError};
+
+impl Bencoding {
+    /// Reads exactly one bencoded dictionary from `reader` and decodes it.
+    ///
+    /// The reader is advanced by no more than the dictionary's own bytes: the
+    /// structure is followed through the buffered bytes, string lengths are
+    /// read from their prefixes and exactly that many bytes are taken. Any
+    /// data following the dictionary on the same stream, such as the next
+    /// protocol message, is left unread.
+    pub fn read_from<R: BufRead>(reader: &mut R) -> Result<Self, BencodingError> {
+        match reader.fill_buf().map_err(BencodingError::Io)?.first() {
+            Some(b'd') => {}
+            Some(&byte) => return Err(BencodingError::UnexpectedByte { position: 0, byte }),
+            None => return Err(BencodingError::UnexpectedEof),
+        }
+
+        let data = read_value_bytes(reader)?;
+
+        return Self::decode(&data);
+    }
+}
+
+fn read_value_bytes<R: BufRead>(reader: &mut R) -> Result<Vec<u8>, BencodingError> {
+    let mut data = Vec::new();
+    let mut depth = 0;
+
+    loop {
+        match read_byte(reader, &mut data)? {
+            b'd' | b'l' => depth += 1,
+            b'e' if depth > 0 => depth -= 1,
+            b'i' => while read_byte(reader, &mut data)? != b'e' {},
+            b'0'..=b'9' => {
+                let start = data.len() - 1;
+                while read_byte(reader, &mut data)? != b':' {}
+
+                let length: u64 = std::str::from_utf8(&data[start..data.len() - 1])
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&length| length <= isize::MAX as u64)
+                    .ok_or(BencodingError::InvalidStringLength { position: start })?;
+                let read = reader
+                    .by_ref()
+                    .take(length)
+                    .read_to_end(&mut data)
+                    .map_err(BencodingError::Io)?;
+                if (read as u64) < length {
+                    return Err(BencodingError::UnexpectedEof);
+                }
+            }
+            byte => {
+                return Err(BencodingError::UnexpectedByte {
+                    position: data.len() - 1,
+                    byte,
+                })
+            }
+        }
+
+        if depth == 0 {
+            return Ok(data);
+        }
+    }
+}
+
+fn read_byte<R: BufRead>(reader: &mut R, data: &mut Vec<u8>) -> Result<u8, BencodingError> {
+    let byte = match reader.fill_buf().map_err(BencodingError::Io)?.first() {
+        Some(&byte) => byte,
+        None => return Err(BencodingError::UnexpectedEof),
+    };
+    reader.consume(1);
+    data.push(byte);
+
+    return Ok(byte);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, Cursor, Read};
+
+    use crate::bencoding_parser::{Bencoding, BencodingError, BencodingValue};
+
+    #[test]
+    fn read_from_stops_at_end_of_dict() {
+        let mut reader = Cursor::new(b"d3:key5:value4:listli1ei2eee\x00\x00\x00\x05hello".to_vec());
+        let parser = Bencoding::read_from(&mut reader).unwrap();
+        let value = match parser.get(b"key").unwrap() {
+            BencodingValue::String(s) => s,
+            _ => panic!(),
+        };
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+
+        assert_eq!(value, b"value");
+        assert_eq!(rest, b"\x00\x00\x00\x05hello");
+    }
+
+    #[test]
+    fn read_from_does_not_treat_string_contents_as_structure() {
+        let mut reader = Cursor::new(b"d3:key3:eee1:xi1ee".to_vec());
+        let parser = Bencoding::read_from(&mut reader).unwrap();
+
+        assert!(parser.get(b"x").is_some());
+        assert!(reader.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_from_consecutive_dicts() {
+        let mut reader = Cursor::new(b"d1:ai1eed1:ai2ee".to_vec());
+        let first = Bencoding::read_from(&mut reader).unwrap();
+        let second = Bencoding::read_from(&mut reader).unwrap();
+
+        assert!(matches!(first.get(b"a"), Some(BencodingValue::Integer(1))));
+        assert!(matches!(second.get(b"a"), Some(BencodingValue::Integer(2))));
+    }
+
+    #[test]
+    fn read_from_truncated_stream_is_error() {
+        let mut reader = Cursor::new(b"d3:key10:valu".to_vec());
+        let result = Bencoding::read_from(&mut reader);
+        assert!(matches!(result, Err(BencodingError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn read_from_rejects_string_length_beyond_isize_max() {
+        let mut reader = Cursor::new(format!("d{}:xe", isize::MAX as u64 + 1).into_bytes());
+        let result = Bencoding::read_from(&mut reader);
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidStringLength { position: 1 })
+        ));
+    }
+
+    #[test]
+    fn read_from_non_dict_does_not_consume() {
+        let mut reader = Cursor::new(b"i42e".to_vec());
+        let result = Bencoding::read_from(&mut reader);
+
+        assert!(matches!(result, Err(BencodingError::UnexpectedByte { .. })));
+        assert_eq!(reader.fill_buf().unwrap(), b"i42e");
+    }
+}
@@ -0,0 +1,134 @@
+use super::{Bencoding, BencodingDict, BencodingValue};
+
+const REDACTED: &[u8] = b"REDACTED";
+
+/// Keys whose values are tracker or web seed URLs. Their query strings are
+/// masked. The values may be a single URL or (nested) lists of URLs.
+const URL_KEYS: [&[u8]; 4] = [b"announce", b"announce-list", b"url-list", b"httpseeds"];
+
+/// Keys that hold credentials outright. Their values are replaced entirely,
+/// wherever they appear in the tree.
+const SECRET_KEYS: [&[u8]; 4] = [b"passkey", b"authkey", b"torrent_pass", b"secret"];
+
+impl Bencoding {
+    /// Returns a copy of the decoded dictionary that is safe to log.
+    ///
+    /// - The query string of every URL under `announce`, `announce-list`,
+    ///   `url-list` and `httpseeds` is replaced with `REDACTED`, so
+    ///   `http://tracker.example/announce?passkey=abc` becomes
+    ///   `http://tracker.example/announce?REDACTED`.
+    /// - The value of any `passkey`, `authkey`, `torrent_pass` or `secret` key,
+    ///   at any depth, is replaced with the string `REDACTED`.
+    ///
+    /// Credentials embedded elsewhere, such as in a URL path segment, are not
+    /// detected.
+    pub fn redacted(&self) -> BencodingValue {
+        return BencodingValue::Dict(redact_dict(&self.dict));
+    }
+}
+
+fn redact_dict(dict: &BencodingDict) -> BencodingDict {
+    let mut redacted = BencodingDict::new();
+    for (key, value) in dict {
+        let value = if SECRET_KEYS.contains(&key.as_slice()) {
+            BencodingValue::String(REDACTED.to_vec())
+        } else if URL_KEYS.contains(&key.as_slice()) {
+            redact_urls(value)
+        } else {
+            redact_value(value)
+        };
+        redacted.insert(key.clone(), value);
+    }
+
+    return redacted;
+}
+
+fn redact_value(value: &BencodingValue) -> BencodingValue {
+    match value {
+        BencodingValue::Dict(dict) => return BencodingValue::Dict(redact_dict(dict)),
+        BencodingValue::List(list) => {
+            return BencodingValue::List(list.iter().map(redact_value).collect())
+        }
+        _ => return value.clone(),
+    }
+}
+
+fn redact_urls(value: &BencodingValue) -> BencodingValue {
+    match value {
+        BencodingValue::String(url) => return BencodingValue::String(redact_query(url)),
+        BencodingValue::List(list) => {
+            return BencodingValue::List(list.iter().map(redact_urls).collect())
+        }
+        _ => return redact_value(value),
+    }
+}
+
+fn redact_query(url: &[u8]) -> Vec<u8> {
+    match url.iter().position(|&b| b == b'?') {
+        Some(idx) => return [&url[..=idx], REDACTED].concat(),
+        None => return url.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{Bencoding, BencodingValue};
+
+    #[test]
+    fn redacted_masks_announce_query_string() {
+        let parser =
+            Bencoding::decode(b"d8:announce44:http://tracker.example/announce?passkey=abc1e")
+                .unwrap();
+        let redacted = match parser.redacted() {
+            BencodingValue::Dict(d) => d,
+            _ => panic!(),
+        };
+        let announce = match &redacted[&b"announce".to_vec()] {
+            BencodingValue::String(s) => s,
+            _ => panic!(),
+        };
+        assert_eq!(announce, b"http://tracker.example/announce?REDACTED");
+    }
+
+    #[test]
+    fn redacted_masks_announce_list_tiers() {
+        let parser = Bencoding::decode(
+            b"d13:announce-listll17:http://a.example/el22:http://b.example/?k=v1eee",
+        )
+        .unwrap();
+        let redacted = parser.redacted();
+        let tiers = redacted.as_dict().unwrap()[&b"announce-list".to_vec()]
+            .as_list()
+            .unwrap();
+        let tier = tiers[0].as_list().unwrap();
+
+        assert_eq!(tier[0].as_string().unwrap(), b"http://a.example/");
+        assert_eq!(
+            tiers[1].as_list().unwrap()[0].as_string().unwrap(),
+            b"http://b.example/?REDACTED"
+        );
+    }
+
+    #[test]
+    fn redacted_masks_nested_passkey() {
+        let parser = Bencoding::decode(b"d4:infod4:name3:foo7:passkey6:s3cr3tee").unwrap();
+        let redacted = parser.redacted();
+        let info = redacted.as_dict().unwrap()[&b"info".to_vec()]
+            .as_dict()
+            .unwrap();
+
+        assert_eq!(info[&b"passkey".to_vec()].as_string().unwrap(), b"REDACTED");
+        assert_eq!(info[&b"name".to_vec()].as_string().unwrap(), b"foo");
+    }
+
+    #[test]
+    fn redacted_leaves_original_untouched() {
+        let parser = Bencoding::decode(b"d7:passkey6:s3cr3te").unwrap();
+        parser.redacted();
+        let passkey = match parser.get(b"passkey").unwrap() {
+            BencodingValue::String(s) => s,
+            _ => panic!(),
+        };
+        assert_eq!(passkey, b"s3cr3t");
+    }
+}
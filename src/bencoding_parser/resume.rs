@@ -0,0 +1,164 @@
+use super::{Bencoding, BencodingDict, BencodingValue};
+
+/// A client's saved state for one torrent, stored bencoded like the torrent
+/// itself. Read with `Bencoding::resume_data` and written back with
+/// `ResumeData::to_bencoding`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResumeData {
+    /// The number of bytes downloaded so far, from `downloaded`.
+    pub downloaded: i64,
+    /// The number of bytes uploaded so far, from `uploaded`.
+    pub uploaded: i64,
+    /// The pieces the client has, from `bitfield`, packed as by
+    /// `pieces_to_bitfield`.
+    pub bitfield: Vec<u8>,
+    /// Whether the download finished, from the integer `completed`.
+    pub completed: bool,
+    /// The tracker tiers the client uses, from `trackers`, a list of lists
+    /// of URLs like `announce-list`.
+    pub trackers: Vec<Vec<Vec<u8>>>,
+}
+
+impl ResumeData {
+    /// Whether the client has the piece at `index`, according to
+    /// `bitfield`. Pieces past the end of the bitfield are missing.
+    pub fn has_piece(&self, index: usize) -> bool {
+        return self
+            .bitfield
+            .get(index / 8)
+            .is_some_and(|byte| byte & (0x80 >> (index % 8)) != 0);
+    }
+
+    /// The resume data as a structure, ready to `encode` to a state file.
+    /// Every field is written, defaults included, with `completed` as 0
+    /// or 1.
+    pub fn to_bencoding(&self) -> Bencoding {
+        let trackers = self
+            .trackers
+            .iter()
+            .map(|tier| {
+                let urls = tier.iter().cloned().map(BencodingValue::String).collect();
+                return BencodingValue::List(urls);
+            })
+            .collect();
+
+        let mut dict = BencodingDict::new();
+        dict.insert(
+            b"bitfield".to_vec(),
+            BencodingValue::String(self.bitfield.clone()),
+        );
+        dict.insert(
+            b"completed".to_vec(),
+            BencodingValue::Integer(self.completed as i64),
+        );
+        dict.insert(
+            b"downloaded".to_vec(),
+            BencodingValue::Integer(self.downloaded),
+        );
+        dict.insert(b"trackers".to_vec(), BencodingValue::List(trackers));
+        dict.insert(b"uploaded".to_vec(), BencodingValue::Integer(self.uploaded));
+
+        return Bencoding {
+            dict,
+            non_canonical: None,
+        };
+    }
+}
+
+impl Bencoding {
+    /// Reads the dictionary as resume data.
+    ///
+    /// Missing fields take their default: 0, empty or not completed. Returns
+    /// `None` if a field has the wrong type, if `downloaded` or `uploaded`
+    /// is negative, or if `trackers` isn't a list of lists of strings, as a
+    /// corrupt state file is better rechecked than trusted.
+    pub fn resume_data(&self) -> Option<ResumeData> {
+        let mut resume = ResumeData::default();
+
+        if let Some(value) = self.dict.get(b"downloaded".as_slice()) {
+            resume.downloaded = value.as_integer().filter(|&bytes| bytes >= 0)?;
+        }
+        if let Some(value) = self.dict.get(b"uploaded".as_slice()) {
+            resume.uploaded = value.as_integer().filter(|&bytes| bytes >= 0)?;
+        }
+        if let Some(value) = self.dict.get(b"bitfield".as_slice()) {
+            resume.bitfield = value.as_string()?.to_vec();
+        }
+        if let Some(value) = self.dict.get(b"completed".as_slice()) {
+            resume.completed = value.as_integer()? != 0;
+        }
+        if let Some(value) = self.dict.get(b"trackers".as_slice()) {
+            for tier in value.as_list()? {
+                let mut urls = Vec::new();
+                for url in tier.as_list()? {
+                    urls.push(url.as_string()?.to_vec());
+                }
+                resume.trackers.push(urls);
+            }
+        }
+
+        return Some(resume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{pieces_to_bitfield, Bencoding, ResumeData};
+
+    const STATE: &[u8] = b"d8:bitfield1:\xA09:completedi0e10:downloadedi32768e8:trackersll12:http://a/annel12:http://b/ann12:http://c/annee8:uploadedi4096ee";
+
+    #[test]
+    fn resume_data_fields() {
+        let resume = Bencoding::decode(STATE).unwrap().resume_data().unwrap();
+
+        assert_eq!(resume.downloaded, 32768);
+        assert_eq!(resume.uploaded, 4096);
+        assert_eq!(resume.bitfield, pieces_to_bitfield(&[true, false, true]));
+        assert!(!resume.completed);
+        assert_eq!(
+            resume.trackers,
+            vec![
+                vec![b"http://a/ann".to_vec()],
+                vec![b"http://b/ann".to_vec(), b"http://c/ann".to_vec()],
+            ]
+        );
+        assert!(resume.has_piece(0) && !resume.has_piece(1) && resume.has_piece(2));
+        assert!(!resume.has_piece(8));
+    }
+
+    #[test]
+    fn resume_data_round_trip() {
+        let parser = Bencoding::decode(STATE).unwrap();
+        let resume = parser.resume_data().unwrap();
+
+        assert_eq!(resume.to_bencoding().canonicalize(), STATE);
+
+        let completed = ResumeData {
+            completed: true,
+            ..resume
+        };
+        assert_eq!(
+            completed.to_bencoding().resume_data(),
+            Some(completed.clone())
+        );
+    }
+
+    #[test]
+    fn resume_data_defaults_missing_fields() {
+        let resume = Bencoding::decode(b"de").unwrap().resume_data();
+        assert_eq!(resume, Some(ResumeData::default()));
+    }
+
+    #[test]
+    fn resume_data_rejects_malformed_fields() {
+        for data in [
+            b"d10:downloaded3:abce".as_slice(),
+            b"d8:uploadedi-1ee",
+            b"d8:bitfieldi1ee",
+            b"d8:trackersl12:http://a/annee",
+        ] {
+            let parser = Bencoding::decode(data).unwrap();
+            assert_eq!(parser.resume_data(), None, "{:?}", data);
+        }
+    }
+}
@@ -0,0 +1,726 @@
+use std::fmt::Display;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use super::{Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+impl ser::Error for BencodingError {
+    fn custom<T: Display>(message: T) -> Self {
+        return BencodingError::Serialize(message.to_string());
+    }
+}
+
+impl Bencoding {
+    /// Builds the structure from a value implementing `Serialize`, such as a
+    /// `#[derive(Serialize)]` struct describing a torrent, ready to be
+    /// encoded or inspected like a decoded one.
+    ///
+    /// The value must serialize to a map or struct, which becomes the
+    /// top-level dictionary. Values map onto bencoding as follows:
+    /// - integers and `bool`s, as 0 or 1, become integers; `u64`s above
+    ///   `i64::MAX` are `Err(IntegerOverflow)`;
+    /// - strings and `char`s become strings of their UTF-8 encoding, and byte
+    ///   strings become strings as they are. A `Vec<u8>` serializes as a
+    ///   sequence, and so as a list of integers, unless it's marked with
+    ///   `#[serde(with = "serde_bytes")]` or similar;
+    /// - sequences and tuples become lists, and maps and structs dictionaries,
+    ///   whose keys must be strings or byte strings;
+    /// - `None` leaves its struct field or map entry out, since bencoding has
+    ///   no null, and is `Err(Unsupported)` elsewhere, such as in a list;
+    /// - unit enum variants become their name, and other variants a dictionary
+    ///   holding their content under their name.
+    ///
+    /// Floating-point numbers and `()` can't be represented and are
+    /// `Err(Unsupported)`. Errors raised by `Serialize` implementations
+    /// themselves are `Err(Serialize)`. The result's dictionaries are sorted,
+    /// as by `canonicalize_in_place`. Requires the `serde` feature.
+    pub fn from_serializable<T: Serialize + ?Sized>(value: &T) -> Result<Self, BencodingError> {
+        match value.serialize(ValueSerializer)? {
+            Some(BencodingValue::Dict(dict)) => {
+                // Struct fields come in declaration order.
+                let mut parser = Bencoding {
+                    dict,
+                    non_canonical: None,
+                };
+                parser.canonicalize_in_place();
+
+                return Ok(parser);
+            }
+            _ => {
+                return Err(BencodingError::Unsupported(
+                    "top-level value that isn't a map",
+                ))
+            }
+        }
+    }
+}
+
+/// Serializes a value into a `BencodingValue`, or `None` for a value that
+/// is left out of its containing dictionary.
+struct ValueSerializer;
+
+type Value = Option<BencodingValue>;
+
+fn integer<T: TryInto<i64>>(value: T) -> Result<Value, BencodingError> {
+    let value = value
+        .try_into()
+        .map_err(|_| BencodingError::IntegerOverflow)?;
+
+    return Ok(Some(BencodingValue::Integer(value)));
+}
+
+/// A dictionary holding `value` under `variant`, for enum variants with
+/// content.
+fn tagged(variant: &str, value: BencodingValue) -> Value {
+    let mut dict = BencodingDict::new();
+    dict.insert(variant.as_bytes().to_vec(), value);
+
+    return Some(BencodingValue::Dict(dict));
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = DictSerializer;
+    type SerializeStruct = DictSerializer;
+    type SerializeStructVariant = DictSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, BencodingError> {
+        return integer(v as i64);
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, BencodingError> {
+        return integer(v);
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Value, BencodingError> {
+        return Err(BencodingError::Unsupported("floating-point number"));
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Value, BencodingError> {
+        return Err(BencodingError::Unsupported("floating-point number"));
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, BencodingError> {
+        return self.serialize_str(v.encode_utf8(&mut [0; 4]));
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, BencodingError> {
+        return self.serialize_bytes(v.as_bytes());
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, BencodingError> {
+        return Ok(Some(BencodingValue::String(v.to_vec())));
+    }
+
+    fn serialize_none(self) -> Result<Value, BencodingError> {
+        return Ok(None);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, BencodingError> {
+        return value.serialize(self);
+    }
+
+    fn serialize_unit(self) -> Result<Value, BencodingError> {
+        return Err(BencodingError::Unsupported("unit value"));
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value, BencodingError> {
+        return self.serialize_unit();
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Value, BencodingError> {
+        return self.serialize_str(variant);
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Value, BencodingError> {
+        return value.serialize(self);
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, BencodingError> {
+        match value.serialize(self)? {
+            Some(value) => return Ok(tagged(variant, value)),
+            None => return Err(BencodingError::Unsupported("None as enum variant content")),
+        }
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, BencodingError> {
+        return Ok(ListSerializer {
+            list: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        });
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, BencodingError> {
+        return self.serialize_seq(Some(len));
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer, BencodingError> {
+        return self.serialize_seq(Some(len));
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer, BencodingError> {
+        return Ok(ListSerializer {
+            list: Vec::with_capacity(len),
+            variant: Some(variant),
+        });
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<DictSerializer, BencodingError> {
+        return Ok(DictSerializer {
+            dict: BencodingDict::new(),
+            key: None,
+            variant: None,
+        });
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<DictSerializer, BencodingError> {
+        return self.serialize_map(Some(len));
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<DictSerializer, BencodingError> {
+        return Ok(DictSerializer {
+            dict: BencodingDict::new(),
+            key: None,
+            variant: Some(variant),
+        });
+    }
+}
+
+/// Collects the elements of a sequence, tuple or tuple variant.
+struct ListSerializer {
+    list: Vec<BencodingValue>,
+    /// The variant name of a tuple variant.
+    variant: Option<&'static str>,
+}
+
+impl ListSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BencodingError> {
+        match value.serialize(ValueSerializer)? {
+            Some(value) => self.list.push(value),
+            None => return Err(BencodingError::Unsupported("None in a list")),
+        }
+
+        return Ok(());
+    }
+
+    fn finish(self) -> Result<Value, BencodingError> {
+        let list = BencodingValue::List(self.list);
+        match self.variant {
+            Some(variant) => return Ok(tagged(variant, list)),
+            None => return Ok(Some(list)),
+        }
+    }
+}
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        return self.push(value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        return self.push(value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        return self.push(value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        return self.push(value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+/// Collects the entries of a map, struct or struct variant.
+struct DictSerializer {
+    dict: BencodingDict,
+    /// The key of a map entry whose value is yet to come.
+    key: Option<Vec<u8>>,
+    /// The variant name of a struct variant.
+    variant: Option<&'static str>,
+}
+
+impl DictSerializer {
+    fn insert<T: Serialize + ?Sized>(
+        &mut self,
+        key: Vec<u8>,
+        value: &T,
+    ) -> Result<(), BencodingError> {
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.dict.insert(key, value);
+        }
+
+        return Ok(());
+    }
+
+    fn finish(self) -> Result<Value, BencodingError> {
+        let dict = BencodingValue::Dict(self.dict);
+        match self.variant {
+            Some(variant) => return Ok(tagged(variant, dict)),
+            None => return Ok(Some(dict)),
+        }
+    }
+}
+
+impl ser::SerializeMap for DictSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(KeySerializer)?);
+
+        return Ok(());
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or(BencodingError::Unsupported("map value without a key"))?;
+
+        return self.insert(key, value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+impl ser::SerializeStruct for DictSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        return self.insert(key.as_bytes().to_vec(), value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+impl ser::SerializeStructVariant for DictSerializer {
+    type Ok = Value;
+    type Error = BencodingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        return self.insert(key.as_bytes().to_vec(), value);
+    }
+
+    fn end(self) -> Result<Value, BencodingError> {
+        return self.finish();
+    }
+}
+
+/// Serializes a map key, which must be a string or a byte string.
+struct KeySerializer;
+
+const NON_STRING_KEY: BencodingError = BencodingError::Unsupported("map key that isn't a string");
+
+impl ser::Serializer for KeySerializer {
+    type Ok = Vec<u8>;
+    type Error = BencodingError;
+    type SerializeSeq = Impossible<Vec<u8>, BencodingError>;
+    type SerializeTuple = Impossible<Vec<u8>, BencodingError>;
+    type SerializeTupleStruct = Impossible<Vec<u8>, BencodingError>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, BencodingError>;
+    type SerializeMap = Impossible<Vec<u8>, BencodingError>;
+    type SerializeStruct = Impossible<Vec<u8>, BencodingError>;
+    type SerializeStructVariant = Impossible<Vec<u8>, BencodingError>;
+
+    fn serialize_str(self, v: &str) -> Result<Vec<u8>, BencodingError> {
+        return Ok(v.as_bytes().to_vec());
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Vec<u8>, BencodingError> {
+        return Ok(v.to_vec());
+    }
+
+    fn serialize_char(self, v: char) -> Result<Vec<u8>, BencodingError> {
+        return self.serialize_str(v.encode_utf8(&mut [0; 4]));
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Vec<u8>, BencodingError> {
+        return self.serialize_str(variant);
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Vec<u8>, BencodingError> {
+        return value.serialize(self);
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, BencodingError> {
+        return Err(NON_STRING_KEY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use crate::bencoding_parser::{Bencoding, BencodingError};
+
+    /// A small single-file torrent, as a user of the crate would describe it.
+    #[derive(Serialize)]
+    struct Torrent {
+        announce: String,
+        #[serde(rename = "creation date")]
+        creation_date: Option<i64>,
+        comment: Option<String>,
+        info: Info,
+    }
+
+    #[derive(Serialize)]
+    struct Info {
+        name: String,
+        length: u64,
+        #[serde(rename = "piece length")]
+        piece_length: u32,
+        #[serde(with = "serde_bytes")]
+        pieces: Vec<u8>,
+        private: bool,
+    }
+
+    #[test]
+    fn from_serializable_of_torrent_struct() {
+        let torrent = Torrent {
+            announce: String::from("http://tracker/announce"),
+            creation_date: Some(1700000000),
+            comment: None,
+            info: Info {
+                name: String::from("a.txt"),
+                length: 5,
+                piece_length: 16384,
+                pieces: vec![0xaa; 20],
+                private: true,
+            },
+        };
+        let parser = Bencoding::from_serializable(&torrent).unwrap();
+
+        let mut expected = b"d8:announce23:http://tracker/announce13:creation datei1700000000e4:infod6:lengthi5e4:name5:a.txt12:piece lengthi16384e6:pieces20:".to_vec();
+        expected.extend_from_slice(&[0xaa; 20]);
+        expected.extend_from_slice(b"7:privatei1eee");
+        assert_eq!(parser.canonicalize(), expected);
+        assert_eq!(parser.name(), Some(b"a.txt".as_slice()));
+        assert!(parser.is_private());
+        assert!(parser.validate_info().is_ok());
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn from_serializable_sorts_struct_fields() {
+        let torrent = Torrent {
+            announce: String::from("http://tracker/announce"),
+            creation_date: None,
+            comment: Some(String::from("c")),
+            info: Info {
+                name: String::from("a.txt"),
+                length: 5,
+                piece_length: 16384,
+                pieces: vec![0xaa; 20],
+                private: false,
+            },
+        };
+        let parser = Bencoding::from_serializable(&torrent).unwrap();
+        let encoded = parser.encode();
+
+        assert_eq!(encoded, parser.canonicalize());
+        assert!(Bencoding::decode(&encoded)
+            .unwrap()
+            .assert_canonical()
+            .is_ok());
+    }
+
+    #[derive(Serialize)]
+    enum Kind {
+        Plain,
+        Sized(u8),
+        Pair(u8, u8),
+        Named { x: u8 },
+    }
+
+    #[test]
+    fn from_serializable_of_enums_lists_and_maps() {
+        let mut map = BTreeMap::new();
+        map.insert("plain", vec![Kind::Plain]);
+        map.insert(
+            "other",
+            vec![Kind::Sized(1), Kind::Pair(2, 3), Kind::Named { x: 4 }],
+        );
+        let parser = Bencoding::from_serializable(&map).unwrap();
+
+        assert_eq!(
+            parser.canonicalize(),
+            b"d5:otherld5:Sizedi1eed4:Pairli2ei3eeed5:Namedd1:xi4eeee5:plainl5:Plainee"
+        );
+    }
+
+    #[test]
+    fn from_serializable_of_unsupported_values_is_error() {
+        assert!(matches!(
+            Bencoding::from_serializable(&vec![1, 2]),
+            Err(BencodingError::Unsupported(_))
+        ));
+
+        let mut floats = BTreeMap::new();
+        floats.insert("ratio", 1.5);
+        assert!(matches!(
+            Bencoding::from_serializable(&floats),
+            Err(BencodingError::Unsupported(_))
+        ));
+
+        let mut integer_keys = BTreeMap::new();
+        integer_keys.insert(1, 2);
+        assert!(matches!(
+            Bencoding::from_serializable(&integer_keys),
+            Err(BencodingError::Unsupported(_))
+        ));
+
+        let mut too_big = BTreeMap::new();
+        too_big.insert("n", u64::MAX);
+        assert!(matches!(
+            Bencoding::from_serializable(&too_big),
+            Err(BencodingError::IntegerOverflow)
+        ));
+
+        let mut holes = BTreeMap::new();
+        holes.insert("list", vec![Some(1), None]);
+        assert!(matches!(
+            Bencoding::from_serializable(&holes),
+            Err(BencodingError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Bencoding, BencodingDict, BencodingValue};
+
+/// One signature of a signed torrent (BEP 35), read by
+/// `Bencoding::signatures`.
+#[derive(Debug, Clone)]
+pub struct SignatureEntry<'a> {
+    /// The signer's identity, the key of the entry in `signatures`, such as
+    /// `org.bittorrent.signer`.
+    pub signer: &'a [u8],
+    /// The signer's X.509 certificate in DER form, from `certificate`. It
+    /// may be left out when the certificate is distributed separately.
+    pub certificate: Option<&'a [u8]>,
+    /// The signature itself, from `signature`.
+    pub signature: &'a [u8],
+    /// Extra signed data, from `info`. The signature covers the `info`
+    /// dictionary followed by this, if present.
+    pub info: Option<&'a BencodingDict>,
+}
+
+impl Bencoding {
+    /// The signatures from the top-level `signatures` dictionary (BEP 35), in
+    /// signer order.
+    ///
+    /// The signatures are not verified. An entry's `certificate` and `info`
+    /// are `None` if missing or of the wrong type. Returns `None` if
+    /// `signatures` is missing or isn't a dictionary, or an entry isn't a
+    /// dictionary with a string `signature`.
+    pub fn signatures(&self) -> Option<Vec<SignatureEntry<'_>>> {
+        let mut entries = Vec::new();
+        for (signer, entry) in self.get_dict(b"signatures")? {
+            let entry = entry.as_dict()?;
+            entries.push(SignatureEntry {
+                signer,
+                certificate: entry
+                    .get(b"certificate".as_slice())
+                    .and_then(BencodingValue::as_string),
+                signature: entry.get(b"signature".as_slice())?.as_string()?,
+                info: entry
+                    .get(b"info".as_slice())
+                    .and_then(BencodingValue::as_dict),
+            });
+        }
+        entries.sort_by_key(|entry| entry.signer);
+
+        return Some(entries);
+    }
+
+    /// The root certificate of an SSL torrent, such as the ones created by
+    /// libtorrent, from `info.ssl-cert`: a PEM certificate that peers'
+    /// certificates must be signed by.
+    ///
+    /// BEP 35 signed torrents have no torrent-wide root certificate; each
+    /// signature carries its own, see `signatures`. As the field is inside
+    /// `info`, it's covered by the info-hash.
+    pub fn root_certificate(&self) -> Option<&[u8]> {
+        return self.info_dict()?.get(b"ssl-cert".as_slice())?.as_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::Bencoding;
+
+    #[test]
+    fn signatures_of_signed_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod4:name1:ae10:signaturesd5:b.orgd9:signature2:s2e5:a.orgd11:certificate4:cert4:infod1:xi1ee9:signature2:s1eee",
+        )
+        .unwrap();
+        let signatures = parser.signatures().unwrap();
+
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(signatures[0].signer, b"a.org");
+        assert_eq!(signatures[0].certificate, Some(b"cert".as_slice()));
+        assert_eq!(signatures[0].signature, b"s1");
+        assert_eq!(
+            signatures[0].info.unwrap()[b"x".as_slice()].as_integer(),
+            Some(1)
+        );
+        assert_eq!(signatures[1].signer, b"b.org");
+        assert_eq!(signatures[1].certificate, None);
+        assert!(signatures[1].info.is_none());
+    }
+
+    #[test]
+    fn signatures_missing_or_malformed_is_none() {
+        assert!(Bencoding::decode(b"d4:infodee")
+            .unwrap()
+            .signatures()
+            .is_none());
+        let parser = Bencoding::decode(b"d10:signaturesd5:a.orgd11:certificate4:certeee").unwrap();
+        assert!(parser.signatures().is_none());
+    }
+
+    #[test]
+    fn root_certificate_of_ssl_torrent() {
+        let parser =
+            Bencoding::decode(b"d4:infod4:name1:a8:ssl-cert11:-----BEGIN e8:ssl-cert1:xe").unwrap();
+        assert_eq!(parser.root_certificate(), Some(b"-----BEGIN ".as_slice()));
+        assert_eq!(
+            Bencoding::decode(b"d8:ssl-cert1:xe")
+                .unwrap()
+                .root_certificate(),
+            None
+        );
+    }
+}
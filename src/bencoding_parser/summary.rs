@@ -0,0 +1,103 @@
+use super::Bencoding;
+
+/// Binary size units for `format_size`, after bytes.
+const SIZE_UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+impl Bencoding {
+    /// A one-line description of the torrent for listings, such as
+    /// `ubuntu.iso — 2.4 GiB, 4891 pieces, 1 tracker`.
+    ///
+    /// It's made of the name, the total size in binary units, the number of
+    /// pieces and the number of distinct trackers. The piece count is
+    /// computed from the size for torrents without `pieces`, such as v2-only
+    /// ones. Parts that are missing or malformed are shown as `?`, so this
+    /// never fails.
+    pub fn summary(&self) -> String {
+        let name = match self.name() {
+            Some(name) => String::from_utf8_lossy(name).into_owned(),
+            None => String::from("?"),
+        };
+        let total_length = self.total_length();
+        let size = match total_length {
+            Some(length) => format_size(length as u64),
+            None => String::from("? B"),
+        };
+        let piece_count = self.piece_count().or_else(|| {
+            let piece_length = self.piece_length()? as u64;
+            return Some((total_length? as u64).div_ceil(piece_length) as usize);
+        });
+        let pieces = match piece_count {
+            Some(1) => String::from("1 piece"),
+            Some(count) => format!("{} pieces", count),
+            None => String::from("? pieces"),
+        };
+        let trackers = match self.tracker_urls().len() {
+            1 => String::from("1 tracker"),
+            count => format!("{} trackers", count),
+        };
+
+        return format!("{} — {}, {}, {}", name, size, pieces, trackers);
+    }
+}
+
+/// Formats a byte count with one decimal in the largest binary unit it
+/// reaches, or as plain bytes below 1 KiB.
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    return format!("{:.1} {}", size, SIZE_UNITS[unit]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_size;
+    use crate::bencoding_parser::Bencoding;
+
+    #[test]
+    fn summary_of_single_file_torrent() {
+        let pieces = "x".repeat(3 * 20);
+        let data = format!(
+            "d8:announce5:udp:a4:infod6:lengthi2621440000e4:name10:ubuntu.iso12:piece lengthi1048576000e6:pieces{}:{}ee",
+            pieces.len(),
+            pieces
+        );
+        let parser = Bencoding::decode(data.as_bytes()).unwrap();
+        assert_eq!(
+            parser.summary(),
+            "ubuntu.iso — 2.4 GiB, 3 pieces, 1 tracker"
+        );
+    }
+
+    #[test]
+    fn summary_counts_distinct_trackers_and_computes_pieces() {
+        let parser = Bencoding::decode(
+            b"d8:announce1:a13:announce-listll1:a1:bel1:cee4:infod6:lengthi5e4:name1:d12:piece lengthi4eee",
+        )
+        .unwrap();
+        assert_eq!(parser.summary(), "d — 5 B, 2 pieces, 3 trackers");
+    }
+
+    #[test]
+    fn summary_of_empty_dict() {
+        let parser = Bencoding::decode(b"de").unwrap();
+        assert_eq!(parser.summary(), "? — ? B, ? pieces, 0 trackers");
+    }
+
+    #[test]
+    fn format_size_units() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(1536 * 1024), "1.5 MiB");
+        assert_eq!(format_size(u64::MAX), "16.0 EiB");
+    }
+}
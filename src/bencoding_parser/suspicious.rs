@@ -0,0 +1,120 @@
+use super::{Bencoding, BencodingValue, PathSegment};
+
+/// Keys that hold integers in torrents, tracker responses and resume data.
+const INTEGER_KEYS: &[&[u8]] = &[
+    b"complete",
+    b"creation date",
+    b"downloaded",
+    b"incomplete",
+    b"interval",
+    b"left",
+    b"length",
+    b"meta version",
+    b"min interval",
+    b"piece length",
+    b"port",
+    b"private",
+    b"uploaded",
+];
+
+/// Why `suspicious_fields` flagged a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suspicion {
+    /// The string is a decimal number, as if the integer had been written
+    /// without its `i`/`e` delimiters.
+    DecimalDigits,
+    /// The string is 1, 2, 4 or 8 bytes long, as if the integer had been
+    /// written as a raw binary number. Holds its little-endian value;
+    /// swapping the bytes gives the big-endian one.
+    BinaryInteger(u64),
+}
+
+/// A string value that looks like it should have been an integer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuspiciousField<'a> {
+    /// The path from the top-level dictionary to the string.
+    pub path: Vec<PathSegment<'a>>,
+    /// The string itself.
+    pub value: &'a [u8],
+    /// Why it was flagged.
+    pub suspicion: Suspicion,
+}
+
+impl Bencoding {
+    /// Flags strings stored under keys that normally hold integers, such as
+    /// `length` or `piece length`, to help track down encoder bugs.
+    ///
+    /// This is an advisory heuristic, not a validation: only the last key of
+    /// each path is looked at, wherever it is in the structure, and a
+    /// flagged value may be legitimate in a format that reuses the key name.
+    /// Strings of digits are reported as `DecimalDigits`; other strings of
+    /// 1, 2, 4 or 8 bytes as `BinaryInteger`. Strings under other keys, and
+    /// other strings under these keys, are never reported.
+    pub fn suspicious_fields(&self) -> Vec<SuspiciousField<'_>> {
+        let mut found = Vec::new();
+        self.walk(|path, value| {
+            let s = match (path.last(), value) {
+                (Some(PathSegment::Key(key)), BencodingValue::String(s))
+                    if INTEGER_KEYS.contains(key) =>
+                {
+                    s
+                }
+                _ => return,
+            };
+            let digits = s.strip_prefix(b"-").unwrap_or(s);
+            let suspicion = if !digits.is_empty() && digits.iter().all(u8::is_ascii_digit) {
+                Suspicion::DecimalDigits
+            } else if matches!(s.len(), 1 | 2 | 4 | 8) {
+                let mut bytes = [0; 8];
+                bytes[..s.len()].copy_from_slice(s);
+                Suspicion::BinaryInteger(u64::from_le_bytes(bytes))
+            } else {
+                return;
+            };
+
+            found.push(SuspiciousField {
+                path: path.to_vec(),
+                value: s,
+                suspicion,
+            });
+        });
+
+        return found;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{Bencoding, PathSegment, Suspicion};
+
+    #[test]
+    fn suspicious_fields_flags_decimal_strings() {
+        let parser = Bencoding::decode(b"d4:infod6:length4:10244:name4:1024ee").unwrap();
+        let found = parser.suspicious_fields();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].path,
+            vec![PathSegment::Key(b"info"), PathSegment::Key(b"length")]
+        );
+        assert_eq!(found[0].value, b"1024");
+        assert_eq!(found[0].suspicion, Suspicion::DecimalDigits);
+    }
+
+    #[test]
+    fn suspicious_fields_flags_binary_integers() {
+        let parser =
+            Bencoding::decode(b"d4:infod12:piece length4:\x00\x40\x00\x004:port3:abcee").unwrap();
+        let found = parser.suspicious_fields();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].suspicion, Suspicion::BinaryInteger(16384));
+    }
+
+    #[test]
+    fn suspicious_fields_ignores_integers_and_other_keys() {
+        let parser =
+            Bencoding::decode(b"d7:comment4:12346:lengthi5e4:porti-1e3:urll4:1234ee").unwrap();
+        assert!(parser.suspicious_fields().is_empty());
+    }
+}
@@ -0,0 +1,1864 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::{escape_bytes, Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+/// BEP 52 requires v2 piece lengths to be at least 16 KiB.
+const V2_MIN_PIECE_LENGTH: i64 = 16 * 1024;
+
+/// Top-level keys defined by BEP 3 and the widely implemented extensions:
+/// web seeds (BEP 17, BEP 19), multitracker (BEP 12), DHT nodes (BEP 5) and v2
+/// piece layers (BEP 52).
+const STANDARD_TOP_LEVEL_KEYS: [&[u8]; 11] = [
+    b"announce",
+    b"announce-list",
+    b"comment",
+    b"created by",
+    b"creation date",
+    b"encoding",
+    b"httpseeds",
+    b"info",
+    b"nodes",
+    b"piece layers",
+    b"url-list",
+];
+
+/// A file described by a torrent's `info` dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentFile {
+    /// Path components relative to the download directory. For single-file
+    /// torrents this is just the torrent's `name`.
+    pub path: Vec<Vec<u8>>,
+    pub length: i64,
+    /// Whether this is a BEP 47 padding file, marked with a `p` in its `attr`
+    /// string, which only aligns the next file to a piece boundary.
+    pub padding: bool,
+}
+
+impl TorrentFile {
+    /// The path as clean relative segments, safe to join onto a download
+    /// directory.
+    ///
+    /// Components are split on both `/` and `\`, since some broken torrents
+    /// embed Windows or full paths in a single component, and empty and `.`
+    /// segments are dropped. Returns `None` if the path is absolute (starting
+    /// with a separator or a drive letter such as `C:`), contains a `..`
+    /// segment, or has no segments left.
+    pub fn normalized_path(&self) -> Option<Vec<Vec<u8>>> {
+        let first = self.path.first()?;
+        let rooted = matches!(first.first(), Some(b'/' | b'\\'));
+        let drive = first.len() >= 2 && first[0].is_ascii_alphabetic() && first[1] == b':';
+        if rooted || drive {
+            return None;
+        }
+
+        let mut segments = Vec::new();
+        for component in &self.path {
+            for segment in component.split(|&b| b == b'/' || b == b'\\') {
+                match segment {
+                    b"" | b"." => {}
+                    b".." => return None,
+                    _ => segments.push(segment.to_vec()),
+                }
+            }
+        }
+        if segments.is_empty() {
+            return None;
+        }
+
+        return Some(segments);
+    }
+}
+
+/// A file along with its place in the torrent's data, as yielded by
+/// `Bencoding::file_entries`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub path: Vec<Vec<u8>>,
+    pub length: i64,
+    /// The offset of the file's first byte in the concatenated data.
+    pub offset: u64,
+    /// The pieces holding data of the file.
+    pub piece_range: Range<usize>,
+}
+
+/// The non-standard per-file hashes some old torrent makers added for
+/// cross-checking files against other networks, as read by
+/// `Bencoding::legacy_file_hashes`. No BEP defines them, clients don't use
+/// them to verify pieces, and they may be missing or wrong.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LegacyFileHashes<'a> {
+    /// `md5sum`, the file's MD5 as 32 hex digits.
+    pub md5sum: Option<&'a [u8]>,
+    /// `ed2k`, the file's eDonkey2000 hash, 16 raw bytes.
+    pub ed2k: Option<&'a [u8]>,
+    /// `filehash`, the file's SHA-1, 20 raw bytes.
+    pub filehash: Option<&'a [u8]>,
+}
+
+impl LegacyFileHashes<'_> {
+    /// `md5sum` decoded, if it's 32 hex digits.
+    pub fn md5(&self) -> Option<[u8; 16]> {
+        let md5sum = self.md5sum.filter(|md5sum| md5sum.len() == 32)?;
+        let mut md5 = [0; 16];
+        for (i, pair) in md5sum.chunks(2).enumerate() {
+            md5[i] = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+        }
+
+        return Some(md5);
+    }
+
+    /// `ed2k`, if it's 16 bytes long.
+    pub fn ed2k_hash(&self) -> Option<[u8; 16]> {
+        return self.ed2k?.try_into().ok();
+    }
+
+    /// `filehash`, if it's 20 bytes long.
+    pub fn sha1(&self) -> Option<[u8; 20]> {
+        return self.filehash?.try_into().ok();
+    }
+}
+
+/// The descriptive top-level fields of a torrent, none of which affect its
+/// content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TorrentMetadata<'a> {
+    pub created_by: Option<&'a [u8]>,
+    pub creation_date: Option<i64>,
+    pub encoding: Option<&'a [u8]>,
+    pub comment: Option<&'a [u8]>,
+}
+
+/// The `created by` field of a torrent, split into the creating tool's name
+/// and version when it follows the usual `name version` form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreatedBy<'a> {
+    /// A tool name followed by a version, such as `mktorrent 1.1`,
+    /// `uTorrent/2210` or `qBittorrent v4.3.9`. The version has no leading
+    /// `v`, and anything after it, such as a build hash, is left out.
+    Tool { name: &'a str, version: &'a str },
+    /// A value that isn't UTF-8 or doesn't end its first word in a version.
+    Unrecognized(&'a [u8]),
+}
+
+/// The sources a client may get peers of a torrent from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerSourcePolicy {
+    /// Only the torrent's trackers, as required for private torrents.
+    TrackersOnly,
+    /// The trackers, the DHT and peer exchange.
+    TrackersDhtAndPex,
+}
+
+impl Bencoding {
+    pub(crate) fn info_dict(&self) -> Option<&BencodingDict> {
+        return self.get_dict(b"info");
+    }
+
+    /// The tracker URL from `announce`.
+    pub fn announce(&self) -> Option<&[u8]> {
+        return self.get_string(b"announce");
+    }
+
+    /// The tracker tiers from `announce-list` (BEP 12), exactly as stored.
+    ///
+    /// Returns `None` if `announce-list` is missing or isn't a list of lists
+    /// of strings.
+    pub fn announce_list_raw(&self) -> Option<Vec<Vec<&[u8]>>> {
+        let mut tiers = Vec::new();
+        for tier in self.get_list(b"announce-list")? {
+            let mut urls = Vec::new();
+            for url in tier.as_list()? {
+                urls.push(url.as_string()?);
+            }
+            tiers.push(urls);
+        }
+
+        return Some(tiers);
+    }
+
+    /// The tracker tiers from `announce-list` (BEP 12).
+    ///
+    /// With `clean`, repeated URLs within a tier are dropped (keeping the
+    /// first occurrence, so the order within the tier is preserved), and so
+    /// are tiers left empty. The same URL in different tiers is kept, as is
+    /// the order of the tiers. Without `clean`, this is `announce_list_raw`.
+    pub fn announce_list(&self, clean: bool) -> Option<Vec<Vec<&[u8]>>> {
+        let tiers = self.announce_list_raw()?;
+        if !clean {
+            return Some(tiers);
+        }
+
+        let mut cleaned = Vec::new();
+        for tier in tiers {
+            let mut urls: Vec<&[u8]> = Vec::new();
+            for url in tier {
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+            }
+            if !urls.is_empty() {
+                cleaned.push(urls);
+            }
+        }
+
+        return Some(cleaned);
+    }
+
+    /// Checks that `announce-list`, if present, is exactly a list of lists
+    /// of strings, as BEP 12 requires. `announce_list` only tells that it
+    /// isn't; this tells where.
+    ///
+    /// The first violation is reported as `Err(UnexpectedType)` with the
+    /// path to the offending value as its key, such as `announce-list[1][0]`
+    /// for the first URL of the second tier.
+    pub fn validate_announce_list(&self) -> Result<(), BencodingError> {
+        let tiers = match self.dict.get(b"announce-list".as_slice()) {
+            Some(tiers) => tiers,
+            None => return Ok(()),
+        };
+        let tiers = expect_list(tiers, || String::from("announce-list"))?;
+
+        for (i, tier) in tiers.iter().enumerate() {
+            let urls = expect_list(tier, || format!("announce-list[{}]", i))?;
+            for (j, url) in urls.iter().enumerate() {
+                if url.as_string().is_none() {
+                    return Err(BencodingError::UnexpectedType {
+                        key: format!("announce-list[{}][{}]", i, j),
+                        expected: "string",
+                        found: url.type_name(),
+                    });
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// The tracker URLs of `announce` and `announce-list`, in order and
+    /// without repetitions.
+    pub(crate) fn tracker_urls(&self) -> Vec<&[u8]> {
+        let tiers = self.announce_list_raw().unwrap_or_default();
+        let mut trackers: Vec<&[u8]> = Vec::new();
+        for url in self
+            .announce()
+            .into_iter()
+            .chain(tiers.into_iter().flatten())
+        {
+            if !trackers.contains(&url) {
+                trackers.push(url);
+            }
+        }
+
+        return trackers;
+    }
+
+    /// Guesses the private tracker passkey embedded in the `announce` URL.
+    ///
+    /// The passkey is taken to be the first path segment or query parameter
+    /// value that is exactly 32 hexadecimal characters, which covers both
+    /// `https://tracker.example/<passkey>/announce` and
+    /// `https://tracker.example/announce.php?passkey=<passkey>`. Passkeys of
+    /// any other length or alphabet are not recognised, and any other 32-char
+    /// hex token in the URL would be mistaken for one.
+    pub fn announce_passkey(&self) -> Option<String> {
+        let announce = std::str::from_utf8(self.announce()?).ok()?;
+        let announce = announce.split('#').next()?;
+        let without_scheme = match announce.find("://") {
+            Some(idx) => &announce[idx + 3..],
+            None => announce,
+        };
+        let (path, query) = match without_scheme.split_once('?') {
+            Some((path, query)) => (path, query),
+            None => (without_scheme, ""),
+        };
+
+        // The first segment is the host.
+        let segments = path.split('/').skip(1);
+        let values = query
+            .split('&')
+            .map(|param| param.split_once('=').map_or(param, |(_, value)| value));
+
+        return segments
+            .chain(values)
+            .find(|token| token.len() == 32 && token.bytes().all(|b| b.is_ascii_hexdigit()))
+            .map(String::from);
+    }
+
+    /// The BEP 19 web seed URLs from `url-list`, which may be a single string
+    /// or a list of strings. Non-string elements are skipped, and a missing
+    /// or malformed `url-list` gives an empty list.
+    pub fn url_list(&self) -> Vec<&[u8]> {
+        match self.dict.get(b"url-list".as_slice()) {
+            Some(BencodingValue::String(url)) => return vec![url.as_slice()],
+            Some(BencodingValue::List(_)) => {
+                return self.get_string_list(b"url-list", true).unwrap()
+            }
+            _ => return Vec::new(),
+        }
+    }
+
+    /// The URLs to download the file at `file_index` of `files` from, one
+    /// for each BEP 19 web seed of `url-list` that is valid UTF-8.
+    ///
+    /// Following BEP 19, a base URL ending in `/` is a directory to which the
+    /// torrent's `name` is appended, followed for a multi-file torrent by
+    /// `/` and the file's path components joined with `/`. For a multi-file
+    /// torrent, a base URL without the trailing `/` gets one added. For a
+    /// single-file torrent, a base URL without it points to the file itself
+    /// and is used as is. The name and path components are percent-encoded.
+    ///
+    /// An index out of range, a BEP 47 padding file, which web seeds don't
+    /// serve, or a missing `name` or file list gives an empty list.
+    pub fn web_seed_urls_for_file(&self, file_index: usize) -> Vec<String> {
+        let files = self.files().unwrap_or_default();
+        let (file, name) = match (files.get(file_index), self.name()) {
+            (Some(file), Some(name)) if !file.padding => (file, name),
+            _ => return Vec::new(),
+        };
+        let multi_file = self
+            .info_dict()
+            .is_some_and(|info| info.contains_key(b"files".as_slice()));
+
+        let mut relative = percent_encode(name);
+        if multi_file {
+            for component in &file.path {
+                relative.push('/');
+                relative += &percent_encode(component);
+            }
+        }
+
+        return self
+            .url_list()
+            .into_iter()
+            .filter_map(|base| std::str::from_utf8(base).ok())
+            .map(|base| {
+                if base.ends_with('/') {
+                    return format!("{}{}", base, relative);
+                }
+                if multi_file {
+                    return format!("{}/{}", base, relative);
+                }
+                return String::from(base);
+            })
+            .collect();
+    }
+
+    /// The BEP 17 HTTP seed URLs from the `httpseeds` list. Non-string
+    /// elements are skipped, and a missing `httpseeds` gives an empty list.
+    pub fn http_seeds(&self) -> Vec<&[u8]> {
+        return self.get_string_list(b"httpseeds", true).unwrap_or_default();
+    }
+
+    /// The BEP 17 request URL for the piece at `piece_index` from `seed`, one
+    /// of the `httpseeds` URLs.
+    ///
+    /// Unlike a BEP 19 web seed, a BEP 17 seed is a script that serves
+    /// pieces by index, so `seed` is used as is, followed by the query
+    /// `info_hash=<hash>&piece=<index>`: the raw 20-byte v1 info-hash,
+    /// percent-encoded as in tracker announces, and the decimal index. The
+    /// query is added after a `?`, or after a `&` if `seed` already has a
+    /// query. The optional `ranges` parameter, for requesting part of a
+    /// piece, is not added.
+    ///
+    /// Returns `None` if there is no `info` or the index is out of range.
+    /// Requires the `sha1` feature.
+    #[cfg(feature = "sha1")]
+    pub fn http_seed_request_url(&self, seed: &str, piece_index: usize) -> Option<String> {
+        if piece_index >= self.piece_count()? {
+            return None;
+        }
+        let info_hash = self.info_hash()?;
+        let separator = if seed.contains('?') { '&' } else { '?' };
+
+        return Some(format!(
+            "{}{}info_hash={}&piece={}",
+            seed,
+            separator,
+            percent_encode(&info_hash),
+            piece_index
+        ));
+    }
+
+    /// The names of the collections the torrent belongs to, from the
+    /// `info.collections` list (BEP 38), used to find related torrents.
+    ///
+    /// Elements that aren't valid UTF-8 strings are skipped, and a missing or
+    /// malformed `collections` gives an empty list.
+    pub fn collections(&self) -> Vec<String> {
+        let collections = self
+            .info_dict()
+            .and_then(|info| info.get(b"collections".as_slice()))
+            .and_then(BencodingValue::as_list);
+
+        return collections
+            .into_iter()
+            .flatten()
+            .filter_map(BencodingValue::as_string)
+            .filter_map(|name| String::from_utf8(name.to_vec()).ok())
+            .collect();
+    }
+
+    /// All web seed URLs, from `url-list` followed by `httpseeds`.
+    ///
+    /// URLs appearing more than once, in either field, are only kept the
+    /// first time, and URLs that aren't valid UTF-8 are left out.
+    pub fn all_web_seeds(&self) -> Vec<String> {
+        let mut seeds: Vec<String> = Vec::new();
+        for url in self.url_list().into_iter().chain(self.http_seeds()) {
+            if let Ok(url) = std::str::from_utf8(url) {
+                if !seeds.iter().any(|seed| seed == url) {
+                    seeds.push(String::from(url));
+                }
+            }
+        }
+
+        return seeds;
+    }
+
+    /// The top-level keys outside the standard set, in sorted order. These are
+    /// typically client or tracker specific additions.
+    pub fn unknown_top_level_keys(&self) -> Vec<&[u8]> {
+        let mut keys: Vec<&[u8]> = self
+            .dict
+            .keys()
+            .map(Vec::as_slice)
+            .filter(|key| !STANDARD_TOP_LEVEL_KEYS.contains(key))
+            .collect();
+        keys.sort();
+
+        return keys;
+    }
+
+    /// The program that created the torrent, from `created by`.
+    pub fn created_by(&self) -> Option<&[u8]> {
+        return self.get_string(b"created by");
+    }
+
+    /// Parses `created by` into the creating tool's name and version, which
+    /// can be used to recognize torrents from encoders with known bugs.
+    ///
+    /// The name is everything before the first space or `/` followed by a
+    /// version: a digit, optionally preceded by `v`. The version runs up to
+    /// the next space. Returns `None` if `created by` is missing.
+    pub fn created_by_tool(&self) -> Option<CreatedBy<'_>> {
+        let raw = self.created_by()?;
+        let unrecognized = CreatedBy::Unrecognized(raw);
+        let text = match std::str::from_utf8(raw) {
+            Ok(text) => text,
+            Err(_) => return Some(unrecognized),
+        };
+
+        for (i, separator) in text.char_indices() {
+            if separator != ' ' && separator != '/' {
+                continue;
+            }
+            let name = text[..i].trim();
+            let rest = &text[i + 1..];
+            let rest = rest.strip_prefix('v').unwrap_or(rest);
+            if name.is_empty() || !rest.starts_with(|c: char| c.is_ascii_digit()) {
+                continue;
+            }
+
+            let version = rest.split(' ').next().unwrap_or(rest);
+            return Some(CreatedBy::Tool { name, version });
+        }
+
+        return Some(unrecognized);
+    }
+
+    /// `creation date`, in seconds since the Unix epoch.
+    pub fn creation_date(&self) -> Option<i64> {
+        return self.get_integer(b"creation date");
+    }
+
+    /// The character encoding of the strings in `info`, from `encoding`.
+    pub fn encoding(&self) -> Option<&[u8]> {
+        return self.get_string(b"encoding");
+    }
+
+    pub fn comment(&self) -> Option<&[u8]> {
+        return self.get_string(b"comment");
+    }
+
+    /// All of `created by`, `creation date`, `encoding` and `comment` at once.
+    pub fn metadata(&self) -> TorrentMetadata<'_> {
+        return TorrentMetadata {
+            created_by: self.created_by(),
+            creation_date: self.creation_date(),
+            encoding: self.encoding(),
+            comment: self.comment(),
+        };
+    }
+
+    /// The suggested file name (single-file) or directory name (multi-file)
+    /// from `info.name`.
+    pub fn name(&self) -> Option<&[u8]> {
+        return self.info_dict()?.get(b"name".as_slice())?.as_string();
+    }
+
+    /// `info.piece length`, or `None` if it's missing or not positive.
+    pub fn piece_length(&self) -> Option<i64> {
+        let piece_length = self
+            .info_dict()?
+            .get(b"piece length".as_slice())?
+            .as_integer()?;
+        if piece_length <= 0 {
+            return None;
+        }
+
+        return Some(piece_length);
+    }
+
+    /// `info.meta version`: 2 for v2 and hybrid torrents (BEP 52), absent for
+    /// v1 torrents.
+    pub fn meta_version(&self) -> Option<i64> {
+        return self
+            .info_dict()?
+            .get(b"meta version".as_slice())?
+            .as_integer();
+    }
+
+    /// Whether the torrent is private (BEP 27), that is, `info.private` is 1.
+    pub fn is_private(&self) -> bool {
+        return self
+            .info_dict()
+            .and_then(|info| info.get(b"private".as_slice()))
+            .and_then(BencodingValue::as_integer)
+            == Some(1);
+    }
+
+    /// Where peers may be looked up. BEP 27 restricts private torrents to
+    /// their trackers: announcing them to the DHT or exchanging their peers
+    /// would leak them outside the tracker's control.
+    pub fn peer_source_policy(&self) -> PeerSourcePolicy {
+        if self.is_private() {
+            return PeerSourcePolicy::TrackersOnly;
+        }
+
+        return PeerSourcePolicy::TrackersDhtAndPex;
+    }
+
+    /// Checks that `info.piece length` is a power of two, and at least 16 KiB
+    /// for v2 torrents.
+    ///
+    /// A missing or non-integer piece length is reported as
+    /// `Err(MissingField)`, any other violation as `Err(InvalidPieceLength)`.
+    pub fn validate_piece_length(&self) -> Result<(), BencodingError> {
+        let piece_length = self
+            .info_dict()
+            .and_then(|info| info.get(b"piece length".as_slice()))
+            .and_then(BencodingValue::as_integer)
+            .ok_or(BencodingError::MissingField("piece length"))?;
+
+        if piece_length <= 0 || piece_length & (piece_length - 1) != 0 {
+            return Err(BencodingError::InvalidPieceLength {
+                piece_length,
+                reason: "not a power of two",
+            });
+        }
+        if self.meta_version() == Some(2) && piece_length < V2_MIN_PIECE_LENGTH {
+            return Err(BencodingError::InvalidPieceLength {
+                piece_length,
+                reason: "below the 16 KiB minimum of v2 torrents",
+            });
+        }
+
+        return Ok(());
+    }
+
+    /// Checks that the `info` dictionary has the fields every torrent needs:
+    /// `name`, `piece length`, `pieces`, and either `length` or `files`.
+    ///
+    /// The first field found missing or of the wrong type is reported as
+    /// `Err(MissingField)`, in that order, with `"length or files"` when
+    /// neither is usable. The values themselves are not validated.
+    pub fn validate_info(&self) -> Result<(), BencodingError> {
+        let info = self
+            .info_dict()
+            .ok_or(BencodingError::MissingField("info"))?;
+        self.name().ok_or(BencodingError::MissingField("name"))?;
+        info.get(b"piece length".as_slice())
+            .and_then(BencodingValue::as_integer)
+            .ok_or(BencodingError::MissingField("piece length"))?;
+        self.pieces()
+            .ok_or(BencodingError::MissingField("pieces"))?;
+
+        let has_length = info
+            .get(b"length".as_slice())
+            .and_then(BencodingValue::as_integer)
+            .is_some();
+        let has_files = info
+            .get(b"files".as_slice())
+            .and_then(BencodingValue::as_list)
+            .is_some();
+        if !has_length && !has_files {
+            return Err(BencodingError::MissingField("length or files"));
+        }
+
+        return Ok(());
+    }
+
+    /// The files of the torrent in the order their data is laid out in the
+    /// pieces: `info.files` for multi-file torrents, or a single file named
+    /// `info.name` with `info.length`.
+    ///
+    /// A file's `path.utf-8`, when it's a list of valid UTF-8 strings, is
+    /// used instead of its `path`, as it's meant for display.
+    ///
+    /// Returns `None` if a length is missing or negative, or a path is not a
+    /// list of strings.
+    pub fn files(&self) -> Option<Vec<TorrentFile>> {
+        let info = self.info_dict()?;
+
+        let files = match info.get(b"files".as_slice()) {
+            Some(files) => files,
+            None => {
+                let length = info.get(b"length".as_slice())?.as_integer()?;
+                if length < 0 {
+                    return None;
+                }
+                return Some(vec![TorrentFile {
+                    path: vec![self.name()?.to_vec()],
+                    length,
+                    padding: false,
+                }]);
+            }
+        };
+
+        let mut result = Vec::new();
+        for file in files.as_list()? {
+            let file = file.as_dict()?;
+            let length = file.get(b"length".as_slice())?.as_integer()?;
+            if length < 0 {
+                return None;
+            }
+            let path = match file.get(b"path.utf-8".as_slice()).and_then(utf8_path) {
+                Some(path) => path,
+                None => path_components(file.get(b"path".as_slice())?)?,
+            };
+            let padding = file
+                .get(b"attr".as_slice())
+                .and_then(BencodingValue::as_string)
+                .is_some_and(|attr| attr.contains(&b'p'));
+            result.push(TorrentFile {
+                path,
+                length,
+                padding,
+            });
+        }
+
+        return Some(result);
+    }
+
+    /// The legacy `md5sum`, `ed2k` and `filehash` fields of each file, in the
+    /// order of `files`: those of each entry of `info.files`, or of `info`
+    /// itself for a single-file torrent. Fields that aren't strings are
+    /// `None`; use the `LegacyFileHashes` methods to check their format.
+    ///
+    /// Returns `None` when `files` does.
+    pub fn legacy_file_hashes(&self) -> Option<Vec<LegacyFileHashes<'_>>> {
+        self.files()?;
+        let info = self.info_dict()?;
+        let dicts: Vec<&BencodingDict> = match info.get(b"files".as_slice()) {
+            Some(files) => files
+                .as_list()?
+                .iter()
+                .filter_map(BencodingValue::as_dict)
+                .collect(),
+            None => vec![info],
+        };
+
+        return Some(
+            dicts
+                .into_iter()
+                .map(|dict| {
+                    let field = |key: &[u8]| dict.get(key).and_then(BencodingValue::as_string);
+                    return LegacyFileHashes {
+                        md5sum: field(b"md5sum"),
+                        ed2k: field(b"ed2k"),
+                        filehash: field(b"filehash"),
+                    };
+                })
+                .collect(),
+        );
+    }
+
+    /// Like `files`, but two files with the same path, which would overwrite
+    /// each other on disk, are `Err(DuplicateFilePath)`, and a malformed file
+    /// list is `Err(MissingField)`.
+    ///
+    /// Paths are compared after `TorrentFile::normalized_path` where it
+    /// accepts them, so `a/./b` and `a/b` collide. BEP 47 padding files are
+    /// not checked, as they're never written and often share a name.
+    pub fn files_checked(&self) -> Result<Vec<TorrentFile>, BencodingError> {
+        let files = self
+            .files()
+            .ok_or(BencodingError::MissingField("length or files"))?;
+
+        let mut seen = HashSet::new();
+        for file in files.iter().filter(|file| !file.padding) {
+            let path = file.normalized_path().unwrap_or_else(|| file.path.clone());
+            if !seen.insert(path) {
+                let path: Vec<String> = file
+                    .path
+                    .iter()
+                    .map(|component| escape_bytes(component))
+                    .collect();
+                return Err(BencodingError::DuplicateFilePath(path.join("/")));
+            }
+        }
+
+        return Ok(files);
+    }
+
+    /// The files of a v2 or hybrid torrent from `info.file tree` (BEP 52), in
+    /// path order, which is also the order of their data.
+    ///
+    /// Returns `None` if there is no file tree, or a file has a missing or
+    /// negative length.
+    pub fn file_tree(&self) -> Option<Vec<TorrentFile>> {
+        let tree = self.info_dict()?.get(b"file tree".as_slice())?.as_dict()?;
+        let mut files = Vec::new();
+        file_tree_files(tree, &mut Vec::new(), &mut files)?;
+
+        return Some(files);
+    }
+
+    /// Like `files`, but with each path replaced by its
+    /// `TorrentFile::normalized_path`. Returns `None` if any path is rejected.
+    pub fn normalized_files(&self) -> Option<Vec<TorrentFile>> {
+        let mut files = self.files()?;
+        for file in &mut files {
+            file.path = file.normalized_path()?;
+        }
+
+        return Some(files);
+    }
+
+    /// The number of files to display: 1 for a single-file torrent, or the
+    /// number of entries of `info.files` that aren't BEP 47 padding files.
+    /// A torrent without `info` has no files.
+    pub fn file_count(&self) -> usize {
+        let info = match self.info_dict() {
+            Some(info) => info,
+            None => return 0,
+        };
+        let files = match info
+            .get(b"files".as_slice())
+            .and_then(BencodingValue::as_list)
+        {
+            Some(files) => files,
+            None => return 1,
+        };
+
+        return files
+            .iter()
+            .filter(|file| {
+                let attr = file
+                    .as_dict()
+                    .and_then(|file| file.get(b"attr".as_slice()))
+                    .and_then(BencodingValue::as_string);
+                return !attr.is_some_and(|attr| attr.contains(&b'p'));
+            })
+            .count();
+    }
+
+    /// The sum of the lengths of all files, or `None` if the file list is
+    /// malformed or the sum overflows. Padding files are included; see
+    /// `content_size` for the size of the actual content.
+    pub fn total_length(&self) -> Option<i64> {
+        let mut total: i64 = 0;
+        for file in self.files()? {
+            total = total.checked_add(file.length)?;
+        }
+
+        return Some(total);
+    }
+
+    /// Like `total_length`, but without the padding files of BEP 47, those
+    /// whose `attr` contains `p`: the size of the content itself, as shown
+    /// to users.
+    pub fn content_size(&self) -> Option<i64> {
+        let mut total: i64 = 0;
+        for file in self.files()? {
+            if !file.padding {
+                total = total.checked_add(file.length)?;
+            }
+        }
+
+        return Some(total);
+    }
+
+    /// Like `total_length`, but a malformed file list is
+    /// `Err(MissingField)` and a sum that doesn't fit an `i64` is
+    /// `Err(IntegerOverflow)`, so an adversarial torrent can't be mistaken
+    /// for one with a missing length.
+    pub fn total_length_checked(&self) -> Result<i64, BencodingError> {
+        let files = self
+            .files()
+            .ok_or(BencodingError::MissingField("length or files"))?;
+        let mut total: i64 = 0;
+        for file in files {
+            total = total
+                .checked_add(file.length)
+                .ok_or(BencodingError::IntegerOverflow)?;
+        }
+
+        return Ok(total);
+    }
+
+    /// Whether the torrent describes more than `max_bytes` of data, for
+    /// services that cap the size of the torrents they accept. Padding
+    /// files count, as they're part of `total_length`.
+    ///
+    /// Returns `None` if the file list is malformed or its total doesn't
+    /// fit an `i64`, which a caller enforcing a cap should treat as a
+    /// rejection as well.
+    pub fn exceeds_size(&self, max_bytes: i64) -> Option<bool> {
+        return Some(self.total_length()? > max_bytes);
+    }
+
+    /// A key to order a list of torrents by name, then by size: the
+    /// lowercased `name`, with invalid UTF-8 replaced, and `total_length`.
+    ///
+    /// A missing name sorts as the empty string, and a malformed file list
+    /// as a size of 0, so that every torrent gets a key.
+    pub fn sort_key(&self) -> (String, i64) {
+        let name = String::from_utf8_lossy(self.name().unwrap_or_default()).to_lowercase();
+
+        return (name, self.total_length().unwrap_or(0));
+    }
+
+    /// The indices of the pieces that hold data of the file at `file_index`.
+    ///
+    /// The first and last pieces of the range may be shared with the
+    /// neighbouring files. An empty file yields an empty range. Returns `None`
+    /// if the index is out of bounds or the torrent is malformed.
+    pub fn file_piece_range(&self, file_index: usize) -> Option<Range<usize>> {
+        let piece_length = self.piece_length()? as u64;
+        let files = self.files()?;
+        let file = files.get(file_index)?;
+
+        let mut offset: u64 = 0;
+        for previous in &files[..file_index] {
+            offset = offset.checked_add(previous.length as u64)?;
+        }
+
+        return piece_range(offset, file.length as u64, piece_length);
+    }
+
+    /// The files with their byte offset in the torrent's data and the range
+    /// of pieces they touch (as in `file_piece_range`), for both single- and
+    /// multi-file torrents. Offsets are accumulated as the iterator advances.
+    ///
+    /// Padding files are included, as in `files`. A malformed torrent, or one
+    /// without a valid piece length, yields no entries.
+    pub fn file_entries(&self) -> impl Iterator<Item = FileEntry> {
+        let (files, piece_length) = match (self.files(), self.piece_length()) {
+            (Some(files), Some(piece_length)) => (files, piece_length as u64),
+            _ => (Vec::new(), 1),
+        };
+
+        let mut offset: u64 = 0;
+        return files.into_iter().map_while(move |file| {
+            let length = file.length as u64;
+            let entry = FileEntry {
+                path: file.path,
+                length: file.length,
+                offset,
+                piece_range: piece_range(offset, length, piece_length)?,
+            };
+            offset += length;
+            Some(entry)
+        });
+    }
+
+    /// The parts of files covered by the piece at `piece_index`, as
+    /// `(file index, start, end)` with the byte range `start..end` within the
+    /// file, in file order. This is the inverse of `file_piece_range`, for
+    /// writing a received piece out to its files.
+    ///
+    /// Empty files are never listed. Returns `None` if the piece is past the
+    /// end of the torrent or the torrent is malformed.
+    pub fn piece_file_overlap(&self, piece_index: usize) -> Option<Vec<(usize, u64, u64)>> {
+        let piece_length = self.piece_length()? as u64;
+        let total = self.total_length()? as u64;
+        let piece_start = (piece_index as u64).checked_mul(piece_length)?;
+        if piece_start >= total {
+            return None;
+        }
+        let piece_end = total.min(piece_start + piece_length);
+
+        let mut overlap = Vec::new();
+        let mut offset: u64 = 0;
+        for (i, file) in self.files()?.iter().enumerate() {
+            let file_end = offset + file.length as u64;
+            let start = offset.max(piece_start);
+            let end = file_end.min(piece_end);
+            if start < end {
+                overlap.push((i, start - offset, end - offset));
+            }
+            offset = file_end;
+        }
+
+        return Some(overlap);
+    }
+}
+
+/// Percent-encodes everything but the RFC 3986 unreserved characters.
+pub(crate) fn percent_encode(s: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for &byte in s {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out += &format!("%{:02X}", byte);
+        }
+    }
+
+    return out;
+}
+
+/// The pieces holding the `length` bytes at `offset`. Empty data gets an empty
+/// range at the piece containing the offset.
+fn piece_range(offset: u64, length: u64, piece_length: u64) -> Option<Range<usize>> {
+    let first_piece = offset / piece_length;
+    if length == 0 {
+        return Some(first_piece as usize..first_piece as usize);
+    }
+    let end_piece = offset.checked_add(length)?.div_ceil(piece_length);
+
+    return Some(first_piece as usize..end_piece as usize);
+}
+
+/// The components of a file's `path`, or `None` if it isn't a list of
+/// strings.
+fn path_components(path: &BencodingValue) -> Option<Vec<Vec<u8>>> {
+    return path
+        .as_list()?
+        .iter()
+        .map(|component| Some(component.as_string()?.to_vec()))
+        .collect();
+}
+
+/// The components of a file's `path.utf-8`, or `None` if it isn't a list of
+/// valid UTF-8 strings.
+fn utf8_path(path: &BencodingValue) -> Option<Vec<Vec<u8>>> {
+    let components = path_components(path)?;
+    if components
+        .iter()
+        .any(|component| std::str::from_utf8(component).is_err())
+    {
+        return None;
+    }
+
+    return Some(components);
+}
+
+/// Collects the files under a `file tree` node. A file is a node with an
+/// empty key, whose value holds the file's `length`.
+fn file_tree_files(
+    node: &BencodingDict,
+    path: &mut Vec<Vec<u8>>,
+    files: &mut Vec<TorrentFile>,
+) -> Option<()> {
+    let mut names: Vec<&Vec<u8>> = node.keys().collect();
+    names.sort();
+
+    for name in names {
+        let child = node[name].as_dict()?;
+        path.push(name.clone());
+        match child.get(b"".as_slice()) {
+            Some(leaf) => {
+                let length = leaf.as_dict()?.get(b"length".as_slice())?.as_integer()?;
+                if length < 0 {
+                    return None;
+                }
+                files.push(TorrentFile {
+                    path: path.clone(),
+                    length,
+                    padding: false,
+                });
+            }
+            None => file_tree_files(child, path, files)?,
+        }
+        path.pop();
+    }
+
+    return Some(());
+}
+
+/// The elements of `value`, or `Err(UnexpectedType)` at the path given by
+/// `path` if it isn't a list.
+fn expect_list<F: FnOnce() -> String>(
+    value: &BencodingValue,
+    path: F,
+) -> Result<&Vec<BencodingValue>, BencodingError> {
+    match value.as_list() {
+        Some(list) => return Ok(list),
+        None => {
+            return Err(BencodingError::UnexpectedType {
+                key: path(),
+                expected: "list",
+                found: value.type_name(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{
+        Bencoding, BencodingError, CreatedBy, FileEntry, LegacyFileHashes, PeerSourcePolicy,
+        TorrentFile, TorrentMetadata,
+    };
+
+    fn multi_file_torrent(piece_length: i64, lengths: &[i64]) -> Bencoding {
+        let mut files = String::new();
+        for (i, length) in lengths.iter().enumerate() {
+            let name = format!("file{}", i);
+            files += &format!("d6:lengthi{}e4:pathl{}:{}ee", length, name.len(), name);
+        }
+        let data = format!(
+            "d4:infod5:filesl{}e4:name4:root12:piece lengthi{}e6:pieces0:ee",
+            files, piece_length
+        );
+
+        return Bencoding::decode(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn unknown_top_level_keys_of_standard_torrent_is_empty() {
+        let parser = Bencoding::decode(
+            b"d8:announce3:url7:comment1:c10:created by1:x13:creation datei1e4:infodee",
+        )
+        .unwrap();
+        assert!(parser.unknown_top_level_keys().is_empty());
+    }
+
+    #[test]
+    fn unknown_top_level_keys_lists_custom_keys() {
+        let parser =
+            Bencoding::decode(b"d8:announce3:url4:infode7:x-crypt4:abcd5:azurei1e4:kkeyi0ee")
+                .unwrap();
+        assert_eq!(
+            parser.unknown_top_level_keys(),
+            vec![b"azure".as_slice(), b"kkey", b"x-crypt"]
+        );
+    }
+
+    #[test]
+    fn metadata_of_torrent() {
+        let parser = Bencoding::decode(
+            b"d7:comment5:hello10:created by13:mktorrent 1.113:creation datei1650000000e8:encoding5:UTF-8e",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.metadata(),
+            TorrentMetadata {
+                created_by: Some(b"mktorrent 1.1"),
+                creation_date: Some(1650000000),
+                encoding: Some(b"UTF-8"),
+                comment: Some(b"hello"),
+            }
+        );
+    }
+
+    #[test]
+    fn metadata_with_missing_fields() {
+        let parser = Bencoding::decode(b"d10:created by4:toole").unwrap();
+        let metadata = parser.metadata();
+
+        assert_eq!(metadata.created_by, Some(b"tool".as_slice()));
+        assert_eq!(metadata.creation_date, None);
+        assert_eq!(metadata.encoding, None);
+        assert_eq!(metadata.comment, None);
+    }
+
+    fn announce_list_error(data: &[u8]) -> (String, &'static str, &'static str) {
+        match Bencoding::decode(data).unwrap().validate_announce_list() {
+            Err(BencodingError::UnexpectedType {
+                key,
+                expected,
+                found,
+            }) => return (key, expected, found),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_announce_list_of_well_formed_tiers() {
+        let parser = Bencoding::decode(b"d13:announce-listll1:ael1:b1:ceee").unwrap();
+        assert!(parser.validate_announce_list().is_ok());
+        assert!(Bencoding::decode(b"de")
+            .unwrap()
+            .validate_announce_list()
+            .is_ok());
+    }
+
+    #[test]
+    fn validate_announce_list_reports_first_violation() {
+        assert_eq!(
+            announce_list_error(b"d13:announce-list1:ae"),
+            (String::from("announce-list"), "list", "string")
+        );
+        assert_eq!(
+            announce_list_error(b"d13:announce-listl1:aee"),
+            (String::from("announce-list[0]"), "list", "string")
+        );
+        assert_eq!(
+            announce_list_error(b"d13:announce-listll1:aeli1eeee"),
+            (String::from("announce-list[1][0]"), "string", "integer")
+        );
+        assert_eq!(
+            announce_list_error(b"d13:announce-listll1:a1:bd1:xi1eeeee"),
+            (String::from("announce-list[0][2]"), "string", "dictionary")
+        );
+    }
+
+    #[test]
+    fn announce_list_raw_keeps_duplicates_and_empty_tiers() {
+        let parser = Bencoding::decode(b"d13:announce-listll1:a1:b1:aelel1:ceee").unwrap();
+        assert_eq!(
+            parser.announce_list_raw().unwrap(),
+            vec![
+                vec![b"a".as_slice(), b"b", b"a"],
+                vec![],
+                vec![b"c".as_slice()]
+            ]
+        );
+        assert_eq!(parser.announce_list(false), parser.announce_list_raw());
+    }
+
+    #[test]
+    fn announce_list_clean_dedups_tiers_and_drops_empty_ones() {
+        let parser = Bencoding::decode(b"d13:announce-listll1:a1:b1:aelel1:a1:c1:ceee").unwrap();
+        assert_eq!(
+            parser.announce_list(true).unwrap(),
+            vec![vec![b"a".as_slice(), b"b"], vec![b"a".as_slice(), b"c"]]
+        );
+    }
+
+    #[test]
+    fn announce_list_with_non_list_tier_is_none() {
+        let parser = Bencoding::decode(b"d13:announce-listl1:aee").unwrap();
+        assert_eq!(parser.announce_list(true), None);
+    }
+
+    #[test]
+    fn announce_passkey_from_query() {
+        let parser = Bencoding::decode(
+            b"d8:announce83:https://tracker.example/announce.php?uid=7&passkey=0123456789abcdef0123456789ABCDEFe",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.announce_passkey(),
+            Some(String::from("0123456789abcdef0123456789ABCDEF"))
+        );
+    }
+
+    #[test]
+    fn announce_passkey_from_path() {
+        let parser = Bencoding::decode(
+            b"d8:announce69:http://tracker.example:2710/0123456789abcdef0123456789abcdef/announcee",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.announce_passkey(),
+            Some(String::from("0123456789abcdef0123456789abcdef"))
+        );
+    }
+
+    #[test]
+    fn announce_passkey_of_public_tracker_is_none() {
+        let parser =
+            Bencoding::decode(b"d8:announce35:udp://tracker.example:1337/announcee").unwrap();
+        assert_eq!(parser.announce_passkey(), None);
+    }
+
+    fn torrent_with_piece_length(piece_length: i64, meta_version: Option<i64>) -> Bencoding {
+        let meta_version = match meta_version {
+            Some(version) => format!("12:meta versioni{}e", version),
+            None => String::new(),
+        };
+        let data = format!(
+            "d4:infod6:lengthi1e{}4:name1:a12:piece lengthi{}eee",
+            meta_version, piece_length
+        );
+
+        return Bencoding::decode(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn validate_piece_length_accepts_powers_of_two() {
+        for piece_length in [1, 16, 262144, 1 << 24] {
+            torrent_with_piece_length(piece_length, None)
+                .validate_piece_length()
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn validate_piece_length_rejects_non_powers_of_two() {
+        for piece_length in [0, -16, 3, 100000] {
+            let result = torrent_with_piece_length(piece_length, None).validate_piece_length();
+            assert!(matches!(
+                result,
+                Err(BencodingError::InvalidPieceLength { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_piece_length_enforces_v2_minimum() {
+        torrent_with_piece_length(16384, Some(2))
+            .validate_piece_length()
+            .unwrap();
+        let result = torrent_with_piece_length(8192, Some(2)).validate_piece_length();
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidPieceLength {
+                piece_length: 8192,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_piece_length_without_piece_length_is_error() {
+        let parser = Bencoding::decode(b"d4:infod6:lengthi1e4:name1:aee").unwrap();
+        let result = parser.validate_piece_length();
+        assert!(matches!(
+            result,
+            Err(BencodingError::MissingField("piece length"))
+        ));
+    }
+
+    #[test]
+    fn files_of_single_file_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod6:lengthi100e4:name8:file.iso12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        let files = parser.files().unwrap();
+        assert_eq!(
+            files,
+            vec![TorrentFile {
+                path: vec![b"file.iso".to_vec()],
+                length: 100,
+                padding: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn files_of_multi_file_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl3:dir1:aeed6:lengthi2e4:pathl1:beee4:name4:root12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        let files = parser.files().unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, vec![b"dir".to_vec(), b"a".to_vec()]);
+        assert_eq!(files[1].length, 2);
+    }
+
+    fn file(path: &[&[u8]]) -> TorrentFile {
+        return TorrentFile {
+            path: path.iter().map(|component| component.to_vec()).collect(),
+            length: 0,
+            padding: false,
+        };
+    }
+
+    #[test]
+    fn normalized_path_splits_on_both_separators() {
+        let file = file(&[b"dir\\sub", b"./a/b.txt", b"c"]);
+        assert_eq!(
+            file.normalized_path().unwrap(),
+            vec![
+                b"dir".to_vec(),
+                b"sub".to_vec(),
+                b"a".to_vec(),
+                b"b.txt".to_vec(),
+                b"c".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_path_rejects_rooted_paths() {
+        assert_eq!(file(&[b"/etc/passwd"]).normalized_path(), None);
+        assert_eq!(file(&[b"\\server\\share"]).normalized_path(), None);
+        assert_eq!(file(&[b"C:\\Windows", b"x.dll"]).normalized_path(), None);
+    }
+
+    #[test]
+    fn normalized_path_rejects_traversal_and_empty_paths() {
+        assert_eq!(file(&[b"a", b"..\\..\\b"]).normalized_path(), None);
+        assert_eq!(file(&[b"", b"."]).normalized_path(), None);
+    }
+
+    #[test]
+    fn normalized_files_of_torrent() {
+        let parser =
+            Bencoding::decode(b"d4:infod5:filesld6:lengthi3e4:pathl9:dir\\a.txteee4:name4:rootee")
+                .unwrap();
+        assert_eq!(
+            parser.normalized_files().unwrap()[0].path,
+            vec![b"dir".to_vec(), b"a.txt".to_vec()]
+        );
+    }
+
+    #[test]
+    fn files_flags_padding_files() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi3e4:pathl1:aeed4:attr1:p6:lengthi13e4:pathl4:.pad2:13eee4:name4:rootee",
+        )
+        .unwrap();
+        let padding: Vec<bool> = parser.files().unwrap().iter().map(|f| f.padding).collect();
+        assert_eq!(padding, vec![false, true]);
+    }
+
+    #[test]
+    fn file_tree_of_v2_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod9:file treed3:dird1:bd0:d6:lengthi2eee1:ad0:d6:lengthi1eeee1:cd0:d6:lengthi3eeee12:meta versioni2e4:name4:rootee",
+        )
+        .unwrap();
+        let files: Vec<(Vec<Vec<u8>>, i64)> = parser
+            .file_tree()
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.path, file.length))
+            .collect();
+
+        assert_eq!(
+            files,
+            vec![
+                (vec![b"c".to_vec()], 3),
+                (vec![b"dir".to_vec(), b"a".to_vec()], 1),
+                (vec![b"dir".to_vec(), b"b".to_vec()], 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn total_length_sums_files() {
+        let parser = multi_file_torrent(16, &[20, 30, 14]);
+        assert_eq!(parser.total_length(), Some(64));
+    }
+
+    #[test]
+    fn exceeds_size_compares_total_length() {
+        let parser = multi_file_torrent(16, &[20, 30, 14]);
+        assert_eq!(parser.exceeds_size(63), Some(true));
+        assert_eq!(parser.exceeds_size(64), Some(false));
+        assert_eq!(parser.exceeds_size(i64::MAX), Some(false));
+    }
+
+    #[test]
+    fn exceeds_size_of_malformed_torrent_is_none() {
+        let parser = multi_file_torrent(16, &[i64::MAX, 1]);
+        assert_eq!(parser.exceeds_size(1024), None);
+        let parser = Bencoding::decode(b"d4:infod4:name1:aee").unwrap();
+        assert_eq!(parser.exceeds_size(1024), None);
+    }
+
+    #[test]
+    fn content_size_leaves_out_padding_files() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi3e4:pathl1:aeed4:attr1:p6:lengthi13e4:pathl4:.pad2:13eed6:lengthi5e4:pathl1:beed4:attr2:xp6:lengthi11e4:pathl4:.pad2:11eee4:name4:rootee",
+        )
+        .unwrap();
+        assert_eq!(parser.total_length(), Some(32));
+        assert_eq!(parser.content_size(), Some(8));
+    }
+
+    #[test]
+    fn content_size_without_padding_is_total_length() {
+        let parser = multi_file_torrent(16, &[20, 30, 14]);
+        assert_eq!(parser.content_size(), Some(64));
+        let parser = Bencoding::decode(b"d4:infod4:name1:aee").unwrap();
+        assert_eq!(parser.content_size(), None);
+    }
+
+    #[test]
+    fn sort_key_orders_by_name_then_size() {
+        let torrent = |name: &str, length: i64| {
+            let data = format!(
+                "d4:infod6:lengthi{}e4:name{}:{}ee",
+                length,
+                name.len(),
+                name
+            );
+            return Bencoding::decode(data.as_bytes()).unwrap();
+        };
+        let mut torrents = [
+            torrent("beta", 1),
+            torrent("Alpha", 20),
+            torrent("alpha", 10),
+            torrent("ÉTÉ", 1),
+        ];
+        torrents.sort_by_key(Bencoding::sort_key);
+        let keys: Vec<(String, i64)> = torrents.iter().map(Bencoding::sort_key).collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                (String::from("alpha"), 10),
+                (String::from("alpha"), 20),
+                (String::from("beta"), 1),
+                (String::from("été"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_key_of_malformed_torrent() {
+        assert_eq!(
+            Bencoding::decode(b"de").unwrap().sort_key(),
+            (String::new(), 0)
+        );
+        let parser = Bencoding::decode(b"d4:infod4:name2:\xffAee").unwrap();
+        assert_eq!(parser.sort_key(), (String::from("\u{fffd}a"), 0));
+    }
+
+    fn created_by_torrent(value: &str) -> Bencoding {
+        let data = format!("d10:created by{}:{}e", value.len(), value);
+        return Bencoding::decode(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn created_by_tool_splits_name_and_version() {
+        let cases = [
+            ("mktorrent 1.1", "mktorrent", "1.1"),
+            ("uTorrent/2210", "uTorrent", "2210"),
+            ("qBittorrent v4.3.9", "qBittorrent", "4.3.9"),
+            ("Transmission/2.94 (d8e60ee44f)", "Transmission", "2.94"),
+            (
+                "Deluge Torrent Creator 2.1.1",
+                "Deluge Torrent Creator",
+                "2.1.1",
+            ),
+        ];
+        for (value, name, version) in cases {
+            assert_eq!(
+                created_by_torrent(value).created_by_tool(),
+                Some(CreatedBy::Tool { name, version })
+            );
+        }
+    }
+
+    #[test]
+    fn created_by_tool_without_version_is_raw() {
+        for value in ["handmade", "/1.0"] {
+            assert_eq!(
+                created_by_torrent(value).created_by_tool(),
+                Some(CreatedBy::Unrecognized(value.as_bytes()))
+            );
+        }
+
+        let parser = Bencoding::decode(b"d10:created by2:\xff1e").unwrap();
+        assert_eq!(
+            parser.created_by_tool(),
+            Some(CreatedBy::Unrecognized(b"\xff1"))
+        );
+        assert_eq!(Bencoding::decode(b"de").unwrap().created_by_tool(), None);
+    }
+
+    #[test]
+    fn collections_from_info() {
+        let parser =
+            Bencoding::decode(b"d4:infod11:collectionsl6:albumsi1e1:\xff5:tourse4:name1:aee")
+                .unwrap();
+        assert_eq!(parser.collections(), vec!["albums", "tours"]);
+        assert!(Bencoding::decode(b"d11:collectionsl1:xee")
+            .unwrap()
+            .collections()
+            .is_empty());
+    }
+
+    #[test]
+    fn files_checked_rejects_duplicate_paths() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl1:a1:beed6:lengthi2e4:pathl1:aeed6:lengthi3e4:pathl1:a1:.1:beee4:name1:d12:piece lengthi4eee",
+        )
+        .unwrap();
+        let error = parser.files_checked().unwrap_err();
+
+        assert!(matches!(&error, BencodingError::DuplicateFilePath(path) if path == "a/./b"));
+        assert_eq!(error.to_string(), "duplicate file path 'a/./b'");
+    }
+
+    #[test]
+    fn files_checked_ignores_padding_files() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed4:attr1:p6:lengthi3e4:pathl4:.pad1:3eed6:lengthi1e4:pathl1:beed4:attr1:p6:lengthi3e4:pathl4:.pad1:3eee4:name1:d12:piece lengthi4eee",
+        )
+        .unwrap();
+        assert_eq!(parser.files_checked().unwrap().len(), 4);
+        assert!(matches!(
+            Bencoding::decode(b"de").unwrap().files_checked(),
+            Err(BencodingError::MissingField(_))
+        ));
+    }
+
+    #[test]
+    fn files_prefer_utf8_path() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl4:caf\xe9e10:path.utf-8l5:caf\xc3\xa9eed6:lengthi2e4:pathl1:be10:path.utf-8l1:\xffeee4:name1:d12:piece lengthi4eee",
+        )
+        .unwrap();
+        let files = parser.files().unwrap();
+
+        assert_eq!(files[0].path, vec!["café".as_bytes().to_vec()]);
+        assert_eq!(files[1].path, vec![b"b".to_vec()]);
+    }
+
+    #[test]
+    fn files_use_utf8_path_over_malformed_path() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathi1e10:path.utf-8l1:aeee4:name1:d12:piece lengthi4eee",
+        )
+        .unwrap();
+        assert_eq!(parser.files().unwrap()[0].path, vec![b"a".to_vec()]);
+    }
+
+    #[test]
+    fn web_seed_urls_for_file_of_multi_file_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl4:docs10:read me.mdeed4:attr1:p6:lengthi3e4:pathl4:.pad1:3eee4:name6:bundle12:piece lengthi4ee8:url-listl17:http://a.example/22:http://b.example/filesee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parser.web_seed_urls_for_file(0),
+            vec![
+                "http://a.example/bundle/docs/read%20me.md",
+                "http://b.example/files/bundle/docs/read%20me.md",
+            ]
+        );
+        assert!(parser.web_seed_urls_for_file(1).is_empty());
+        assert!(parser.web_seed_urls_for_file(2).is_empty());
+    }
+
+    #[test]
+    fn web_seed_urls_for_file_of_single_file_torrent() {
+        let parser = Bencoding::decode(
+            b"d4:infod6:lengthi5e4:name7:a b.iso12:piece lengthi4ee8:url-listl17:http://a.example/22:http://b.example/x.isoee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            parser.web_seed_urls_for_file(0),
+            vec!["http://a.example/a%20b.iso", "http://b.example/x.iso"]
+        );
+        assert!(Bencoding::decode(b"de")
+            .unwrap()
+            .web_seed_urls_for_file(0)
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sha1")]
+    fn http_seed_request_url_of_piece() {
+        let parser = Bencoding::decode(include_bytes!("../../testdata/v1_multi.torrent")).unwrap();
+        let seed = std::str::from_utf8(parser.http_seeds()[0]).unwrap();
+        let info_hash = super::percent_encode(&parser.info_hash().unwrap());
+
+        assert_eq!(
+            parser.http_seed_request_url(seed, 2).unwrap(),
+            format!(
+                "http://seed.example.com/seed?info_hash={}&piece=2",
+                info_hash
+            )
+        );
+        assert_eq!(
+            parser
+                .http_seed_request_url("http://seed.example.com/seed.php?user=1", 0)
+                .unwrap(),
+            format!(
+                "http://seed.example.com/seed.php?user=1&info_hash={}&piece=0",
+                info_hash
+            )
+        );
+        assert_eq!(parser.http_seed_request_url(seed, 3), None);
+    }
+
+    #[test]
+    fn legacy_file_hashes_per_file() {
+        let parser = Bencoding::decode(include_bytes!("../../testdata/v1_multi.torrent")).unwrap();
+        let hashes = parser.legacy_file_hashes().unwrap();
+
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0], LegacyFileHashes::default());
+        assert_eq!(
+            hashes[1].md5sum,
+            Some(b"0123456789abcdef0123456789abcdef".as_slice())
+        );
+        assert_eq!(
+            hashes[1].md5(),
+            Some([
+                0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab,
+                0xcd, 0xef
+            ])
+        );
+        assert_eq!(hashes[1].sha1(), None);
+    }
+
+    #[test]
+    fn legacy_file_hashes_of_single_file_torrent() {
+        let mut data = b"d4:infod4:ed2k16:".to_vec();
+        data.extend_from_slice(&[7; 16]);
+        data.extend_from_slice(b"8:filehash3:abc6:lengthi1e6:md5sum4:nope4:name1:aee");
+        let parser = Bencoding::decode(&data).unwrap();
+        let hashes = parser.legacy_file_hashes().unwrap();
+
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes[0].ed2k_hash(), Some([7; 16]));
+        assert_eq!(hashes[0].filehash, Some(b"abc".as_slice()));
+        assert_eq!(hashes[0].sha1(), None);
+        assert_eq!(hashes[0].md5(), None);
+        assert!(Bencoding::decode(b"de")
+            .unwrap()
+            .legacy_file_hashes()
+            .is_none());
+    }
+
+    #[test]
+    fn file_count_of_both_layouts() {
+        let parser = Bencoding::decode(b"d4:infod6:lengthi5e4:name1:aee").unwrap();
+        assert_eq!(parser.file_count(), 1);
+        assert_eq!(multi_file_torrent(16, &[1, 2, 3]).file_count(), 3);
+        assert_eq!(Bencoding::decode(b"de").unwrap().file_count(), 0);
+    }
+
+    #[test]
+    fn file_count_excludes_padding_files() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl1:aeed4:attr1:p6:lengthi3e4:pathl4:.pad1:3eed6:lengthi1e4:pathl1:beee4:name1:d12:piece lengthi4eee",
+        )
+        .unwrap();
+        assert_eq!(parser.file_count(), 2);
+    }
+
+    #[test]
+    fn total_length_checked_overflow_is_error() {
+        let parser = multi_file_torrent(16, &[i64::MAX - 1, 1, 1]);
+        assert_eq!(parser.total_length(), None);
+        assert!(matches!(
+            parser.total_length_checked(),
+            Err(BencodingError::IntegerOverflow)
+        ));
+        assert_eq!(
+            multi_file_torrent(16, &[i64::MAX - 1, 1])
+                .total_length_checked()
+                .unwrap(),
+            i64::MAX
+        );
+    }
+
+    #[test]
+    fn total_length_checked_without_files_is_error() {
+        let parser = Bencoding::decode(b"d4:infod4:name1:aee").unwrap();
+        assert!(matches!(
+            parser.total_length_checked(),
+            Err(BencodingError::MissingField("length or files"))
+        ));
+    }
+
+    #[test]
+    fn file_piece_range_within_one_piece() {
+        let parser = multi_file_torrent(16, &[4, 4, 8]);
+        assert_eq!(parser.file_piece_range(0), Some(0..1));
+        assert_eq!(parser.file_piece_range(1), Some(0..1));
+        assert_eq!(parser.file_piece_range(2), Some(0..1));
+    }
+
+    #[test]
+    fn file_piece_range_across_piece_boundaries() {
+        let parser = multi_file_torrent(16, &[20, 30, 14]);
+        assert_eq!(parser.file_piece_range(0), Some(0..2));
+        assert_eq!(parser.file_piece_range(1), Some(1..4));
+        assert_eq!(parser.file_piece_range(2), Some(3..4));
+    }
+
+    #[test]
+    fn file_piece_range_ending_on_piece_boundary() {
+        let parser = multi_file_torrent(16, &[32, 16]);
+        assert_eq!(parser.file_piece_range(0), Some(0..2));
+        assert_eq!(parser.file_piece_range(1), Some(2..3));
+    }
+
+    #[test]
+    fn file_piece_range_of_empty_file_is_empty() {
+        let parser = multi_file_torrent(16, &[20, 0, 4]);
+        assert_eq!(parser.file_piece_range(1), Some(1..1));
+    }
+
+    #[test]
+    fn file_piece_range_out_of_bounds_is_none() {
+        let parser = multi_file_torrent(16, &[20]);
+        assert_eq!(parser.file_piece_range(1), None);
+    }
+
+    #[test]
+    fn file_entries_of_multi_file_torrent() {
+        let entries: Vec<FileEntry> = multi_file_torrent(4, &[6, 0, 5]).file_entries().collect();
+        assert_eq!(
+            entries,
+            vec![
+                FileEntry {
+                    path: vec![b"file0".to_vec()],
+                    length: 6,
+                    offset: 0,
+                    piece_range: 0..2,
+                },
+                FileEntry {
+                    path: vec![b"file1".to_vec()],
+                    length: 0,
+                    offset: 6,
+                    piece_range: 1..1,
+                },
+                FileEntry {
+                    path: vec![b"file2".to_vec()],
+                    length: 5,
+                    offset: 6,
+                    piece_range: 1..3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn file_entries_of_single_file_torrent() {
+        let parser = torrent_with_piece_length(16, None);
+        let entries: Vec<FileEntry> = parser.file_entries().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].offset, 0);
+    }
+
+    #[test]
+    fn file_entries_without_piece_length_is_empty() {
+        let parser = Bencoding::decode(b"d4:infod6:lengthi1e4:name1:aee").unwrap();
+        assert_eq!(parser.file_entries().count(), 0);
+    }
+
+    #[test]
+    fn piece_file_overlap_across_file_boundaries() {
+        let parser = multi_file_torrent(4, &[6, 1, 0, 5]);
+        assert_eq!(parser.piece_file_overlap(0), Some(vec![(0, 0, 4)]));
+        assert_eq!(
+            parser.piece_file_overlap(1),
+            Some(vec![(0, 4, 6), (1, 0, 1), (3, 0, 1)])
+        );
+        assert_eq!(parser.piece_file_overlap(2), Some(vec![(3, 1, 5)]));
+    }
+
+    #[test]
+    fn piece_file_overlap_of_short_last_piece() {
+        let parser = multi_file_torrent(4, &[6, 5]);
+        assert_eq!(parser.piece_file_overlap(2), Some(vec![(1, 2, 5)]));
+        assert_eq!(parser.piece_file_overlap(3), None);
+    }
+
+    #[test]
+    fn file_piece_range_without_piece_length_is_none() {
+        let parser = Bencoding::decode(b"d4:infod6:lengthi100e4:name1:aee").unwrap();
+        assert_eq!(parser.file_piece_range(0), None);
+    }
+
+    #[test]
+    fn url_list_as_single_string() {
+        let parser = Bencoding::decode(b"d8:url-list14:http://a.test/e").unwrap();
+        assert_eq!(parser.url_list(), vec![b"http://a.test/".as_slice()]);
+    }
+
+    #[test]
+    fn all_web_seeds_merges_and_deduplicates() {
+        let parser = Bencoding::decode(
+            b"d9:httpseedsl14:http://b.test/14:http://a.test/e8:url-listl14:http://a.test/14:http://c.test/14:http://a.test/ee",
+        )
+        .unwrap();
+        assert_eq!(
+            parser.all_web_seeds(),
+            vec!["http://a.test/", "http://c.test/", "http://b.test/"]
+        );
+    }
+
+    #[test]
+    fn all_web_seeds_without_seeds_is_empty() {
+        let parser = Bencoding::decode(b"d4:infodee").unwrap();
+        assert!(parser.all_web_seeds().is_empty());
+    }
+
+    fn info_validation(info: &str) -> Result<(), BencodingError> {
+        let data = format!("d4:infod{}ee", info);
+        return Bencoding::decode(data.as_bytes()).unwrap().validate_info();
+    }
+
+    #[test]
+    fn validate_info_accepts_complete_info() {
+        assert!(info_validation("6:lengthi1e4:name1:a12:piece lengthi4e6:pieces0:").is_ok());
+        assert!(multi_file_torrent(4, &[1, 2]).validate_info().is_ok());
+    }
+
+    #[test]
+    fn validate_info_without_info() {
+        let parser = Bencoding::decode(b"d8:announce3:urle").unwrap();
+        assert!(matches!(
+            parser.validate_info(),
+            Err(BencodingError::MissingField("info"))
+        ));
+    }
+
+    #[test]
+    fn validate_info_reports_each_missing_field() {
+        assert!(matches!(
+            info_validation("6:lengthi1e12:piece lengthi4e6:pieces0:"),
+            Err(BencodingError::MissingField("name"))
+        ));
+        assert!(matches!(
+            info_validation("6:lengthi1e4:name1:a6:pieces0:"),
+            Err(BencodingError::MissingField("piece length"))
+        ));
+        assert!(matches!(
+            info_validation("6:lengthi1e4:name1:a12:piece lengthi4e"),
+            Err(BencodingError::MissingField("pieces"))
+        ));
+        assert!(matches!(
+            info_validation("4:name1:a12:piece lengthi4e6:pieces0:"),
+            Err(BencodingError::MissingField("length or files"))
+        ));
+    }
+
+    #[test]
+    fn validate_info_field_of_wrong_type_is_missing() {
+        assert!(matches!(
+            info_validation("6:lengthi1e4:name1:a12:piece length1:46:pieces0:"),
+            Err(BencodingError::MissingField("piece length"))
+        ));
+    }
+
+    #[test]
+    fn private_torrent_allows_trackers_only() {
+        let parser = Bencoding::decode(b"d4:infod4:name1:a7:privatei1eee").unwrap();
+        assert!(parser.is_private());
+        assert_eq!(parser.peer_source_policy(), PeerSourcePolicy::TrackersOnly);
+    }
+
+    #[test]
+    fn public_torrent_allows_all_peer_sources() {
+        for data in [b"d4:infod4:name1:aee".as_slice(), b"d4:infod7:privatei0eee"] {
+            let parser = Bencoding::decode(data).unwrap();
+            assert!(!parser.is_private());
+            assert_eq!(
+                parser.peer_source_policy(),
+                PeerSourcePolicy::TrackersDhtAndPex
+            );
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use super::{compact, Bencoding, BencodingError, BencodingValue};
+
+/// How much a leecher counts for in `Bencoding::health`, compared to a
+/// seeder: leechers hold part of the data on average, and share what they
+/// have.
+const LEECHER_WEIGHT: f64 = 0.5;
+
+/// The swarm statistics of one torrent in a tracker scrape response
+/// (BEP 48), read by `Bencoding::scrape_files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrapeEntry {
+    /// The info hash the statistics are for, the key of the entry in `files`.
+    pub info_hash: [u8; 20],
+    /// The number of seeders, from `complete`, or 0 if it's missing.
+    pub complete: i64,
+    /// The number of completed downloads the tracker has seen, from
+    /// `downloaded`, or 0 if it's missing.
+    pub downloaded: i64,
+    /// The number of leechers, from `incomplete`, or 0 if it's missing.
+    pub incomplete: i64,
+    /// The torrent name, from the optional `name`.
+    pub name: Option<Vec<u8>>,
+}
+
+impl Bencoding {
+    /// Reads the compact `peers` string of a tracker announce response
+    /// (BEP 23), where each peer is a 6-byte IPv4 address and port.
+    ///
+    /// A response without a compact `peers` string yields an empty list.
+    pub fn compact_peers(&self) -> Result<Vec<SocketAddrV4>, BencodingError> {
+        match self.get_string(b"peers") {
+            Some(peers) => return compact::peers_v4(peers),
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    /// Reads the compact `peers6` string of a tracker announce response
+    /// (BEP 7), where each peer is an 18-byte IPv6 address and port.
+    ///
+    /// A response without `peers6` yields an empty list.
+    pub fn compact_peers6(&self) -> Result<Vec<SocketAddrV6>, BencodingError> {
+        match self.get_string(b"peers6") {
+            Some(peers) => return compact::peers_v6(peers),
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    /// The peers of both `peers` and `peers6`, for a dual-stack client,
+    /// with those of the preferred address family first: the IPv6 ones if
+    /// `prefer_v6`, else the IPv4 ones. Each family keeps the tracker's
+    /// order.
+    ///
+    /// A family whose compact string is malformed is left out, as
+    /// `compact_peers` or `compact_peers6` would reject it.
+    pub fn peers_preferring(&self, prefer_v6: bool) -> Vec<SocketAddr> {
+        let v4 = self.compact_peers().unwrap_or_default();
+        let v6 = self.compact_peers6().unwrap_or_default();
+        let v4 = v4.into_iter().map(SocketAddr::V4);
+        let v6 = v6.into_iter().map(SocketAddr::V6);
+
+        if prefer_v6 {
+            return v6.chain(v4).collect();
+        }
+        return v4.chain(v6).collect();
+    }
+
+    /// The `tracker id` of a tracker announce response, with a space in the
+    /// key. Clients must keep it and send it back as `trackerid` on their
+    /// next announces to that tracker.
+    pub fn tracker_id(&self) -> Option<&[u8]> {
+        return self.get_string(b"tracker id");
+    }
+
+    /// The minimum number of seconds a client must wait between announces,
+    /// from `min interval` of a tracker announce response, with a space in
+    /// the key.
+    pub fn min_interval(&self) -> Option<i64> {
+        return self.get_integer(b"min interval");
+    }
+
+    /// The number of seeders in the swarm, from the `complete` count of a
+    /// tracker announce or scrape response.
+    pub fn seeders(&self) -> Option<i64> {
+        return self.get_integer(b"complete");
+    }
+
+    /// The number of leechers in the swarm, from the `incomplete` count of a
+    /// tracker announce or scrape response.
+    pub fn leechers(&self) -> Option<i64> {
+        return self.get_integer(b"incomplete");
+    }
+
+    /// A simple swarm health figure for display: `seeders + 0.5 * leechers`,
+    /// roughly the number of complete copies of the data in the swarm.
+    ///
+    /// Returns `None` if either count is missing or negative.
+    pub fn health(&self) -> Option<f64> {
+        let seeders = self.seeders().filter(|&seeders| seeders >= 0)?;
+        let leechers = self.leechers().filter(|&leechers| leechers >= 0)?;
+
+        return Some(seeders as f64 + LEECHER_WEIGHT * leechers as f64);
+    }
+
+    /// The per-torrent entries of a tracker scrape response (BEP 48), from
+    /// the `files` dictionary keyed by 20-byte info hash, in info hash order.
+    ///
+    /// Entries whose key isn't 20 bytes long, or whose value isn't a
+    /// dictionary, are skipped. Any of the `complete`, `downloaded` and
+    /// `incomplete` counts that is missing or isn't an integer is read as 0,
+    /// as some trackers leave out the counts they don't keep, such as
+    /// `downloaded`. A response without `files` yields an empty list.
+    pub fn scrape_files(&self) -> Vec<ScrapeEntry> {
+        let files = match self.get_dict(b"files") {
+            Some(files) => files,
+            None => return Vec::new(),
+        };
+
+        let mut entries: Vec<ScrapeEntry> = files
+            .iter()
+            .filter_map(|(info_hash, stats)| {
+                let stats = stats.as_dict()?;
+                let integer = |key: &[u8]| {
+                    return stats
+                        .get(key)
+                        .and_then(BencodingValue::as_integer)
+                        .unwrap_or(0);
+                };
+                return Some(ScrapeEntry {
+                    info_hash: info_hash.as_slice().try_into().ok()?,
+                    complete: integer(b"complete"),
+                    downloaded: integer(b"downloaded"),
+                    incomplete: integer(b"incomplete"),
+                    name: stats
+                        .get(b"name".as_slice())
+                        .and_then(BencodingValue::as_string)
+                        .map(<[u8]>::to_vec),
+                });
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.info_hash);
+
+        return entries;
+    }
+
+    /// The minimum number of seconds a client must wait between scrapes,
+    /// from `flags.min_request_interval` of a tracker scrape response
+    /// (BEP 48).
+    pub fn scrape_min_request_interval(&self) -> Option<i64> {
+        return self
+            .get_dict(b"flags")?
+            .get(b"min_request_interval".as_slice())?
+            .as_integer();
+    }
+
+    /// The peer addresses from `x.pe`, as carried by magnet links and some
+    /// torrents to bootstrap connections, either a single string or a list of
+    /// them.
+    ///
+    /// Each address is `ip:port`, with IPv6 addresses in brackets as in
+    /// `[2001:db8::1]:6881`. Addresses that don't parse, including host
+    /// names, which would need resolving, are skipped.
+    pub fn x_pe_peers(&self) -> Vec<SocketAddr> {
+        let addresses = match self.dict.get(b"x.pe".as_slice()) {
+            Some(BencodingValue::String(address)) => vec![address.as_slice()],
+            Some(BencodingValue::List(_)) => self.get_string_list(b"x.pe", true).unwrap(),
+            _ => return Vec::new(),
+        };
+
+        return addresses
+            .into_iter()
+            .filter_map(|address| std::str::from_utf8(address).ok()?.parse().ok())
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+    use crate::bencoding_parser::{Bencoding, ScrapeEntry};
+
+    #[test]
+    fn compact_peers_from_announce_response() {
+        let mut data = b"d8:intervali1800e5:peers12:".to_vec();
+        data.extend_from_slice(b"\x0A\x00\x00\x01\x1A\xE1\xC0\xA8\x01\x02\xC8\xD5");
+        data.extend_from_slice(b"e");
+        let parser = Bencoding::decode(&data).unwrap();
+        let peers = parser.compact_peers().unwrap();
+
+        assert_eq!(
+            peers,
+            vec![
+                SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6881),
+                SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 51413),
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_peers6_from_announce_response() {
+        let mut data = b"d8:intervali1800e6:peers636:".to_vec();
+        data.extend_from_slice(b"\x20\x01\x0D\xB8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01");
+        data.extend_from_slice(b"\x1A\xE1");
+        data.extend_from_slice(b"\xFE\x80\x00\x00\x00\x00\x00\x00\x02\x1B\x63\xFF\xFE\x84\x88\xF2");
+        data.extend_from_slice(b"\xC8\xD5e");
+        let parser = Bencoding::decode(&data).unwrap();
+        let peers = parser.compact_peers6().unwrap();
+
+        assert_eq!(
+            peers,
+            vec![
+                SocketAddrV6::new(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 6881, 0, 0),
+                SocketAddrV6::new(
+                    Ipv6Addr::new(0xfe80, 0, 0, 0, 0x21b, 0x63ff, 0xfe84, 0x88f2),
+                    51413,
+                    0,
+                    0
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_peers6_with_partial_entry_is_error() {
+        let parser = Bencoding::decode(b"d6:peers66:abcdefe").unwrap();
+        assert!(parser.compact_peers6().is_err());
+    }
+
+    #[test]
+    fn compact_peers_without_peers_is_empty() {
+        let parser = Bencoding::decode(b"d8:intervali1800ee").unwrap();
+        assert!(parser.compact_peers().unwrap().is_empty());
+        assert!(parser.compact_peers6().unwrap().is_empty());
+    }
+
+    #[test]
+    fn peers_preferring_orders_by_family() {
+        let mut data = b"d5:peers12:".to_vec();
+        data.extend_from_slice(b"\x0A\x00\x00\x01\x1A\xE1\xC0\xA8\x01\x02\xC8\xD5");
+        data.extend_from_slice(b"6:peers618:");
+        data.extend_from_slice(b"\x20\x01\x0D\xB8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01");
+        data.extend_from_slice(b"\x1A\xE1e");
+        let parser = Bencoding::decode(&data).unwrap();
+
+        let first = SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 6881));
+        let second = SocketAddr::from((Ipv4Addr::new(192, 168, 1, 2), 51413));
+        let v6 = SocketAddr::from((Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 6881));
+        assert_eq!(parser.peers_preferring(false), vec![first, second, v6]);
+        assert_eq!(parser.peers_preferring(true), vec![v6, first, second]);
+    }
+
+    #[test]
+    fn peers_preferring_skips_malformed_family() {
+        let parser =
+            Bencoding::decode(b"d5:peers6:\x0A\x00\x00\x01\x1A\xE16:peers63:abce").unwrap();
+        assert_eq!(
+            parser.peers_preferring(true),
+            vec![SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 6881))]
+        );
+        assert!(Bencoding::decode(b"de")
+            .unwrap()
+            .peers_preferring(false)
+            .is_empty());
+    }
+
+    #[test]
+    fn x_pe_peers_v4_and_v6() {
+        let parser =
+            Bencoding::decode(b"d4:x.pel13:10.0.0.1:688119:[2001:db8::1]:5141314:example.com:80ee")
+                .unwrap();
+        assert_eq!(
+            parser.x_pe_peers(),
+            vec![
+                SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 6881)),
+                SocketAddr::from((Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 51413)),
+            ]
+        );
+    }
+
+    #[test]
+    fn x_pe_peers_single_string() {
+        let parser = Bencoding::decode(b"d4:x.pe13:10.0.0.1:6881e").unwrap();
+        assert_eq!(parser.x_pe_peers().len(), 1);
+    }
+
+    #[test]
+    fn tracker_id_from_announce_response() {
+        let parser =
+            Bencoding::decode(b"d8:intervali1800e5:peers0:10:tracker id8:a1b2c3d4e").unwrap();
+        assert_eq!(parser.tracker_id(), Some(b"a1b2c3d4".as_slice()));
+
+        let parser = Bencoding::decode(b"d9:trackerid8:a1b2c3d4e").unwrap();
+        assert_eq!(parser.tracker_id(), None);
+    }
+
+    #[test]
+    fn swarm_stats_from_announce_response() {
+        let mut data =
+            b"d8:completei143e10:incompletei27e8:intervali1800e12:min intervali900e5:peers6:"
+                .to_vec();
+        data.extend_from_slice(b"\x0A\x00\x00\x01\x1A\xE1e");
+        let parser = Bencoding::decode(&data).unwrap();
+
+        assert_eq!(parser.seeders(), Some(143));
+        assert_eq!(parser.leechers(), Some(27));
+        assert_eq!(parser.min_interval(), Some(900));
+        assert_eq!(Bencoding::decode(b"de").unwrap().seeders(), None);
+    }
+
+    #[test]
+    fn health_from_swarm_stats() {
+        let parser = Bencoding::decode(b"d8:completei143e10:incompletei27ee").unwrap();
+        assert_eq!(parser.health(), Some(156.5));
+        let parser = Bencoding::decode(b"d8:completei0e10:incompletei0ee").unwrap();
+        assert_eq!(parser.health(), Some(0.0));
+    }
+
+    #[test]
+    fn health_without_stats_is_none() {
+        assert_eq!(
+            Bencoding::decode(b"d8:completei3ee").unwrap().health(),
+            None
+        );
+        let parser = Bencoding::decode(b"d8:completei-1e10:incompletei2ee").unwrap();
+        assert_eq!(parser.health(), None);
+    }
+
+    #[test]
+    fn scrape_files_from_scrape_response() {
+        let mut data = b"d5:filesd20:".to_vec();
+        data.extend_from_slice(&[0xbb; 20]);
+        data.extend_from_slice(b"d8:completei5e10:downloadedi50e10:incompletei10e4:name3:fooe20:");
+        data.extend_from_slice(&[0xaa; 20]);
+        data.extend_from_slice(b"d8:completei0e10:downloadedi1e10:incompletei2ee");
+        data.extend_from_slice(b"5:short");
+        data.extend_from_slice(b"d8:completei0e10:downloadedi0e10:incompletei0ee");
+        data.extend_from_slice(b"e5:flagsd20:min_request_intervali3600eee");
+        let parser = Bencoding::decode(&data).unwrap();
+
+        assert_eq!(
+            parser.scrape_files(),
+            vec![
+                ScrapeEntry {
+                    info_hash: [0xaa; 20],
+                    complete: 0,
+                    downloaded: 1,
+                    incomplete: 2,
+                    name: None,
+                },
+                ScrapeEntry {
+                    info_hash: [0xbb; 20],
+                    complete: 5,
+                    downloaded: 50,
+                    incomplete: 10,
+                    name: Some(b"foo".to_vec()),
+                },
+            ]
+        );
+        assert_eq!(parser.scrape_min_request_interval(), Some(3600));
+    }
+
+    #[test]
+    fn scrape_files_defaults_missing_counts() {
+        let mut data = b"d5:filesd20:".to_vec();
+        data.extend_from_slice(&[0xaa; 20]);
+        data.extend_from_slice(b"d8:completei7e10:incompletei3ee20:");
+        data.extend_from_slice(&[0xbb; 20]);
+        data.extend_from_slice(b"de20:");
+        data.extend_from_slice(&[0xcc; 20]);
+        data.extend_from_slice(b"d8:complete1:x10:downloadedi9ee20:");
+        data.extend_from_slice(&[0xdd; 20]);
+        data.extend_from_slice(b"i1eee");
+        let parser = Bencoding::decode(&data).unwrap();
+        let counts: Vec<([u8; 20], i64, i64, i64)> = parser
+            .scrape_files()
+            .iter()
+            .map(|entry| {
+                return (
+                    entry.info_hash,
+                    entry.complete,
+                    entry.downloaded,
+                    entry.incomplete,
+                );
+            })
+            .collect();
+
+        assert_eq!(
+            counts,
+            vec![
+                ([0xaa; 20], 7, 0, 3),
+                ([0xbb; 20], 0, 0, 0),
+                ([0xcc; 20], 0, 9, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn scrape_files_without_files_is_empty() {
+        let parser = Bencoding::decode(b"d14:failure reason5:oops!e").unwrap();
+        assert!(parser.scrape_files().is_empty());
+        assert_eq!(parser.scrape_min_request_interval(), None);
+    }
+}
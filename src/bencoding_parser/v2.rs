@@ -0,0 +1,643 @@
+use std::io::{ErrorKind, Read};
+
+use sha2::{Digest, Sha256};
+
+use super::{Bencoding, BencodingDict, BencodingError, BencodingValue};
+
+/// BEP 52 hashes files in blocks of 16 KiB, the leaves of each file's merkle
+/// tree.
+const BLOCK_LEN: usize = 16 * 1024;
+
+/// The hash of a merkle tree node.
+type Hash = [u8; 32];
+
+impl Bencoding {
+    /// Upgrades a single-file v1 torrent to BEP 52, given the file's
+    /// contents in `data`.
+    ///
+    /// The returned torrent has the v2 `info` fields, `meta version` 2 and a
+    /// `file tree` with the file's `pieces root`, and the top-level `piece
+    /// layers` the file needs if it spans more than one piece. With `hybrid`
+    /// the v1 fields are kept, so it's a hybrid torrent with the same v1
+    /// content (but a new v1 info-hash, as `info` changed); without it the v1
+    /// `length` and `pieces` are removed. Every other field is kept.
+    ///
+    /// Multi-file torrents are `Err(Unsupported)`. The piece length must be a
+    /// power of two of at least 16 KiB, or this is `Err(InvalidPieceLength)`.
+    /// Data shorter than `length` is `Err(Io)` and data longer than it is
+    /// ignored. The result's dictionaries are sorted, as by
+    /// `canonicalize_in_place`.
+    pub fn upgrade_to_v2<R: Read>(
+        &self,
+        data: R,
+        hybrid: bool,
+    ) -> Result<Bencoding, BencodingError> {
+        let info = self
+            .info_dict()
+            .ok_or(BencodingError::MissingField("info"))?;
+        if info.contains_key(b"files".as_slice()) {
+            return Err(BencodingError::Unsupported(
+                "upgrading multi-file torrents to v2",
+            ));
+        }
+        let name = self.name().ok_or(BencodingError::MissingField("name"))?;
+        let length = info
+            .get(b"length".as_slice())
+            .and_then(BencodingValue::as_integer)
+            .filter(|&length| length >= 0)
+            .ok_or(BencodingError::MissingField("length"))?;
+        let piece_length = self
+            .piece_length()
+            .ok_or(BencodingError::MissingField("piece length"))?;
+        check_piece_length(piece_length)?;
+
+        let mut layers = BencodingDict::new();
+        let file = file_entry(data, length, piece_length, &mut layers)?;
+        let mut dict = self.dict.clone();
+        if !layers.is_empty() {
+            dict.insert(b"piece layers".to_vec(), BencodingValue::Dict(layers));
+        }
+
+        let mut leaf = BencodingDict::new();
+        leaf.insert(Vec::new(), BencodingValue::Dict(file));
+        let mut tree = BencodingDict::new();
+        tree.insert(name.to_vec(), BencodingValue::Dict(leaf));
+
+        let mut info = info.clone();
+        info.insert(b"file tree".to_vec(), BencodingValue::Dict(tree));
+        info.insert(b"meta version".to_vec(), BencodingValue::Integer(2));
+        if !hybrid {
+            info.retain(|key, _| key != b"length" && key != b"pieces");
+        }
+        dict.insert(b"info".to_vec(), BencodingValue::Dict(info));
+
+        // The new keys were added at the end, out of order with the
+        // `indexmap` feature.
+        let mut upgraded = Bencoding {
+            dict,
+            non_canonical: None,
+        };
+        upgraded.canonicalize_in_place();
+
+        return Ok(upgraded);
+    }
+
+    /// Creates a v2-only torrent (BEP 52) from its files, given as their
+    /// path components, length and contents, in any order.
+    ///
+    /// `info` only has the v2 fields: `file tree`, `meta version` 2, `name`
+    /// and `piece length`, without the v1 `pieces`, `length` or `files`, so
+    /// v1-only clients can't use the torrent. The top-level `piece layers`
+    /// holds the layer of every file spanning more than one piece, and is
+    /// empty if there is none. Nothing else is set: add trackers and other
+    /// fields to the result before encoding it.
+    ///
+    /// The piece length must be a power of two of at least 16 KiB, or this
+    /// is `Err(InvalidPieceLength)`. A path that is empty or has an empty
+    /// component is `Err(Unsupported)`, and one that is the same as another
+    /// file's, or that goes through another file, is
+    /// `Err(DuplicateFilePath)`. Short data is `Err(Io)`, as with
+    /// `upgrade_to_v2`, and the result's dictionaries are sorted likewise.
+    pub fn create_v2<R: Read>(
+        name: &[u8],
+        piece_length: i64,
+        files: Vec<(Vec<Vec<u8>>, u64, R)>,
+    ) -> Result<Bencoding, BencodingError> {
+        check_piece_length(piece_length)?;
+
+        let mut tree = BencodingDict::new();
+        let mut layers = BencodingDict::new();
+        for (path, length, data) in files {
+            let length = i64::try_from(length).map_err(|_| BencodingError::IntegerOverflow)?;
+            let file = file_entry(data, length, piece_length, &mut layers)?;
+            insert_file(&mut tree, &path, file)?;
+        }
+
+        let mut info = BencodingDict::new();
+        info.insert(b"file tree".to_vec(), BencodingValue::Dict(tree));
+        info.insert(b"meta version".to_vec(), BencodingValue::Integer(2));
+        info.insert(b"name".to_vec(), BencodingValue::String(name.to_vec()));
+        info.insert(
+            b"piece length".to_vec(),
+            BencodingValue::Integer(piece_length),
+        );
+        let mut dict = BencodingDict::new();
+        dict.insert(b"info".to_vec(), BencodingValue::Dict(info));
+        dict.insert(b"piece layers".to_vec(), BencodingValue::Dict(layers));
+
+        // The file tree is built in the order the files came in.
+        let mut created = Bencoding {
+            dict,
+            non_canonical: None,
+        };
+        created.canonicalize_in_place();
+
+        return Ok(created);
+    }
+}
+
+/// Checks that `piece_length` is a power of two of at least 16 KiB, as
+/// BEP 52 requires.
+fn check_piece_length(piece_length: i64) -> Result<(), BencodingError> {
+    if piece_length < BLOCK_LEN as i64 || piece_length & (piece_length - 1) != 0 {
+        return Err(BencodingError::InvalidPieceLength {
+            piece_length,
+            reason: "not a power of two of at least 16 KiB",
+        });
+    }
+
+    return Ok(());
+}
+
+/// The `file tree` entry of a file of `length` bytes read from `data`: its
+/// `length` and, unless it's empty, its `pieces root`. The file's piece
+/// layer is added to `layers` if it spans more than one piece.
+fn file_entry<R: Read>(
+    data: R,
+    length: i64,
+    piece_length: i64,
+    layers: &mut BencodingDict,
+) -> Result<BencodingDict, BencodingError> {
+    let leaves = block_hashes(data, length as u64)?;
+    let mut file = BencodingDict::new();
+    file.insert(b"length".to_vec(), BencodingValue::Integer(length));
+    if !leaves.is_empty() {
+        let (root, layer) = merkle_tree(&leaves, piece_length as usize / BLOCK_LEN);
+        file.insert(
+            b"pieces root".to_vec(),
+            BencodingValue::String(root.to_vec()),
+        );
+        if length > piece_length {
+            layers.insert(root.to_vec(), BencodingValue::String(layer.concat()));
+        }
+    }
+
+    return Ok(file);
+}
+
+/// Adds `file` to `tree` at `path`, creating the directories on the way.
+fn insert_file(
+    tree: &mut BencodingDict,
+    path: &[Vec<u8>],
+    file: BencodingDict,
+) -> Result<(), BencodingError> {
+    if path.is_empty() || path.iter().any(Vec::is_empty) {
+        return Err(BencodingError::Unsupported("empty file path components"));
+    }
+    let duplicate = || {
+        let path: Vec<String> = path
+            .iter()
+            .map(|component| String::from_utf8_lossy(component).into_owned())
+            .collect();
+        return BencodingError::DuplicateFilePath(path.join("/"));
+    };
+
+    let mut directory = tree;
+    for component in path {
+        directory = match directory
+            .entry(component.clone())
+            .or_insert_with(|| BencodingValue::Dict(BencodingDict::new()))
+        {
+            BencodingValue::Dict(entry) if !entry.contains_key(b"".as_slice()) => entry,
+            _ => return Err(duplicate()),
+        };
+    }
+    if !directory.is_empty() {
+        return Err(duplicate());
+    }
+    directory.insert(Vec::new(), BencodingValue::Dict(file));
+
+    return Ok(());
+}
+
+/// The root of the BEP 52 merkle tree over `leaf_hashes`, the SHA-256 of
+/// each 16 KiB block of a file, as the file's `pieces root`.
+///
+/// The leaves are padded with zero hashes to the next power of two. The root
+/// doesn't depend on `piece_length` beyond that padding, which already covers
+/// whole pieces for files spanning several; it's taken so the tree is built
+/// the same way as the piece layer. No leaves give an all-zero root, though
+/// empty files have no `pieces root` at all.
+pub fn merkle_root(leaf_hashes: &[[u8; 32]], piece_length: u64) -> [u8; 32] {
+    let blocks_per_piece = (piece_length / BLOCK_LEN as u64) as usize;
+
+    return merkle_tree(leaf_hashes, blocks_per_piece).0;
+}
+
+/// The merkle proof of the piece at `piece_index` of a file: the sibling
+/// hashes on the way from the piece up to the file's `pieces root`, lowest
+/// first, as checked by `verify_v2_piece`.
+///
+/// `piece_layer` is the file's layer from `piece layers`, see
+/// `Bencoding::piece_layer`. It's padded to a power of two with the hash of a
+/// piece of zero blocks, as BEP 52 pads the tree's leaves with zero hashes.
+/// Returns `None` if the index is out of range.
+pub fn piece_proof(
+    piece_layer: &[[u8; 32]],
+    piece_length: u64,
+    piece_index: usize,
+) -> Option<Vec<[u8; 32]>> {
+    if piece_index >= piece_layer.len() {
+        return None;
+    }
+
+    let mut pad = [0; 32];
+    let mut covered = BLOCK_LEN as u64;
+    while covered < piece_length {
+        pad = hash_pair(&pad, &pad);
+        covered *= 2;
+    }
+    let mut layer = piece_layer.to_vec();
+    layer.resize(piece_layer.len().next_power_of_two(), pad);
+
+    let mut proof = Vec::new();
+    let mut index = piece_index;
+    while layer.len() > 1 {
+        proof.push(layer[index ^ 1]);
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        index /= 2;
+    }
+
+    return Some(proof);
+}
+
+/// Checks that `piece_hash` is the hash of the piece at `piece_index` of the
+/// file with the `pieces root` `root`, using the sibling hashes in `proof`,
+/// lowest first, as built by `piece_proof`.
+///
+/// This verifies a single piece against the root alone, without the rest of
+/// the file's piece layer.
+pub fn verify_v2_piece(
+    root: &[u8; 32],
+    piece_index: usize,
+    piece_hash: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> bool {
+    let mut hash = *piece_hash;
+    let mut index = piece_index;
+    for sibling in proof {
+        hash = match index % 2 {
+            0 => hash_pair(&hash, sibling),
+            _ => hash_pair(sibling, &hash),
+        };
+        index /= 2;
+    }
+
+    return index == 0 && hash == *root;
+}
+
+/// The SHA-256 of each 16 KiB block of the first `length` bytes of `data`.
+fn block_hashes<R: Read>(mut data: R, length: u64) -> Result<Vec<Hash>, BencodingError> {
+    let mut hashes = Vec::new();
+    let mut block = Vec::with_capacity(BLOCK_LEN);
+    let mut remaining = length;
+    while remaining > 0 {
+        block.clear();
+        let wanted = remaining.min(BLOCK_LEN as u64);
+        let read = data
+            .by_ref()
+            .take(wanted)
+            .read_to_end(&mut block)
+            .map_err(BencodingError::Io)?;
+        if (read as u64) < wanted {
+            return Err(BencodingError::Io(ErrorKind::UnexpectedEof.into()));
+        }
+        hashes.push(Sha256::digest(&block).into());
+        remaining -= wanted;
+    }
+
+    return Ok(hashes);
+}
+
+/// The root of the merkle tree over `leaves`, padded with zero hashes to a
+/// power of two, and its piece layer: the nodes covering `blocks_per_piece`
+/// leaves each, for the pieces holding data.
+fn merkle_tree(leaves: &[Hash], blocks_per_piece: usize) -> (Hash, Vec<Hash>) {
+    let mut layer = leaves.to_vec();
+    layer.resize(leaves.len().next_power_of_two(), [0; 32]);
+
+    let mut piece_layer = Vec::new();
+    let mut covered = 1;
+    loop {
+        if covered == blocks_per_piece {
+            piece_layer = layer[..leaves.len().div_ceil(blocks_per_piece)].to_vec();
+        }
+        if layer.len() == 1 {
+            return (layer[0], piece_layer);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        covered *= 2;
+    }
+}
+
+/// The parent of two merkle tree nodes.
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    return Sha256::new()
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into();
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::merkle_tree;
+    use crate::bencoding_parser::{
+        merkle_root, piece_proof, verify_v2_piece, Bencoding, BencodingError,
+    };
+
+    fn data() -> Vec<u8> {
+        return (0..40000).map(|i| (i * 7 % 251) as u8).collect();
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        return (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+    }
+
+    fn torrent(length: usize, piece_length: i64) -> Bencoding {
+        let data = format!(
+            "d4:infod6:lengthi{}e4:name4:file12:piece lengthi{}e6:pieces0:7:privatei1eee",
+            length, piece_length
+        );
+        return Bencoding::decode(data.as_bytes()).unwrap();
+    }
+
+    fn leaves(count: u8) -> Vec<[u8; 32]> {
+        return (0..count).map(|i| Sha256::digest([i]).into()).collect();
+    }
+
+    #[test]
+    fn merkle_root_of_single_leaf_is_leaf() {
+        let leaves = leaves(1);
+        assert_eq!(merkle_root(&leaves, 16384), leaves[0]);
+    }
+
+    #[test]
+    fn merkle_root_of_two_leaves() {
+        assert_eq!(
+            merkle_root(&leaves(2), 16384).to_vec(),
+            unhex("30e1867424e66e8b6d159246db94e3486778136f7e386ff5f001859d6b8484ab")
+        );
+    }
+
+    #[test]
+    fn merkle_root_pads_with_zero_hashes() {
+        let root = unhex("41886469306e351e1887e826f9fd0a51c08cfa38881129135e646320e3a134a2");
+        let mut padded = leaves(5);
+        padded.resize(8, [0; 32]);
+
+        assert_eq!(merkle_root(&leaves(5), 16384).to_vec(), root);
+        assert_eq!(merkle_root(&leaves(5), 65536).to_vec(), root);
+        assert_eq!(merkle_root(&padded, 16384).to_vec(), root);
+    }
+
+    #[test]
+    fn merkle_root_of_no_leaves_is_zero() {
+        assert_eq!(merkle_root(&[], 16384), [0; 32]);
+    }
+
+    #[test]
+    fn verify_v2_piece_with_proofs() {
+        let leaves = leaves(5);
+        let (root, layer) = merkle_tree(&leaves, 2);
+        assert_eq!(layer.len(), 3);
+
+        for (i, piece_hash) in layer.iter().enumerate() {
+            let proof = piece_proof(&layer, 32768, i).unwrap();
+            assert_eq!(proof.len(), 2);
+            assert!(verify_v2_piece(&root, i, piece_hash, &proof));
+            assert!(!verify_v2_piece(&root, i ^ 1, piece_hash, &proof));
+        }
+        assert!(piece_proof(&layer, 32768, 3).is_none());
+    }
+
+    #[test]
+    fn verify_v2_piece_rejects_tampered_hashes() {
+        let (root, layer) = merkle_tree(&leaves(4), 2);
+        let proof = piece_proof(&layer, 32768, 1).unwrap();
+
+        assert!(verify_v2_piece(&root, 1, &layer[1], &proof));
+        assert!(!verify_v2_piece(&root, 1, &[0; 32], &proof));
+        assert!(!verify_v2_piece(&root, 1, &layer[1], &[[0; 32]]));
+        assert!(!verify_v2_piece(&root, 5, &layer[1], &proof));
+    }
+
+    #[test]
+    fn verify_v2_piece_of_upgraded_torrent() {
+        let upgraded = torrent(40000, 32768)
+            .upgrade_to_v2(data().as_slice(), false)
+            .unwrap();
+        let root: [u8; 32] =
+            unhex("64824cf81b2ac8c4eafa2be687b8741af3f643acbf230a64e0544b2244842cb2")
+                .try_into()
+                .unwrap();
+        let layer = upgraded.piece_layer(&root).unwrap();
+        let proof = piece_proof(&layer, 32768, 1).unwrap();
+
+        assert_eq!(proof, vec![layer[0]]);
+        assert!(verify_v2_piece(&root, 1, &layer[1], &proof));
+    }
+
+    #[test]
+    fn upgrade_to_v2_computes_root_and_piece_layer() {
+        let upgraded = torrent(40000, 32768)
+            .upgrade_to_v2(data().as_slice(), false)
+            .unwrap();
+        let root = unhex("64824cf81b2ac8c4eafa2be687b8741af3f643acbf230a64e0544b2244842cb2");
+
+        let info = upgraded.info_dict().unwrap();
+        let tree = info[b"file tree".as_slice()].as_dict().unwrap();
+        let file = tree[b"file".as_slice()].as_dict().unwrap()[b"".as_slice()]
+            .as_dict()
+            .unwrap();
+        assert_eq!(file[b"length".as_slice()].as_integer(), Some(40000));
+        assert_eq!(
+            file[b"pieces root".as_slice()].as_string(),
+            Some(root.as_slice())
+        );
+
+        let layers = upgraded.get_dict(b"piece layers").unwrap();
+        assert_eq!(
+            layers[&root].as_string().unwrap(),
+            unhex("96a0dd5f00f7441893e63bf62c03d8d9449214aaacd9ce3e603b58125984d3e341aba4977da72da403ee3014d5f5b5212e5e56b9632075943c3ae322ff12f3ea")
+        );
+
+        assert_eq!(upgraded.meta_version(), Some(2));
+        assert!(upgraded.is_private());
+        assert!(upgraded.pieces().is_none());
+        assert!(!info.contains_key(b"length".as_slice()));
+    }
+
+    #[test]
+    fn upgrade_to_v2_hybrid_keeps_v1_fields() {
+        let upgraded = torrent(40000, 65536)
+            .upgrade_to_v2(data().as_slice(), true)
+            .unwrap();
+        assert!(upgraded.pieces().is_some());
+        assert_eq!(upgraded.total_length(), Some(40000));
+        assert!(upgraded.get_dict(b"piece layers").is_none());
+        assert_eq!(upgraded.file_tree().unwrap()[0].length, 40000);
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn upgrade_to_v2_sorts_new_keys() {
+        let upgraded = torrent(40000, 32768)
+            .upgrade_to_v2(data().as_slice(), true)
+            .unwrap();
+        let encoded = upgraded.encode();
+
+        assert_eq!(encoded, upgraded.canonicalize());
+        assert!(Bencoding::decode(&encoded)
+            .unwrap()
+            .assert_canonical()
+            .is_ok());
+    }
+
+    #[test]
+    fn upgrade_to_v2_with_short_data_is_error() {
+        let result = torrent(40000, 32768).upgrade_to_v2(&data()[..100], false);
+        assert!(matches!(result, Err(BencodingError::Io(_))));
+    }
+
+    #[test]
+    fn upgrade_to_v2_rejects_small_piece_length() {
+        let result = torrent(100, 8192).upgrade_to_v2(&data()[..100], false);
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidPieceLength {
+                piece_length: 8192,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn create_v2_has_only_v2_fields() {
+        let data = data();
+        let files = vec![
+            (
+                vec![b"dir".to_vec(), b"big".to_vec()],
+                40000,
+                data.as_slice(),
+            ),
+            (vec![b"small".to_vec()], 100, &data[..100]),
+            (vec![b"dir".to_vec(), b"empty".to_vec()], 0, &[][..]),
+        ];
+        let created = Bencoding::create_v2(b"content", 32768, files).unwrap();
+        let root = unhex("64824cf81b2ac8c4eafa2be687b8741af3f643acbf230a64e0544b2244842cb2");
+
+        assert_eq!(created.meta_version(), Some(2));
+        assert_eq!(created.name(), Some(b"content".as_slice()));
+        assert_eq!(created.piece_length(), Some(32768));
+        assert!(created.pieces().is_none());
+        assert!(created.files().is_none());
+        let lengths: Vec<(Vec<Vec<u8>>, i64)> = created
+            .file_tree()
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.path, file.length))
+            .collect();
+        assert_eq!(
+            lengths,
+            vec![
+                (vec![b"dir".to_vec(), b"big".to_vec()], 40000),
+                (vec![b"dir".to_vec(), b"empty".to_vec()], 0),
+                (vec![b"small".to_vec()], 100),
+            ]
+        );
+
+        let layers = created.get_dict(b"piece layers").unwrap();
+        assert_eq!(layers.len(), 1);
+        let upgraded = torrent(40000, 32768)
+            .upgrade_to_v2(data.as_slice(), false)
+            .unwrap();
+        assert_eq!(
+            layers[&root].as_string(),
+            upgraded.get_dict(b"piece layers").unwrap()[&root].as_string()
+        );
+
+        let reparsed = Bencoding::decode(&created.encode()).unwrap();
+        assert!(reparsed.assert_canonical().is_ok());
+        assert_eq!(reparsed.meta_version(), Some(2));
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn create_v2_sorts_file_tree() {
+        let data = data();
+        let files = vec![
+            (vec![b"b".to_vec()], 100, &data[..100]),
+            (vec![b"a".to_vec(), b"y".to_vec()], 10, &data[..10]),
+            (vec![b"a".to_vec(), b"x".to_vec()], 10, &data[..10]),
+        ];
+        let created = Bencoding::create_v2(b"c", 16384, files).unwrap();
+        let encoded = created.encode();
+
+        assert_eq!(encoded, created.canonicalize());
+        assert!(Bencoding::decode(&encoded)
+            .unwrap()
+            .assert_canonical()
+            .is_ok());
+    }
+
+    #[test]
+    fn create_v2_rejects_conflicting_paths() {
+        let data = data();
+        let result = Bencoding::create_v2(
+            b"content",
+            16384,
+            vec![
+                (vec![b"a".to_vec()], 1, data.as_slice()),
+                (vec![b"a".to_vec(), b"b".to_vec()], 1, data.as_slice()),
+            ],
+        );
+        assert!(matches!(&result, Err(BencodingError::DuplicateFilePath(path)) if path == "a/b"));
+
+        let result = Bencoding::create_v2(
+            b"content",
+            16384,
+            vec![
+                (vec![b"a".to_vec(), b"b".to_vec()], 1, data.as_slice()),
+                (vec![b"a".to_vec()], 1, data.as_slice()),
+            ],
+        );
+        assert!(matches!(&result, Err(BencodingError::DuplicateFilePath(path)) if path == "a"));
+
+        let result = Bencoding::create_v2(b"c", 16384, vec![(Vec::new(), 1, data.as_slice())]);
+        assert!(matches!(result, Err(BencodingError::Unsupported(_))));
+    }
+
+    #[test]
+    fn create_v2_rejects_small_piece_length() {
+        let result = Bencoding::create_v2(b"c", 8192, Vec::<(Vec<Vec<u8>>, u64, &[u8])>::new());
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidPieceLength {
+                piece_length: 8192,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn upgrade_to_v2_rejects_multi_file_torrents() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl1:aeee4:name1:d12:piece lengthi16384eee",
+        )
+        .unwrap();
+        let result = parser.upgrade_to_v2(b"x".as_slice(), false);
+        assert!(matches!(result, Err(BencodingError::Unsupported(_))));
+    }
+}
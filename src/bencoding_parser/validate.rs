@@ -0,0 +1,182 @@
+use super::{escape_bytes, Bencoding, BencodingError};
+
+/// How serious a problem found by `Bencoding::validate_all` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The torrent can be used, but something about it is off, such as a
+    /// non-canonical encoding that other tools may hash differently.
+    Warning,
+    /// The torrent is broken: clients would reject it, fail to verify its
+    /// data, or write files outside the download directory.
+    Error,
+}
+
+/// One problem found by `Bencoding::validate_all`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// The check that found the problem: `"well-formedness"`,
+    /// `"required fields"`, `"piece length"`, `"piece count"` or
+    /// `"path safety"`.
+    pub check: &'static str,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// The result of `Bencoding::validate_all`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Every problem found, in the order the checks ran.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no check found an error. Warnings are allowed.
+    pub fn is_valid(&self) -> bool {
+        return self.errors().next().is_none();
+    }
+
+    /// The problems of `Error` severity.
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        return self
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error);
+    }
+
+    /// The problems of `Warning` severity.
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        return self
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning);
+    }
+
+    fn push(&mut self, severity: Severity, check: &'static str, error: BencodingError) {
+        self.issues.push(ValidationIssue {
+            severity,
+            check,
+            message: error.to_string(),
+        });
+    }
+}
+
+impl Bencoding {
+    /// Runs every torrent check and reports all the problems found, rather
+    /// than stopping at the first one like the individual validators.
+    ///
+    /// In order, these are:
+    /// - well-formedness: `assert_canonical`, as a warning;
+    /// - required fields: `validate_info`;
+    /// - piece length: `validate_piece_length`;
+    /// - piece count: `validate_piece_count`;
+    /// - path safety: every file of `content_files` must be accepted by
+    ///   `TorrentFile::normalized_path`, and `files_checked` must find no
+    ///   duplicate paths.
+    ///
+    /// A field missing for one of the later checks is left to the required
+    /// fields check, so that it's reported once.
+    pub fn validate_all(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        if let Err(error) = self.assert_canonical() {
+            report.push(Severity::Warning, "well-formedness", error);
+        }
+        if let Err(error) = self.validate_info() {
+            report.push(Severity::Error, "required fields", error);
+        }
+        match self.validate_piece_length() {
+            Ok(()) | Err(BencodingError::MissingField(_)) => {}
+            Err(error) => report.push(Severity::Error, "piece length", error),
+        }
+        match self.validate_piece_count() {
+            Ok(()) | Err(BencodingError::MissingField(_)) => {}
+            Err(error) => report.push(Severity::Error, "piece count", error),
+        }
+
+        for file in self.content_files().unwrap_or_default() {
+            if file.normalized_path().is_none() {
+                let path: Vec<String> = file
+                    .path
+                    .iter()
+                    .map(|component| escape_bytes(component))
+                    .collect();
+                report.issues.push(ValidationIssue {
+                    severity: Severity::Error,
+                    check: "path safety",
+                    message: format!("unsafe file path '{}'", path.join("/")),
+                });
+            }
+        }
+        match self.files_checked() {
+            Ok(_) | Err(BencodingError::MissingField(_)) => {}
+            Err(error) => report.push(Severity::Error, "path safety", error),
+        }
+
+        return report;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencoding_parser::{Bencoding, Severity};
+
+    #[test]
+    fn validate_all_of_a_good_torrent_is_clean() {
+        let parser = Bencoding::decode(include_bytes!("../../testdata/v1_multi.torrent")).unwrap();
+        let report = parser.validate_all();
+
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn validate_all_collects_every_problem() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi10e4:pathl2:..6:passwdeed6:lengthi5e4:pathl1:aeed6:lengthi5e4:pathl1:aeee4:name1:x12:piece lengthi3e6:pieces20:xxxxxxxxxxxxxxxxxxxxee",
+        )
+        .unwrap();
+        let report = parser.validate_all();
+        let checks: Vec<(Severity, &str)> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.severity, issue.check))
+            .collect();
+
+        assert_eq!(
+            checks,
+            vec![
+                (Severity::Error, "piece length"),
+                (Severity::Error, "piece count"),
+                (Severity::Error, "path safety"),
+                (Severity::Error, "path safety"),
+            ]
+        );
+        assert_eq!(report.issues[2].message, "unsafe file path '../passwd'");
+        assert_eq!(report.issues[3].message, "duplicate file path 'a'");
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 4);
+    }
+
+    #[test]
+    fn validate_all_reports_missing_fields_once() {
+        let parser = Bencoding::decode(b"d4:infod4:name1:xee").unwrap();
+        let report = parser.validate_all();
+
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].check, "required fields");
+    }
+
+    #[test]
+    fn validate_all_warns_about_non_canonical_encoding() {
+        let parser = Bencoding::decode(
+            b"d4:infod6:lengthi1e4:name1:x6:pieces20:xxxxxxxxxxxxxxxxxxxx12:piece lengthi16384eee",
+        )
+        .unwrap();
+        let report = parser.validate_all();
+
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(report.issues[0].check, "well-formedness");
+        assert!(report.is_valid());
+    }
+}
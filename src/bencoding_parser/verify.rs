@@ -0,0 +1,650 @@
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use sha1::{Digest, Sha1};
+
+use super::{Bencoding, BencodingError, BencodingValue};
+
+/// The result of checking a torrent's data against its piece hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Whether each piece matched its hash, by piece index.
+    pub pieces: Vec<bool>,
+}
+
+impl VerifyReport {
+    /// The number of pieces that matched.
+    pub fn valid_count(&self) -> usize {
+        return self.pieces.iter().filter(|&&valid| valid).count();
+    }
+
+    /// Whether every piece matched.
+    pub fn is_complete(&self) -> bool {
+        return self.pieces.iter().all(|&valid| valid);
+    }
+}
+
+/// Checks pieces read from a stream one at a time, as returned by
+/// `Bencoding::verify_stream`.
+pub struct PieceMatches<'a, R: Read> {
+    torrent: &'a Bencoding,
+    reader: R,
+    piece_length: u64,
+    piece_count: usize,
+    total_length: u64,
+    next: usize,
+    piece: Vec<u8>,
+}
+
+impl<R: Read> Iterator for PieceMatches<'_, R> {
+    type Item = Result<(usize, bool), BencodingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.piece_count {
+            return None;
+        }
+        let index = self.next;
+        self.next += 1;
+
+        // A piece starting past what a u64 holds is past the data too, and
+        // so doesn't match.
+        let start = match (index as u64).checked_mul(self.piece_length) {
+            Some(start) => start,
+            None => return Some(Ok((index, false))),
+        };
+        let size = self
+            .total_length
+            .saturating_sub(start)
+            .min(self.piece_length);
+        self.piece.clear();
+        let read = match self.reader.by_ref().take(size).read_to_end(&mut self.piece) {
+            Ok(read) => read,
+            Err(error) => {
+                self.next = self.piece_count;
+                return Some(Err(BencodingError::Io(error)));
+            }
+        };
+
+        let matches = size > 0
+            && read as u64 == size
+            && self.torrent.piece_hash(index) == Some(Sha1::digest(&self.piece).as_slice());
+        return Some(Ok((index, matches)));
+    }
+}
+
+/// Where a file's data lives on disk and in the torrent's data.
+struct DiskFile {
+    path: PathBuf,
+    offset: u64,
+    length: u64,
+    padding: bool,
+}
+
+/// The file layout of a torrent resolved against a download directory.
+struct DiskLayout {
+    files: Vec<DiskFile>,
+    piece_length: u64,
+    piece_count: usize,
+    total_length: u64,
+}
+
+impl Bencoding {
+    /// Checks the piece at `index` against the files under `base_dir`, where
+    /// a single-file torrent is the file `base_dir/name` and a multi-file
+    /// torrent the directory `base_dir/name`.
+    ///
+    /// Missing or short files make the piece invalid rather than an error.
+    /// BEP 47 padding files are taken to be zeros and aren't read. Fails with
+    /// `Err(MissingField)` if the torrent has no valid `pieces`, `piece
+    /// length` or file list (including a file path that isn't safe to join
+    /// onto `base_dir`, see `TorrentFile::normalized_path`), with
+    /// `Err(IntegerOverflow)` if the file lengths add up to more than a
+    /// `u64` holds, and with `Err(Io)` if reading fails otherwise.
+    pub fn verify_piece<P: AsRef<Path>>(
+        &self,
+        base_dir: P,
+        index: usize,
+    ) -> Result<bool, BencodingError> {
+        let layout = self.disk_layout(base_dir.as_ref())?;
+        if index >= layout.piece_count {
+            return Ok(false);
+        }
+
+        return self.check_piece(&layout, index);
+    }
+
+    /// Checks every piece against the files under `base_dir`, as
+    /// `verify_piece` does.
+    pub fn verify_data<P: AsRef<Path>>(&self, base_dir: P) -> Result<VerifyReport, BencodingError> {
+        let layout = self.disk_layout(base_dir.as_ref())?;
+        let pieces = (0..layout.piece_count)
+            .map(|index| self.check_piece(&layout, index))
+            .collect::<Result<Vec<bool>, BencodingError>>()?;
+
+        return Ok(VerifyReport { pieces });
+    }
+
+    /// Like `verify_data`, but hashes pieces on the rayon thread pool. The
+    /// report is in piece order whatever the scheduling, and is the same as
+    /// `verify_data`'s; if reading fails for several pieces, which of the
+    /// errors is returned is not specified.
+    #[cfg(feature = "rayon")]
+    pub fn verify_data_parallel<P: AsRef<Path>>(
+        &self,
+        base_dir: P,
+    ) -> Result<VerifyReport, BencodingError> {
+        let layout = self.disk_layout(base_dir.as_ref())?;
+        let pieces = (0..layout.piece_count)
+            .into_par_iter()
+            .map(|index| self.check_piece(&layout, index))
+            .collect::<Result<Vec<bool>, BencodingError>>()?;
+
+        return Ok(VerifyReport { pieces });
+    }
+
+    /// Checks the pieces of the torrent's data read from `reader`, such as
+    /// the file of a single-file torrent, holding one piece in memory at a
+    /// time.
+    ///
+    /// The iterator yields `(piece index, matches)` for every piece in order,
+    /// reading each as it's asked for. Pieces the stream ends before are
+    /// reported as not matching. A read error is yielded as `Some(Err(Io))`
+    /// and ends the iteration. Fails up front with `Err(MissingField)` if
+    /// the torrent has no valid `pieces`, `piece length` or file list.
+    pub fn verify_stream<R: Read>(&self, reader: R) -> Result<PieceMatches<'_, R>, BencodingError> {
+        let piece_count = self
+            .piece_count()
+            .ok_or(BencodingError::MissingField("pieces"))?;
+        let piece_length = self
+            .piece_length()
+            .ok_or(BencodingError::MissingField("piece length"))?;
+        let total_length = self
+            .total_length()
+            .ok_or(BencodingError::MissingField("files"))?;
+
+        return Ok(PieceMatches {
+            torrent: self,
+            reader,
+            piece_length: piece_length as u64,
+            piece_count,
+            total_length: total_length as u64,
+            next: 0,
+            // Grown by the reads, up to what the stream actually holds, as
+            // the torrent's piece length can't be trusted for a reservation.
+            piece: Vec::new(),
+        });
+    }
+
+    /// A copy of the `info` dictionary with the given `piece length` and its
+    /// `pieces` recomputed from the files under `base_dir`, which are laid out
+    /// as for `verify_piece` and read in file order, across file boundaries.
+    ///
+    /// Padding files are hashed as zeros and kept as they are, so they no
+    /// longer align the following files to piece boundaries unless the new
+    /// piece length is a divisor of the old one. The new piece length must be
+    /// a power of two, or this is `Err(InvalidPieceLength)`. A missing or
+    /// short file is `Err(Io)`, and an invalid file list `Err(MissingField)`.
+    pub fn repiece<P: AsRef<Path>>(
+        &self,
+        base_dir: P,
+        piece_length: i64,
+    ) -> Result<BencodingValue, BencodingError> {
+        if piece_length <= 0 || piece_length & (piece_length - 1) != 0 {
+            return Err(BencodingError::InvalidPieceLength {
+                piece_length,
+                reason: "not a power of two",
+            });
+        }
+        let mut info = self
+            .info_dict()
+            .ok_or(BencodingError::MissingField("info"))?
+            .clone();
+
+        let mut hasher = PieceHasher::new(piece_length as usize);
+        for file in self.disk_files(base_dir.as_ref())? {
+            if file.padding {
+                hasher.feed_zeros(file.length);
+            } else {
+                let reader = File::open(&file.path).map_err(BencodingError::Io)?;
+                hasher.feed_reader(reader, file.length)?;
+            }
+        }
+
+        info.insert(
+            b"piece length".to_vec(),
+            BencodingValue::Integer(piece_length),
+        );
+        info.insert(b"pieces".to_vec(), BencodingValue::String(hasher.finish()));
+
+        return Ok(BencodingValue::Dict(info));
+    }
+
+    fn disk_layout(&self, base_dir: &Path) -> Result<DiskLayout, BencodingError> {
+        let piece_count = self
+            .piece_count()
+            .ok_or(BencodingError::MissingField("pieces"))?;
+        let piece_length = self
+            .piece_length()
+            .ok_or(BencodingError::MissingField("piece length"))?;
+        let files = self.disk_files(base_dir)?;
+        let total_length = files.last().map_or(0, |file| file.offset + file.length);
+
+        return Ok(DiskLayout {
+            files,
+            piece_length: piece_length as u64,
+            piece_count,
+            total_length,
+        });
+    }
+
+    fn disk_files(&self, base_dir: &Path) -> Result<Vec<DiskFile>, BencodingError> {
+        let files = self
+            .normalized_files()
+            .ok_or(BencodingError::MissingField("files"))?;
+
+        let multi_file = self
+            .info_dict()
+            .is_some_and(|info| info.contains_key(b"files".as_slice()));
+        let root = match multi_file {
+            true => {
+                let name = self.name().ok_or(BencodingError::MissingField("name"))?;
+                base_dir.join(path_segment(name))
+            }
+            false => base_dir.to_path_buf(),
+        };
+
+        let mut disk_files = Vec::new();
+        let mut offset: u64 = 0;
+        for file in files {
+            let mut path = root.clone();
+            for segment in &file.path {
+                path.push(path_segment(segment));
+            }
+            disk_files.push(DiskFile {
+                path,
+                offset,
+                length: file.length as u64,
+                padding: file.padding,
+            });
+            offset = offset
+                .checked_add(file.length as u64)
+                .ok_or(BencodingError::IntegerOverflow)?;
+        }
+
+        return Ok(disk_files);
+    }
+
+    fn check_piece(&self, layout: &DiskLayout, index: usize) -> Result<bool, BencodingError> {
+        let expected = match self.piece_hash(index) {
+            Some(hash) => hash,
+            None => return Ok(false),
+        };
+        // A piece starting past what a u64 holds is past the data too.
+        let start = match (index as u64).checked_mul(layout.piece_length) {
+            Some(start) if start < layout.total_length => start,
+            _ => return Ok(false),
+        };
+        let end = start
+            .checked_add(layout.piece_length)
+            .map_or(layout.total_length, |end| end.min(layout.total_length));
+
+        // The piece is hashed as it's read rather than buffered, as its
+        // length comes from the torrent and may be far beyond what the data
+        // on disk holds.
+        let mut hasher = Sha1::new();
+        let first = layout
+            .files
+            .partition_point(|file| file.offset + file.length <= start);
+        for file in &layout.files[first..] {
+            if file.offset >= end {
+                break;
+            }
+            let from = start.max(file.offset) - file.offset;
+            let to = end.min(file.offset + file.length) - file.offset;
+            if file.padding {
+                std::io::copy(&mut std::io::repeat(0).take(to - from), &mut hasher)
+                    .map_err(BencodingError::Io)?;
+            } else if !hash_range(&file.path, from, to, &mut hasher)? {
+                return Ok(false);
+            }
+        }
+
+        return Ok(hasher.finalize().as_slice() == expected);
+    }
+}
+
+/// Hashes a stream of data in pieces of a fixed length.
+struct PieceHasher {
+    piece_length: usize,
+    piece: Vec<u8>,
+    pieces: Vec<u8>,
+}
+
+impl PieceHasher {
+    fn new(piece_length: usize) -> Self {
+        return Self {
+            piece_length,
+            piece: Vec::with_capacity(piece_length),
+            pieces: Vec::new(),
+        };
+    }
+
+    fn feed_reader<R: Read>(&mut self, mut reader: R, length: u64) -> Result<(), BencodingError> {
+        let mut remaining = length;
+        while remaining > 0 {
+            let wanted = remaining.min((self.piece_length - self.piece.len()) as u64);
+            let read = reader
+                .by_ref()
+                .take(wanted)
+                .read_to_end(&mut self.piece)
+                .map_err(BencodingError::Io)?;
+            if read == 0 {
+                return Err(BencodingError::Io(ErrorKind::UnexpectedEof.into()));
+            }
+            remaining -= read as u64;
+            self.hash_full_piece();
+        }
+
+        return Ok(());
+    }
+
+    fn feed_zeros(&mut self, length: u64) {
+        let mut remaining = length;
+        while remaining > 0 {
+            let zeros = remaining.min((self.piece_length - self.piece.len()) as u64);
+            self.piece.resize(self.piece.len() + zeros as usize, 0);
+            remaining -= zeros;
+            self.hash_full_piece();
+        }
+    }
+
+    fn hash_full_piece(&mut self) {
+        if self.piece.len() == self.piece_length {
+            self.pieces.extend_from_slice(&Sha1::digest(&self.piece));
+            self.piece.clear();
+        }
+    }
+
+    /// The concatenated hashes, including that of a final partial piece.
+    fn finish(mut self) -> Vec<u8> {
+        if !self.piece.is_empty() {
+            self.pieces.extend_from_slice(&Sha1::digest(&self.piece));
+        }
+
+        return self.pieces;
+    }
+}
+
+/// Feeds bytes `from..to` of the file at `path` to `hasher`. Returns `false`
+/// if the file is missing or too short.
+fn hash_range(path: &Path, from: u64, to: u64, hasher: &mut Sha1) -> Result<bool, BencodingError> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(BencodingError::Io(error)),
+    };
+    // Checked up front, as seeking to an offset the torrent declares can
+    // fail outright when it's beyond what the platform supports.
+    if file.metadata().map_err(BencodingError::Io)?.len() < to {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::Start(from))
+        .map_err(BencodingError::Io)?;
+
+    let read = std::io::copy(&mut file.take(to - from), hasher).map_err(BencodingError::Io)?;
+
+    return Ok(read == to - from);
+}
+
+fn path_segment(segment: &[u8]) -> String {
+    return String::from_utf8_lossy(segment).into_owned();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use sha1::{Digest, Sha1};
+
+    use crate::bencoding_parser::{Bencoding, BencodingError};
+
+    const DATA: &[u8] = b"hello, world";
+
+    /// A torrent of `DATA` split into files `a` (5 bytes) and `b` (7 bytes)
+    /// with 4-byte pieces.
+    fn torrent() -> Bencoding {
+        let mut pieces = Vec::new();
+        for chunk in DATA.chunks(4) {
+            pieces.extend_from_slice(&Sha1::digest(chunk));
+        }
+        let mut data = b"d4:infod5:filesld6:lengthi5e4:pathl1:aeed6:lengthi7e4:pathl1:beee4:name4:data12:piece lengthi4e6:pieces60:".to_vec();
+        data.extend_from_slice(&pieces);
+        data.extend_from_slice(b"ee");
+
+        return Bencoding::decode(&data).unwrap();
+    }
+
+    fn download_dir(test: &str, b: &[u8]) -> PathBuf {
+        let base =
+            std::env::temp_dir().join(format!("bencoding_parser_{}_{}", std::process::id(), test));
+        let root = base.join("data");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a"), &DATA[..5]).unwrap();
+        fs::write(root.join("b"), b).unwrap();
+
+        return base;
+    }
+
+    #[test]
+    fn verify_data_of_complete_download() {
+        let base = download_dir("complete", &DATA[5..]);
+        let report = torrent().verify_data(&base).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(report.pieces, vec![true, true, true]);
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn verify_data_with_corrupt_and_short_file() {
+        let base = download_dir("corrupt", b"XXrld");
+        let report = torrent().verify_data(&base).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(report.pieces, vec![true, false, false]);
+        assert_eq!(report.valid_count(), 1);
+    }
+
+    #[test]
+    fn verify_piece_with_missing_file() {
+        let base = download_dir("missing", &DATA[5..]);
+        fs::remove_file(base.join("data").join("b")).unwrap();
+        let parser = torrent();
+        let first = parser.verify_piece(&base, 0).unwrap();
+        let second = parser.verify_piece(&base, 1).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(first);
+        assert!(!second);
+    }
+
+    /// A single-file torrent named `big` declaring one piece of 2^62 bytes.
+    fn huge_torrent() -> Bencoding {
+        let mut data = format!(
+            "d4:infod6:lengthi{0}e4:name3:big12:piece lengthi{0}e6:pieces20:",
+            1u64 << 62
+        )
+        .into_bytes();
+        data.extend_from_slice(&[0; 20]);
+        data.extend_from_slice(b"ee");
+
+        return Bencoding::decode(&data).unwrap();
+    }
+
+    #[test]
+    fn verify_piece_of_huge_declared_piece_does_not_reserve_it() {
+        let base = download_dir("huge", b"");
+        fs::write(base.join("big"), DATA).unwrap();
+        let parser = huge_torrent();
+        let piece = parser.verify_piece(&base, 0).unwrap();
+        let report = parser.verify_data(&base).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(!piece);
+        assert_eq!(report.pieces, vec![false]);
+    }
+
+    /// A single-file torrent named `big` of `i64::MAX` bytes in 5 pieces of
+    /// 2^62 bytes, so the later pieces start past what a `u64` holds.
+    fn overflowing_torrent() -> Bencoding {
+        let mut data = format!(
+            "d4:infod6:lengthi{}e4:name3:big12:piece lengthi{}e6:pieces100:",
+            i64::MAX,
+            1u64 << 62
+        )
+        .into_bytes();
+        data.extend_from_slice(&[0; 100]);
+        data.extend_from_slice(b"ee");
+
+        return Bencoding::decode(&data).unwrap();
+    }
+
+    #[test]
+    fn verify_data_with_overflowing_piece_offsets() {
+        let base = download_dir("overflow", b"");
+        fs::write(base.join("big"), DATA).unwrap();
+        let parser = overflowing_torrent();
+        let last = parser.verify_piece(&base, 4).unwrap();
+        let report = parser.verify_data(&base).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(!last);
+        assert_eq!(report.pieces, vec![false; 5]);
+    }
+
+    #[test]
+    fn verify_data_with_overflowing_file_offsets() {
+        let data = format!(
+            "d4:infod5:filesld6:lengthi{0}e4:pathl1:aeed6:lengthi{0}e4:pathl1:beed6:lengthi{0}e4:pathl1:ceee4:name4:data12:piece lengthi4e6:pieces20:{1}ee",
+            i64::MAX,
+            "x".repeat(20)
+        );
+        let parser = Bencoding::decode(data.as_bytes()).unwrap();
+        let result = parser.verify_data(std::env::temp_dir());
+
+        assert!(matches!(result, Err(BencodingError::IntegerOverflow)));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn verify_data_parallel_matches_sequential() {
+        let base = download_dir("parallel", b"XXrld");
+        let parser = torrent();
+        let sequential = parser.verify_data(&base).unwrap();
+        let parallel = parser.verify_data_parallel(&base).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn verify_stream_checks_each_piece() {
+        let matches: Vec<(usize, bool)> = torrent()
+            .verify_stream(b"hello, worlX".as_slice())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(matches, vec![(0, true), (1, true), (2, false)]);
+    }
+
+    #[test]
+    fn verify_stream_of_huge_declared_piece_does_not_reserve_it() {
+        let parser = huge_torrent();
+        let results: Vec<(usize, bool)> = parser
+            .verify_stream(DATA)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(results, vec![(0, false)]);
+    }
+
+    #[test]
+    fn verify_stream_with_overflowing_piece_offsets() {
+        let parser = overflowing_torrent();
+        let results: Vec<(usize, bool)> = parser
+            .verify_stream(std::io::empty())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        assert_eq!(
+            results,
+            (0..5).map(|index| (index, false)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn verify_stream_of_truncated_data() {
+        let matches: Vec<(usize, bool)> = torrent()
+            .verify_stream(&DATA[..6])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(matches, vec![(0, true), (1, false), (2, false)]);
+    }
+
+    #[test]
+    fn repiece_recomputes_pieces_across_files() {
+        let base = download_dir("repiece", &DATA[5..]);
+        let info = torrent().repiece(&base, 8).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        let info = info.as_dict().unwrap();
+        let mut expected = Sha1::digest(&DATA[..8]).to_vec();
+        expected.extend_from_slice(&Sha1::digest(&DATA[8..]));
+        assert_eq!(info[b"piece length".as_slice()].as_integer(), Some(8));
+        assert_eq!(
+            info[b"pieces".as_slice()].as_string(),
+            Some(expected.as_slice())
+        );
+    }
+
+    #[test]
+    fn repiece_same_piece_length_reproduces_pieces() {
+        let base = download_dir("repiece_same", &DATA[5..]);
+        let parser = torrent();
+        let info = parser.repiece(&base, 4).unwrap();
+        fs::remove_dir_all(&base).unwrap();
+
+        assert_eq!(
+            info.as_dict().unwrap()[b"pieces".as_slice()].as_string(),
+            parser.pieces()
+        );
+    }
+
+    #[test]
+    fn repiece_with_short_file_is_error() {
+        let base = download_dir("repiece_short", b"XX");
+        let result = torrent().repiece(&base, 4);
+        fs::remove_dir_all(&base).unwrap();
+
+        assert!(matches!(result, Err(BencodingError::Io(_))));
+    }
+
+    #[test]
+    fn repiece_rejects_invalid_piece_length() {
+        let result = torrent().repiece(std::env::temp_dir(), 3);
+        assert!(matches!(
+            result,
+            Err(BencodingError::InvalidPieceLength {
+                piece_length: 3,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use super::{Bencoding, BencodingType, BencodingValue};
+
+/// One step of the path leading to a value inside a decoded structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+    /// The value under this key of a dictionary.
+    Key(&'a [u8]),
+    /// The element at this index of a list.
+    Index(usize),
+}
+
+impl Bencoding {
+    /// Visits every value in the structure, parents before their children,
+    /// along with the path from the top-level dictionary to it.
+    ///
+    /// Dictionary entries are visited in sorted key order, so the visiting
+    /// order is the same as the order of the canonical encoding.
+    pub fn walk<'a, F: FnMut(&[PathSegment<'a>], &'a BencodingValue)>(&'a self, mut visit: F) {
+        let mut path = Vec::new();
+        let mut keys: Vec<&Vec<u8>> = self.dict.keys().collect();
+        keys.sort();
+
+        for key in keys {
+            path.push(PathSegment::Key(key));
+            walk_value(&self.dict[key], &mut path, &mut visit);
+            path.pop();
+        }
+    }
+
+    /// The number of values of each type in the structure, counting the
+    /// top-level dictionary itself, to characterize a torrent or spot
+    /// anomalies. Types that don't occur are absent from the map.
+    pub fn type_histogram(&self) -> HashMap<BencodingType, usize> {
+        let mut histogram = HashMap::from([(BencodingType::Dict, 1)]);
+        self.walk(|_, value| *histogram.entry(value.value_type()).or_insert(0) += 1);
+
+        return histogram;
+    }
+
+    /// Finds every string value matching `pred`, along with its key path.
+    ///
+    /// The path holds the dictionary keys leading to the string. List indices
+    /// are not part of it: a string inside a list is reported under the key
+    /// path of the list, so all file path components of a multi-file torrent
+    /// have the path `["info", "files", "path"]`. Use `walk` to tell list
+    /// elements apart.
+    pub fn find_strings<F: Fn(&[u8]) -> bool>(&self, pred: F) -> Vec<(Vec<&[u8]>, &[u8])> {
+        let mut found = Vec::new();
+        self.walk(|path, value| {
+            if let BencodingValue::String(s) = value {
+                if pred(s) {
+                    let keys = path
+                        .iter()
+                        .filter_map(|segment| match segment {
+                            PathSegment::Key(key) => Some(*key),
+                            PathSegment::Index(_) => None,
+                        })
+                        .collect();
+                    found.push((keys, s.as_slice()));
+                }
+            }
+        });
+
+        return found;
+    }
+}
+
+fn walk_value<'a, F: FnMut(&[PathSegment<'a>], &'a BencodingValue)>(
+    value: &'a BencodingValue,
+    path: &mut Vec<PathSegment<'a>>,
+    visit: &mut F,
+) {
+    visit(path, value);
+
+    match value {
+        BencodingValue::List(list) => {
+            for (i, element) in list.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                walk_value(element, path, visit);
+                path.pop();
+            }
+        }
+        BencodingValue::Dict(dict) => {
+            let mut keys: Vec<&Vec<u8>> = dict.keys().collect();
+            keys.sort();
+            for key in keys {
+                path.push(PathSegment::Key(key));
+                walk_value(&dict[key], path, visit);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::bencoding_parser::{Bencoding, BencodingType, PathSegment};
+
+    #[test]
+    fn walk_visits_parents_before_children_in_key_order() {
+        let parser = Bencoding::decode(b"d1:bli1ee1:ad1:ci2eee").unwrap();
+        let mut paths = Vec::new();
+        parser.walk(|path, _| paths.push(path.to_vec()));
+
+        assert_eq!(
+            paths,
+            vec![
+                vec![PathSegment::Key(b"a")],
+                vec![PathSegment::Key(b"a"), PathSegment::Key(b"c")],
+                vec![PathSegment::Key(b"b")],
+                vec![PathSegment::Key(b"b"), PathSegment::Index(0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn find_strings_in_file_paths() {
+        let parser = Bencoding::decode(
+            b"d4:infod5:filesld6:lengthi1e4:pathl4:docs9:notes.txteed6:lengthi2e4:pathl8:song.mp3eee4:name8:bundle.dee",
+        )
+        .unwrap();
+        let found = parser.find_strings(|s| s.ends_with(b".txt") || s.ends_with(b".mp3"));
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    vec![b"info".as_slice(), b"files", b"path"],
+                    b"notes.txt".as_slice()
+                ),
+                (
+                    vec![b"info".as_slice(), b"files", b"path"],
+                    b"song.mp3".as_slice()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_strings_without_matches_is_empty() {
+        let parser = Bencoding::decode(b"d3:key5:valuee").unwrap();
+        assert!(parser.find_strings(|s| s == b"other").is_empty());
+    }
+
+    #[test]
+    fn type_histogram_counts_every_value() {
+        let parser = Bencoding::decode(b"d1:ali1ei2e1:xe1:dd1:bi3eee").unwrap();
+        assert_eq!(
+            parser.type_histogram(),
+            HashMap::from([
+                (BencodingType::Dict, 2),
+                (BencodingType::List, 1),
+                (BencodingType::Integer, 3),
+                (BencodingType::String, 1),
+            ])
+        );
+        assert_eq!(
+            Bencoding::decode(b"de").unwrap().type_histogram(),
+            HashMap::from([(BencodingType::Dict, 1)])
+        );
+    }
+}
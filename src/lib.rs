@@ -1,135 +1,454 @@
+#![allow(clippy::needless_return)]
+
 pub mod bencoding_parser {
-    use std::collections::HashMap;
+    mod compact;
+    mod content;
+    mod decode;
+    mod dump;
+    mod encode;
+    mod extension;
+    mod factory;
+    mod getters;
+    #[cfg(feature = "flate2")]
+    mod gzip;
+    mod hash;
+    #[cfg(all(feature = "sha1", feature = "sha2"))]
+    mod hybrid;
+    mod krpc;
+    mod lazy;
+    #[cfg(feature = "sha1")]
+    mod magnet;
+    mod pieces;
+    mod read;
+    mod redact;
+    mod resume;
+    #[cfg(feature = "serde")]
+    mod ser;
+    mod signature;
+    mod summary;
+    mod suspicious;
+    mod torrent;
+    mod tracker;
+    #[cfg(feature = "sha2")]
+    mod v2;
+    mod validate;
+    #[cfg(feature = "sha1")]
+    mod verify;
+    mod walk;
+
+    pub use content::{ContentDifference, InfoDiff};
+    use decode::Decoder;
+    pub use decode::{count_tokens, DecodeOptions, TokenCounts};
+    pub use dump::escape_bytes;
+    pub use extension::ExtensionHandshake;
+    pub use factory::{decode_with_factory, ValueFactory};
+    pub use getters::{integers_borrowed, BencodingType};
+    #[cfg(all(feature = "sha1", feature = "sha2"))]
+    pub use hybrid::HybridReport;
+    pub use krpc::KrpcMessage;
+    pub use lazy::LazyBencoding;
+    #[cfg(feature = "sha1")]
+    pub use magnet::MagnetLink;
+    pub use pieces::{pieces_to_bitfield, suggest_piece_length};
+    pub use resume::ResumeData;
+    pub use signature::SignatureEntry;
+    pub use suspicious::{Suspicion, SuspiciousField};
+    pub use torrent::{
+        CreatedBy, FileEntry, LegacyFileHashes, PeerSourcePolicy, TorrentFile, TorrentMetadata,
+    };
+    pub use tracker::ScrapeEntry;
+    #[cfg(feature = "sha2")]
+    pub use v2::{merkle_root, piece_proof, verify_v2_piece};
+    pub use validate::{Severity, ValidationIssue, ValidationReport};
+    #[cfg(feature = "sha1")]
+    pub use verify::{PieceMatches, VerifyReport};
+    pub use walk::PathSegment;
 
     #[derive(Debug)]
-    pub enum BencodingError {}
+    pub enum BencodingError {
+        /// The input ended in the middle of a value.
+        UnexpectedEof,
+        /// A byte that can't start a value, or a missing delimiter.
+        UnexpectedByte { position: usize, byte: u8 },
+        /// An integer whose digits don't parse as an `i64`.
+        InvalidInteger { position: usize },
+        /// A string length prefix that doesn't parse as a `usize`.
+        InvalidStringLength { position: usize },
+        /// The source deviates from the canonical encoding at `position`.
+        NonCanonical {
+            position: usize,
+            reason: &'static str,
+        },
+        /// A compact address string whose length is not a multiple of the
+        /// entry size.
+        InvalidCompactLength { length: usize, entry_size: usize },
+        /// The value under `key` has type `found` instead of `expected`.
+        UnexpectedType {
+            key: String,
+            expected: &'static str,
+            found: &'static str,
+        },
+        /// The integer `value` under `key` is outside `min..=max`.
+        OutOfRange {
+            key: String,
+            value: i64,
+            min: i64,
+            max: i64,
+        },
+        /// Two files of the torrent have this path.
+        DuplicateFilePath(String),
+        /// A required field is absent or doesn't have the expected type.
+        MissingField(&'static str),
+        /// `info.piece length` is not valid for the torrent.
+        InvalidPieceLength {
+            piece_length: i64,
+            reason: &'static str,
+        },
+        /// The output buffer can't hold the `needed` bytes of the encoding.
+        BufferTooSmall { needed: usize, available: usize },
+        /// The `pieces` string doesn't hold exactly one hash per piece of the
+        /// torrent's data.
+        InconsistentPieces {
+            pieces_len: usize,
+            expected_pieces: usize,
+        },
+        /// The operation doesn't support this kind of input.
+        Unsupported(&'static str),
+        /// A sum of integers from the input doesn't fit an `i64`.
+        IntegerOverflow,
+        /// Decoding took more than `DecodeOptions::max_operations`.
+        BudgetExceeded { limit: usize },
+        /// Reading from the underlying stream failed.
+        Io(std::io::Error),
+        /// Compressed input failed to decompress.
+        Decompression(std::io::Error),
+        /// A value's `Serialize` implementation reported an error.
+        Serialize(String),
+        /// A magnet link that can't be parsed, for this reason.
+        InvalidMagnetLink(&'static str),
+        /// A dictionary key at `position` is the empty string, rejected with
+        /// `DecodeOptions::reject_empty_keys`.
+        EmptyKey { position: usize },
+    }
+
+    impl std::fmt::Display for BencodingError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                BencodingError::UnexpectedEof => write!(f, "unexpected end of input"),
+                BencodingError::UnexpectedByte { position, byte } => {
+                    write!(f, "unexpected byte 0x{:02x} at offset {}", byte, position)
+                }
+                BencodingError::InvalidInteger { position } => {
+                    write!(f, "invalid integer at offset {}", position)
+                }
+                BencodingError::InvalidStringLength { position } => {
+                    write!(f, "invalid string length at offset {}", position)
+                }
+                BencodingError::NonCanonical { position, reason } => {
+                    write!(
+                        f,
+                        "non-canonical encoding at offset {}: {}",
+                        position, reason
+                    )
+                }
+                BencodingError::InvalidCompactLength { length, entry_size } => write!(
+                    f,
+                    "compact string of {} bytes is not a multiple of {}",
+                    length, entry_size
+                ),
+                BencodingError::UnexpectedType {
+                    key,
+                    expected,
+                    found,
+                } => write!(
+                    f,
+                    "expected {} for key '{}', found {}",
+                    expected, key, found
+                ),
+                BencodingError::OutOfRange {
+                    key,
+                    value,
+                    min,
+                    max,
+                } => write!(
+                    f,
+                    "value {} for key '{}' is outside {}..={}",
+                    value, key, min, max
+                ),
+                BencodingError::DuplicateFilePath(path) => {
+                    write!(f, "duplicate file path '{}'", path)
+                }
+                BencodingError::MissingField(field) => write!(f, "missing field '{}'", field),
+                BencodingError::InvalidPieceLength {
+                    piece_length,
+                    reason,
+                } => write!(f, "invalid piece length {}: {}", piece_length, reason),
+                BencodingError::BufferTooSmall { needed, available } => write!(
+                    f,
+                    "buffer of {} bytes is too small, {} needed",
+                    available, needed
+                ),
+                BencodingError::InconsistentPieces {
+                    pieces_len,
+                    expected_pieces,
+                } => write!(
+                    f,
+                    "pieces string of {} bytes doesn't hold the {} hashes of the data",
+                    pieces_len, expected_pieces
+                ),
+                BencodingError::Unsupported(what) => write!(f, "unsupported: {}", what),
+                BencodingError::IntegerOverflow => write!(f, "integer overflow"),
+                BencodingError::BudgetExceeded { limit } => {
+                    write!(f, "decoding exceeded the budget of {} operations", limit)
+                }
+                BencodingError::Io(error) => write!(f, "{}", error),
+                BencodingError::Decompression(error) => {
+                    write!(f, "decompression failed: {}", error)
+                }
+                BencodingError::Serialize(message) => {
+                    write!(f, "serialization failed: {}", message)
+                }
+                BencodingError::InvalidMagnetLink(reason) => {
+                    write!(f, "invalid magnet link: {}", reason)
+                }
+                BencodingError::EmptyKey { position } => {
+                    write!(f, "empty dictionary key at offset {}", position)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for BencodingError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                BencodingError::Io(error) | BencodingError::Decompression(error) => {
+                    return Some(error)
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Storage for decoded dictionaries.
+    ///
+    /// A `HashMap` by default. With the `indexmap` feature it's an `IndexMap`
+    /// instead, which keeps the entries in the order they appeared in the
+    /// input, for iteration and `Bencoding::encode_stored_order`. `encode`
+    /// and the info-hash sort the keys either way.
+    #[cfg(not(feature = "indexmap"))]
+    pub type BencodingDict = std::collections::HashMap<Vec<u8>, BencodingValue>;
+    #[cfg(feature = "indexmap")]
+    pub type BencodingDict = indexmap::IndexMap<Vec<u8>, BencodingValue>;
 
     #[derive(Debug, Clone)]
     pub enum BencodingValue {
         String(Vec<u8>),
         Integer(i64),
-        Dict(HashMap<Vec<u8>, BencodingValue>),
+        /// A non-negative integer above `i64::MAX`, only decoded with
+        /// `DecodeOptions::allow_u64_integers`.
+        Unsigned(u64),
+        Dict(BencodingDict),
         List(Vec<BencodingValue>),
     }
 
-    pub struct Bencoding {
-        dict: HashMap<Vec<u8>, BencodingValue>,
-    }
-
-    impl Bencoding {
-        pub fn decode(data: &[u8]) -> Result<Self, BencodingError> {
-            let (dict, _) = Self::decode_dict(data);
-
-            return Ok(Self { dict });
+    impl BencodingValue {
+        pub fn as_string(&self) -> Option<&[u8]> {
+            match self {
+                BencodingValue::String(s) => Some(s),
+                _ => None,
+            }
         }
 
-        pub fn get(&self, key: &[u8]) -> Option<BencodingValue> {
-            if !self.dict.contains_key(key) {
-                return None;
+        pub fn as_integer(&self) -> Option<i64> {
+            match self {
+                BencodingValue::Integer(i) => Some(*i),
+                _ => None,
             }
-
-            return Some(self.dict[key].clone());
         }
 
-        fn decode_dict(mut data: &[u8]) -> (HashMap<Vec<u8>, BencodingValue>, &[u8]) {
-            data = &data[1..];
-            let mut key;
-            let mut value;
-
-            let mut dict = HashMap::new();
-            loop {
-                // 0x65 ('e') indicates end of dictionary
-                if data[0] == 'e' as u8 {
-                    break;
-                }
-
-                (key, data) = Self::decode_string(data);
-                (value, data) = Self::decode_next(data);
-                dict.insert(key, value);
+        /// The integer as a `u64`, whether it's an `Integer` that isn't
+        /// negative or an `Unsigned`.
+        pub fn as_u64(&self) -> Option<u64> {
+            match self {
+                BencodingValue::Integer(i) => u64::try_from(*i).ok(),
+                BencodingValue::Unsigned(u) => Some(*u),
+                _ => None,
             }
-
-            return (dict, data);
         }
 
-        fn decode_string(mut data: &[u8]) -> (Vec<u8>, &[u8]) {
-            let mut separator_idx = 0;
+        pub fn as_list(&self) -> Option<&Vec<BencodingValue>> {
+            match self {
+                BencodingValue::List(l) => Some(l),
+                _ => None,
+            }
+        }
 
-            while data[separator_idx] != ':' as u8 {
-                separator_idx = separator_idx + 1;
+        pub fn as_dict(&self) -> Option<&BencodingDict> {
+            match self {
+                BencodingValue::Dict(d) => Some(d),
+                _ => None,
             }
+        }
+    }
 
-            let length = std::str::from_utf8(&data[..separator_idx])
-                .unwrap()
-                .parse()
-                .unwrap();
-            data = &data[separator_idx + 1..];
-            let value = data[..length].to_vec();
-            data = &data[length..];
+    pub struct Bencoding {
+        dict: BencodingDict,
+        non_canonical: Option<(usize, &'static str)>,
+    }
 
-            return (value, data);
+    impl Bencoding {
+        pub fn decode(data: &[u8]) -> Result<Self, BencodingError> {
+            return Self::decode_with_options(data, &DecodeOptions::default());
         }
 
-        fn decode_integer(mut data: &[u8]) -> (i64, &[u8]) {
-            // TODO: i-0e is invalid. All encodings with a leading zero, such as i03e, are
-            // invalid, other than i0e, which of course corresponds to the integer "0".
-            data = &data[1..];
-            let mut ending_idx = 0;
-            while data[ending_idx] != 'e' as u8 {
-                ending_idx = ending_idx + 1;
+        pub fn decode_with_options(
+            data: &[u8],
+            options: &DecodeOptions,
+        ) -> Result<Self, BencodingError> {
+            if options.validate_lengths_first {
+                let mut decoder = Decoder::new(data, options);
+                decoder.skip_leading_bytes();
+                decoder.skip_next()?;
             }
+            let mut decoder = Decoder::new(data, options);
+            decoder.skip_leading_bytes();
+            let dict = decoder.decode_dict()?;
+            decoder.expect_end()?;
+
+            return Ok(Self {
+                dict,
+                non_canonical: decoder.non_canonical,
+            });
+        }
 
-            let value = std::str::from_utf8(&data[..ending_idx])
-                .unwrap()
-                .parse()
-                .unwrap();
+        /// Like `decode`, but calls `on_key` with each top-level key as it's
+        /// reached, for progress reporting while decoding a large torrent.
+        ///
+        /// The call happens right after the key is decoded and before its
+        /// value is, so most of the time between two calls is spent on the
+        /// value of the first key, such as `info`. Keys are reported in input
+        /// order, including ones decoded before an error in a later value.
+        pub fn decode_with_progress<F: FnMut(&[u8])>(
+            data: &[u8],
+            on_key: F,
+        ) -> Result<Self, BencodingError> {
+            let options = DecodeOptions::default();
+            let mut decoder = Decoder::new(data, &options);
+            let dict = decoder.decode_dict_reporting_keys(on_key)?;
+            decoder.expect_end()?;
+
+            return Ok(Self {
+                dict,
+                non_canonical: decoder.non_canonical,
+            });
+        }
 
-            return (value, &data[ending_idx + 1..]);
+        /// Like `decode`, but also returns the bytes of `data` that make up
+        /// the top-level dictionary, borrowed from `data`, so the original
+        /// encoding can be kept or re-served without reading it again. Use
+        /// `to_vec` on the slice to keep it past the input's lifetime.
+        ///
+        /// As with `decode`, bytes after the dictionary are rejected, so the
+        /// slice is the whole of `data`.
+        pub fn decode_with_input(data: &[u8]) -> Result<(Self, &[u8]), BencodingError> {
+            let options = DecodeOptions::default();
+            let mut decoder = Decoder::new(data, &options);
+            let dict = decoder.decode_dict()?;
+            decoder.expect_end()?;
+            let input = &data[..decoder.position()];
+
+            return Ok((
+                Self {
+                    dict,
+                    non_canonical: decoder.non_canonical,
+                },
+                input,
+            ));
         }
 
-        fn decode_list(mut data: &[u8]) -> (Vec<BencodingValue>, &[u8]) {
-            data = &data[1..];
-            let mut value;
+        /// Decodes the dictionary starting at byte `offset` of `data`, such
+        /// as a bencoded blob embedded in a container file, and returns it
+        /// with the offset right past its end.
+        ///
+        /// Bytes before `offset` and after the dictionary are ignored. Error
+        /// positions, and the offsets reported by `assert_canonical`, are
+        /// relative to the start of `data`. An offset past the end of `data`
+        /// is `Err(UnexpectedEof)`.
+        pub fn decode_at(data: &[u8], offset: usize) -> Result<(Self, usize), BencodingError> {
+            if offset > data.len() {
+                return Err(BencodingError::UnexpectedEof);
+            }
+            let options = DecodeOptions::default();
+            let mut decoder = Decoder::new(data, &options);
+            decoder.seek(offset);
+            let dict = decoder.decode_dict()?;
+
+            return Ok((
+                Self {
+                    dict,
+                    non_canonical: decoder.non_canonical,
+                },
+                decoder.position(),
+            ));
+        }
 
-            let mut list: Vec<BencodingValue> = Vec::new();
-            loop {
-                // 0x65 ('e') indicates end of dictionary
-                if data[0] == 'e' as u8 {
-                    break;
-                }
+        /// Decodes a standalone `info` dictionary, such as the metadata
+        /// fetched from peers for a magnet link (BEP 9), into a structure
+        /// holding it under `info`, so that `name`, `files`, `pieces` and the
+        /// other `info` accessors work on it. The structure has no other
+        /// fields.
+        ///
+        /// Non-canonical encoding is reported by `assert_canonical` with
+        /// offsets into `info`.
+        pub fn from_info_bytes(info: &[u8]) -> Result<Self, BencodingError> {
+            let options = DecodeOptions::default();
+            let mut decoder = Decoder::new(info, &options);
+            let info = decoder.decode_dict()?;
+            decoder.expect_end()?;
+            let mut dict = BencodingDict::new();
+            dict.insert(b"info".to_vec(), BencodingValue::Dict(info));
+
+            return Ok(Self {
+                dict,
+                non_canonical: decoder.non_canonical,
+            });
+        }
 
-                (value, data) = Self::decode_next(data);
-                list.push(value);
+        pub fn get(&self, key: &[u8]) -> Option<BencodingValue> {
+            if !self.dict.contains_key(key) {
+                return None;
             }
 
-            return (list, data);
+            return Some(self.dict[key].clone());
         }
 
-        fn decode_next(data: &[u8]) -> (BencodingValue, &[u8]) {
-            match data[0] as char {
-                'i' => {
-                    let (value, data) = Self::decode_integer(&data);
-                    return (BencodingValue::Integer(value), data);
-                }
-                'l' => {
-                    let (value, data) = Self::decode_list(&data);
-                    return (BencodingValue::List(value), data);
-                }
-                'd' => {
-                    let (value, data) = Self::decode_dict(&data);
-                    return (BencodingValue::Dict(value), data);
-                }
-                _ => {
-                    let (value, data) = Self::decode_string(&data);
-                    return (BencodingValue::String(value), data);
+        /// Iterates over the entries of the top-level dictionary, in
+        /// `BencodingDict` order.
+        pub fn iter(&self) -> impl Iterator<Item = (&[u8], &BencodingValue)> {
+            return self.dict.iter().map(|(key, value)| (key.as_slice(), value));
+        }
+
+        /// Checks that the source was already canonically encoded, that is,
+        /// that re-encoding the decoded structure reproduces it byte for byte.
+        ///
+        /// The decoder accepts unsorted or duplicate dictionary keys and
+        /// integers or string lengths with leading zeros, all of which are
+        /// lost on re-encoding. The first one found in the source is reported
+        /// as `Err(NonCanonical)` with its byte offset.
+        pub fn assert_canonical(&self) -> Result<(), BencodingError> {
+            match self.non_canonical {
+                Some((position, reason)) => {
+                    return Err(BencodingError::NonCanonical { position, reason })
                 }
-            };
+                None => return Ok(()),
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::bencoding_parser::{Bencoding, BencodingValue};
+    use crate::bencoding_parser::{Bencoding, BencodingError, BencodingValue};
 
     #[test]
     fn decode_string_key_hello_value_world() {
@@ -271,6 +590,16 @@ mod tests {
         assert_eq!(number, 42);
     }
 
+    #[test]
+    fn decode_keys_after_nested_dict_and_list() {
+        let parser = Bencoding::decode(b"d1:ad1:bi1ee1:lli2ee1:ci3ee").unwrap();
+        let result = match parser.get(b"c").unwrap() {
+            BencodingValue::Integer(i) => i,
+            _ => panic!(),
+        };
+        assert_eq!(result, 3);
+    }
+
     #[test]
     fn decode_empty_dict_does_not_panic() {
         Bencoding::decode(b"de").unwrap();
@@ -282,4 +611,165 @@ mod tests {
         let result = parser.get(b"fake");
         assert!(result.is_none());
     }
+
+    #[test]
+    fn assert_canonical_accepts_canonical_source() {
+        let data = b"d3:cow3:moo4:spaml1:a1:bee";
+        let parser = Bencoding::decode(data).unwrap();
+        parser.assert_canonical().unwrap();
+        assert_eq!(parser.encode(), data);
+    }
+
+    #[test]
+    fn assert_canonical_rejects_unsorted_keys() {
+        let parser = Bencoding::decode(b"d4:spami1e3:cowi2ee").unwrap();
+        let result = parser.assert_canonical();
+        assert!(matches!(
+            result,
+            Err(BencodingError::NonCanonical { position: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn assert_canonical_rejects_duplicate_keys() {
+        let parser = Bencoding::decode(b"d3:cowi1e3:cowi2ee").unwrap();
+        assert!(parser.assert_canonical().is_err());
+    }
+
+    #[test]
+    fn assert_canonical_rejects_leading_zero_integer() {
+        let parser = Bencoding::decode(b"d3:cowi03ee").unwrap();
+        let result = parser.assert_canonical();
+        assert!(matches!(
+            result,
+            Err(BencodingError::NonCanonical { position: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn assert_canonical_rejects_negative_zero() {
+        let parser = Bencoding::decode(b"d3:cowi-0ee").unwrap();
+        assert!(parser.assert_canonical().is_err());
+    }
+
+    #[test]
+    fn assert_canonical_rejects_leading_zero_string_length() {
+        let parser = Bencoding::decode(b"d3:cow03:mooe").unwrap();
+        let result = parser.assert_canonical();
+        assert!(matches!(
+            result,
+            Err(BencodingError::NonCanonical { position: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn assert_canonical_reports_first_deviation() {
+        let parser = Bencoding::decode(b"d1:bi01e1:ai1ee").unwrap();
+        let result = parser.assert_canonical();
+        assert!(matches!(
+            result,
+            Err(BencodingError::NonCanonical { position: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn decode_with_input_returns_dict_bytes() {
+        let data = b"d3:key5:valuee";
+        let (parser, input) = Bencoding::decode_with_input(data).unwrap();
+
+        assert_eq!(input, data);
+        assert!(parser.get(b"key").is_some());
+    }
+
+    #[test]
+    fn decode_with_input_rejects_trailing_bytes() {
+        let result = Bencoding::decode_with_input(b"d1:ai1eeGARBAGE");
+        assert!(matches!(
+            result,
+            Err(BencodingError::UnexpectedByte {
+                position: 8,
+                byte: b'G'
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_at_offset_within_other_bytes() {
+        let data = b"HEADER\x00\x01d3:key5:value4:listli1eeeFOOTER";
+        let (parser, end) = Bencoding::decode_at(data, 8).unwrap();
+
+        assert_eq!(parser.get_string(b"key"), Some(b"value".as_slice()));
+        assert_eq!(parser.get_integer_list(b"list", false), Some(vec![1]));
+        assert_eq!(end, 33);
+        assert_eq!(&data[end..], b"FOOTER");
+    }
+
+    #[test]
+    fn decode_at_reports_positions_in_whole_input() {
+        let data = b"xxd3:key5:valuei1ee";
+        assert!(matches!(
+            Bencoding::decode_at(data, 2),
+            Err(BencodingError::UnexpectedByte {
+                position: 15,
+                byte: b'i'
+            })
+        ));
+        assert!(matches!(
+            Bencoding::decode_at(data, 0),
+            Err(BencodingError::UnexpectedByte {
+                position: 0,
+                byte: b'x'
+            })
+        ));
+        assert!(matches!(
+            Bencoding::decode_at(data, 100),
+            Err(BencodingError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            Bencoding::decode_at(data, data.len()),
+            Err(BencodingError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn from_info_bytes_wraps_info_dict() {
+        let info = b"d6:lengthi5e4:name5:a.txt12:piece lengthi4e6:pieces0:e";
+        let parser = Bencoding::from_info_bytes(info).unwrap();
+
+        assert_eq!(parser.name(), Some(b"a.txt".as_slice()));
+        assert_eq!(parser.total_length(), Some(5));
+        assert_eq!(parser.encoded_info().unwrap(), info);
+        assert_eq!(parser.iter().count(), 1);
+    }
+
+    #[test]
+    fn from_info_bytes_rejects_non_dict() {
+        assert!(Bencoding::from_info_bytes(b"li1ee").is_err());
+    }
+
+    #[test]
+    fn decode_with_progress_reports_top_level_keys() {
+        let mut keys = Vec::new();
+        let parser =
+            Bencoding::decode_with_progress(b"d8:announce3:url4:infod4:name1:ae1:xi1ee", |key| {
+                keys.push(key.to_vec())
+            })
+            .unwrap();
+
+        assert_eq!(
+            keys,
+            vec![b"announce".to_vec(), b"info".to_vec(), b"x".to_vec()]
+        );
+        assert!(parser.info_dict().is_some());
+    }
+
+    #[test]
+    fn decode_with_progress_reports_keys_before_error() {
+        let mut keys = Vec::new();
+        let result =
+            Bencoding::decode_with_progress(b"d1:ai1e1:bi1", |key| keys.push(key.to_vec()));
+
+        assert!(result.is_err());
+        assert_eq!(keys, vec![b"a".to_vec(), b"b".to_vec()]);
+    }
 }